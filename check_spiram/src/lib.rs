@@ -0,0 +1,36 @@
+//! Memory report of the ESP32: size and free space of the internal DRAM and the external SPIRAM.
+//! Used by the `check_spiram` binary and by other firmware that wants to log it at boot.
+use esp_idf_sys::{
+    heap_caps_get_free_size, heap_caps_get_total_size, MALLOC_CAP_DMA, MALLOC_CAP_INTERNAL,
+    MALLOC_CAP_SPIRAM,
+};
+
+mod report;
+
+pub use report::MemoryReport;
+
+/// Queries the ESP-IDF heap capabilities for the current memory situation.
+pub fn memory_report() -> MemoryReport {
+    // MALLOC_CAP_INTERNAL | MALLOC_CAP_DMA represents the main internal memory
+    // capable of DMA operations (where stack and data usually live).
+    let internal_caps = MALLOC_CAP_INTERNAL | MALLOC_CAP_DMA;
+
+    // We use 'unsafe' here because we are calling C functions from the ESP-IDF framework directly.
+    // They only read allocator statistics.
+    unsafe {
+        MemoryReport {
+            internal_total: heap_caps_get_total_size(internal_caps),
+            internal_free: heap_caps_get_free_size(internal_caps),
+            // MALLOC_CAP_SPIRAM refers specifically to the external SPI-connected RAM.
+            spiram_total: heap_caps_get_total_size(MALLOC_CAP_SPIRAM),
+            spiram_free: heap_caps_get_free_size(MALLOC_CAP_SPIRAM),
+        }
+    }
+}
+
+/// Returns an error if less than `min_bytes` of external SPIRAM are available.
+/// Meant to be called at boot, so that a board without (or with disabled) PSRAM fails
+/// loudly instead of running out of memory in the middle of rendering.
+pub fn assert_spiram_present(min_bytes: usize) -> anyhow::Result<()> {
+    memory_report().ensure_spiram(min_bytes)
+}
@@ -0,0 +1,123 @@
+//! Formatting of the memory report, kept free of ESP-IDF calls so it can be unit tested.
+use core::fmt;
+
+/// Total and free heap of the internal DRAM and the external SPIRAM, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryReport {
+    /// Total internal DRAM (DMA capable, where stack and data usually live).
+    pub internal_total: usize,
+    /// Currently free internal DRAM.
+    pub internal_free: usize,
+    /// Total external SPIRAM (PSRAM), 0 if none is present.
+    pub spiram_total: usize,
+    /// Currently free external SPIRAM.
+    pub spiram_free: usize,
+}
+
+impl MemoryReport {
+    /// Returns true if external SPIRAM was found.
+    pub fn has_spiram(&self) -> bool {
+        self.spiram_total > 0
+    }
+
+    /// Returns an error if less than `min_bytes` of SPIRAM are available in total.
+    pub fn ensure_spiram(&self, min_bytes: usize) -> anyhow::Result<()> {
+        if self.spiram_total < min_bytes {
+            anyhow::bail!(
+                "Only {} Bytes of SPIRAM found, at least {} required (is CONFIG_SPIRAM enabled in sdkconfig?)",
+                self.spiram_total,
+                min_bytes
+            );
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- ESP32 Memory Analysis ---")?;
+
+        // Internal memory in KB
+        writeln!(
+            f,
+            "🏭 Internal DRAM (Data/Stack): {} Bytes ({:.2} KB), {} Bytes free",
+            self.internal_total,
+            self.internal_total as f32 / 1024.0,
+            self.internal_free
+        )?;
+
+        // External memory in MB
+        writeln!(
+            f,
+            "💾 External SPIRAM (PSRAM):    {} Bytes ({:.2} MB), {} Bytes free",
+            self.spiram_total,
+            self.spiram_total as f32 / 1024.0 / 1024.0,
+            self.spiram_free
+        )?;
+
+        writeln!(f, "---------------------------------")?;
+
+        // Logic check to see if SPIRAM is actually active
+        if self.has_spiram() {
+            write!(f, "✅ Result: **SPIRAM is PRESENT** and available.")
+        } else {
+            writeln!(f, "❌ Result: **No SPIRAM (PSRAM) found**.")?;
+            write!(
+                f,
+                "   (If you expected SPIRAM, check your sdkconfig or board type)"
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_with_spiram() {
+        let report = MemoryReport {
+            internal_total: 295_000,
+            internal_free: 200_000,
+            spiram_total: 4_194_304,
+            spiram_free: 4_000_000,
+        };
+        let text = report.to_string();
+
+        assert!(text.contains("295000 Bytes (288.09 KB), 200000 Bytes free"));
+        assert!(text.contains("4194304 Bytes (4.00 MB), 4000000 Bytes free"));
+        assert!(text.ends_with("✅ Result: **SPIRAM is PRESENT** and available."));
+    }
+
+    #[test]
+    fn test_report_without_spiram() {
+        let report = MemoryReport {
+            internal_total: 295_000,
+            internal_free: 200_000,
+            spiram_total: 0,
+            spiram_free: 0,
+        };
+
+        assert!(!report.has_spiram());
+        assert!(report
+            .to_string()
+            .contains("❌ Result: **No SPIRAM (PSRAM) found**."));
+    }
+
+    #[test]
+    fn test_ensure_spiram() {
+        let mut report = MemoryReport {
+            internal_total: 295_000,
+            internal_free: 200_000,
+            spiram_total: 4_194_304,
+            spiram_free: 4_000_000,
+        };
+        assert!(report.ensure_spiram(0).is_ok());
+        assert!(report.ensure_spiram(4_194_304).is_ok());
+        assert!(report.ensure_spiram(8_388_608).is_err());
+
+        report.spiram_total = 0;
+        assert!(report.ensure_spiram(0).is_ok());
+        assert!(report.ensure_spiram(1).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! An off-screen copy of the display, kept free of ESP-IDF calls so it can be unit tested.
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*, primitives::Rectangle};
+
+/// A full-screen Rgb565 buffer that is drawn into instead of the panel
+///
+/// Drawing only changes the buffer and records the bounding box of the pixels whose color
+/// actually changed. `blit` then sends that box to the panel in one contiguous transfer, so
+/// overlapping text and cleared areas never show up half drawn. At 240x320 the buffer takes
+/// 150 KB, with `CONFIG_SPIRAM_USE_MALLOC` such a large allocation is placed in PSRAM.
+pub struct FrameBuffer {
+    size: Size,
+    pixels: Vec<Rgb565>,
+    /// Top-left and bottom-right corner of the changed pixels, `None` if the panel is up to date
+    dirty: Option<(Point, Point)>,
+}
+
+impl FrameBuffer {
+    /// Bytes needed for a buffer of `size`
+    pub fn byte_len(size: Size) -> usize {
+        size.width as usize * size.height as usize * 2
+    }
+
+    /// Allocate a buffer of `size` filled with `color`
+    /// The panel content is unknown, so the first `blit` sends the whole buffer.
+    ///
+    /// # Returns
+    /// * `Ok(FrameBuffer)` - The buffer
+    /// * `Err` - Not enough memory for the buffer
+    pub fn new(size: Size, color: Rgb565) -> anyhow::Result<Self> {
+        let len = size.width as usize * size.height as usize;
+        let mut pixels = Vec::new();
+        pixels.try_reserve_exact(len).map_err(|_| {
+            anyhow::anyhow!(
+                "Not enough memory for a {}x{} frame buffer ({} bytes)",
+                size.width,
+                size.height,
+                Self::byte_len(size)
+            )
+        })?;
+        pixels.resize(len, color);
+
+        let mut frame_buffer = Self {
+            size,
+            pixels,
+            dirty: None,
+        };
+        frame_buffer.invalidate();
+        Ok(frame_buffer)
+    }
+
+    /// Mark the whole buffer as changed, e.g. after something was drawn on the panel directly
+    pub fn invalidate(&mut self) {
+        self.dirty = Rectangle::new(Point::zero(), self.size)
+            .bottom_right()
+            .map(|bottom_right| (Point::zero(), bottom_right));
+    }
+
+    /// The area that differs from the panel, `None` if there is nothing to send
+    pub fn dirty_area(&self) -> Option<Rectangle> {
+        self.dirty
+            .map(|(top_left, bottom_right)| Rectangle::with_corners(top_left, bottom_right))
+    }
+
+    /// Send the changed area to `display` and mark the buffer as up to date
+    /// The area is written as one window, the display must have the size of the buffer.
+    pub fn blit<D: DrawTarget<Color = Rgb565>>(&mut self, display: &mut D) -> Result<(), D::Error> {
+        let Some(area) = self.dirty_area() else {
+            return Ok(());
+        };
+        let width = self.size.width as usize;
+        let pixels = &self.pixels;
+        display.fill_contiguous(
+            &area,
+            area.points()
+                .map(|point| pixels[point.y as usize * width + point.x as usize]),
+        )?;
+        self.dirty = None;
+        Ok(())
+    }
+
+    /// Extend the changed area by `point`
+    fn mark_dirty(&mut self, point: Point) {
+        self.dirty = Some(match self.dirty {
+            Some((top_left, bottom_right)) => (
+                top_left.component_min(point),
+                bottom_right.component_max(point),
+            ),
+            None => (point, point),
+        });
+    }
+}
+
+impl OriginDimensions for FrameBuffer {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for FrameBuffer {
+    type Color = Rgb565;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (width, height) = (self.size.width as i32, self.size.height as i32);
+        for Pixel(point, color) in pixels {
+            if point.x < 0 || point.y < 0 || point.x >= width || point.y >= height {
+                continue;
+            }
+            // Redrawing a pixel in the same color does not need a transfer
+            let index = (point.y * width + point.x) as usize;
+            if self.pixels[index] != color {
+                self.pixels[index] = color;
+                self.mark_dirty(point);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::primitives::PrimitiveStyle;
+
+    fn fill(frame_buffer: &mut FrameBuffer, area: Rectangle, color: Rgb565) {
+        area.into_styled(PrimitiveStyle::with_fill(color))
+            .draw(frame_buffer)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_dirty_area() {
+        let mut frame_buffer = FrameBuffer::new(Size::new(16, 16), Rgb565::BLACK).unwrap();
+        assert_eq!(
+            frame_buffer.dirty_area(),
+            Some(Rectangle::new(Point::zero(), Size::new(16, 16)))
+        );
+        frame_buffer.dirty = None;
+
+        // Drawing the same color changes nothing
+        fill(
+            &mut frame_buffer,
+            Rectangle::new(Point::zero(), Size::new(16, 16)),
+            Rgb565::BLACK,
+        );
+        assert_eq!(frame_buffer.dirty_area(), None);
+
+        // The box grows with every change, pixels outside the buffer are dropped
+        fill(
+            &mut frame_buffer,
+            Rectangle::new(Point::new(2, 3), Size::new(2, 2)),
+            Rgb565::WHITE,
+        );
+        fill(
+            &mut frame_buffer,
+            Rectangle::new(Point::new(8, 1), Size::new(20, 1)),
+            Rgb565::RED,
+        );
+        assert_eq!(
+            frame_buffer.dirty_area(),
+            Some(Rectangle::with_corners(Point::new(2, 1), Point::new(15, 4)))
+        );
+    }
+
+    #[test]
+    fn test_blit() {
+        let mut frame_buffer = FrameBuffer::new(Size::new(8, 8), Rgb565::BLACK).unwrap();
+        let mut display = MockDisplay::new();
+        frame_buffer.blit(&mut display).unwrap();
+        assert_eq!(
+            display.affected_area(),
+            Rectangle::new(Point::zero(), Size::new(8, 8))
+        );
+
+        // Only the changed box is sent, a second blit sends nothing
+        fill(
+            &mut frame_buffer,
+            Rectangle::new(Point::new(2, 2), Size::new(3, 2)),
+            Rgb565::WHITE,
+        );
+        let mut display = MockDisplay::new();
+        frame_buffer.blit(&mut display).unwrap();
+        display.assert_pattern(&["     ", "     ", "  WWW", "  WWW"]);
+        frame_buffer.blit(&mut display).unwrap();
+        assert_eq!(frame_buffer.dirty_area(), None);
+    }
+
+    #[test]
+    fn test_byte_len() {
+        assert_eq!(FrameBuffer::byte_len(Size::new(240, 320)), 153_600);
+    }
+}
@@ -0,0 +1,692 @@
+// ===============================================================================
+// Shared ST7789 display setup for the display apps
+// ===============================================================================
+// esp-idf-hal has its own SPI/GPIO API while mipidsi expects the embedded-hal 1.0
+// traits. The wrappers below bridge the two, `build_display` wires everything up.
+// `register_panic_display` shows panic messages on the display before the reboot.
+// `FrameBuffer` draws off-screen and sends only the changed area to the panel.
+// ===============================================================================
+
+use core::ops::RangeInclusive;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*, primitives::Rectangle};
+use embedded_hal::digital::OutputPin as OutputPinTrait;
+use embedded_hal::spi::SpiDevice;
+use esp_idf_hal::{
+    delay::FreeRtos,
+    gpio::{self, AnyIOPin, AnyOutputPin, Output, OutputPin, PinDriver, Pins},
+    ledc::{config::TimerConfig, LedcChannel, LedcDriver, LedcTimer, LedcTimerDriver},
+    peripheral::Peripheral,
+    prelude::*,
+    spi::{config::Config, SpiAnyPins, SpiDeviceDriver, SpiDriver, SpiDriverConfig},
+};
+use mipidsi::{
+    interface::SpiInterface,
+    models::ST7789,
+    options::{ColorInversion, ColorOrder, Orientation},
+    Builder, NoResetPin,
+};
+use std::sync::{Mutex, PoisonError};
+use std::thread::{self, ThreadId};
+
+mod frame_buffer;
+
+pub use frame_buffer::FrameBuffer;
+
+/// Size of the ST7789 frame memory, the largest supported panel
+const DISPLAY_WIDTH: u16 = 240;
+const DISPLAY_HEIGHT: u16 = 320;
+
+/// Size of the mipidsi batch buffer: 10 lines of RGB565 pixels
+const DISPLAY_BUFFER_LEN: usize = DISPLAY_WIDTH as usize * 10 * 2;
+
+/// Default SPI clock, works with the usual short jumper wires
+pub const DEFAULT_SPI_MHZ: u32 = 26;
+
+/// SPI clocks the ESP32 SPI master can drive, higher or lower values are clamped
+pub const SPI_MHZ_RANGE: RangeInclusive<u32> = 1..=80;
+
+/// Set once the display buffer has been handed out, so that it is only leaked once
+static DISPLAY_TAKEN: AtomicBool = AtomicBool::new(false);
+
+// ===============================================================================
+// EMBEDDED-HAL WRAPPERS
+// ===============================================================================
+
+/// Custom error type for SPI and GPIO operations.
+/// This is a workaround to adapt the `esp_idf_hal` error types to the `embedded-hal` traits.
+#[derive(Debug)]
+pub struct CustomError;
+
+impl embedded_hal::spi::Error for CustomError {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+
+impl embedded_hal::digital::Error for CustomError {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+/// Wrapper for ESP-IDF SPI driver to make it compatible with `embedded-hal` traits.
+pub struct SpiWrapper<'d> {
+    spi: SpiDeviceDriver<'d, SpiDriver<'d>>,
+}
+
+impl embedded_hal::spi::ErrorType for SpiWrapper<'_> {
+    type Error = CustomError;
+}
+
+impl SpiDevice for SpiWrapper<'_> {
+    fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        // mipidsi issues zero-length operations, e.g. for commands without parameters.
+        // The ESP-IDF SPI master rejects those in its transaction length check and the
+        // resulting error aborts the display init, so empty operations are skipped.
+        for op in operations {
+            match op {
+                embedded_hal::spi::Operation::Write(data) => {
+                    if !data.is_empty() {
+                        self.spi.write(data).map_err(|_| CustomError)?;
+                    }
+                }
+                embedded_hal::spi::Operation::Transfer(read, write) => {
+                    if !read.is_empty() || !write.is_empty() {
+                        self.spi.transfer(read, write).map_err(|_| CustomError)?;
+                    }
+                }
+                embedded_hal::spi::Operation::TransferInPlace(data) => {
+                    // Full duplex: every byte of `data` is sent and replaced by the byte
+                    // received in the same clock cycle, so no copy of the input is needed
+                    if !data.is_empty() {
+                        self.spi.transfer_in_place(data).map_err(|_| CustomError)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wrapper for the Data/Command (DC) pin to make it compatible with `embedded-hal` traits.
+pub struct DcPinWrapper<'d> {
+    pin: PinDriver<'d, AnyOutputPin, Output>,
+}
+
+impl embedded_hal::digital::ErrorType for DcPinWrapper<'_> {
+    type Error = CustomError;
+}
+
+impl OutputPinTrait for DcPinWrapper<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low().map_err(|_| CustomError)
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high().map_err(|_| CustomError)
+    }
+}
+
+// ===============================================================================
+// DISPLAY SETUP
+// ===============================================================================
+
+/// The mipidsi driver type produced by `build_display`
+pub type Display<'d> =
+    mipidsi::Display<SpiInterface<'static, SpiWrapper<'d>, DcPinWrapper<'d>>, ST7789, NoResetPin>;
+
+/// GPIO pins the display is wired to
+pub struct DisplayPins {
+    /// SPI clock (SCL on the display)
+    pub sclk: AnyOutputPin,
+    /// SPI data out (SDA on the display)
+    pub mosi: AnyOutputPin,
+    /// Chip select
+    pub cs: AnyOutputPin,
+    /// Data/Command select
+    pub dc: AnyOutputPin,
+    /// Hardware reset
+    pub rst: AnyOutputPin,
+}
+
+impl DisplayPins {
+    /// Take the display pins from `gpios` by their GPIO numbers
+    ///
+    /// # Returns
+    /// * `Ok(DisplayPins)` - The pins, ready for `build_display`
+    /// * `Err` - A number is not an output-capable GPIO or used twice
+    pub fn take(gpios: &mut GpioPool, numbers: DisplayPinNumbers) -> anyhow::Result<Self> {
+        Ok(Self {
+            sclk: gpios.take_output(numbers.sclk)?,
+            mosi: gpios.take_output(numbers.mosi)?,
+            cs: gpios.take_output(numbers.cs)?,
+            dc: gpios.take_output(numbers.dc)?,
+            rst: gpios.take_output(numbers.rst)?,
+        })
+    }
+}
+
+/// GPIO numbers of the display pins, e.g. from the `[display.pins]` configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayPinNumbers {
+    pub sclk: u8,
+    pub mosi: u8,
+    pub cs: u8,
+    pub dc: u8,
+    pub rst: u8,
+}
+
+impl Default for DisplayPinNumbers {
+    /// The wiring described in the READMEs
+    fn default() -> Self {
+        Self {
+            sclk: 18,
+            mosi: 23,
+            cs: 15,
+            dc: 21,
+            rst: 22,
+        }
+    }
+}
+
+// ===============================================================================
+// GPIO BY NUMBER
+// ===============================================================================
+
+/// The GPIOs of the chip, handed out by number
+/// esp-idf-hal has one type per pin, so a pin number from the configuration can't
+/// index `Peripherals::pins` directly. The pool owns all pins and gives each out once.
+pub struct GpioPool {
+    /// Proves ownership of all GPIOs, the pins are recreated by number in `take_output`
+    _pins: Pins,
+    /// Bit n is set once GPIO n has been taken
+    taken: u64,
+}
+
+impl GpioPool {
+    pub fn new(pins: Pins) -> Self {
+        Self {
+            _pins: pins,
+            taken: 0,
+        }
+    }
+
+    /// Take GPIO `number` as an output pin
+    ///
+    /// # Returns
+    /// * `Ok(AnyOutputPin)` - The pin
+    /// * `Err` - The pin has already been taken, or it can't drive an output
+    pub fn take_output(&mut self, number: u8) -> anyhow::Result<AnyOutputPin> {
+        if !is_output_pin(number) {
+            anyhow::bail!("GPIO{} can't be used as an output pin", number);
+        }
+        if self.taken & (1 << number) != 0 {
+            anyhow::bail!("GPIO{} is assigned twice", number);
+        }
+        self.taken |= 1 << number;
+
+        // SAFETY: the pool owns all pins and the bit mask above hands each out only once
+        let pin = unsafe {
+            match number {
+                0 => gpio::Gpio0::new().downgrade_output(),
+                2 => gpio::Gpio2::new().downgrade_output(),
+                4 => gpio::Gpio4::new().downgrade_output(),
+                5 => gpio::Gpio5::new().downgrade_output(),
+                12 => gpio::Gpio12::new().downgrade_output(),
+                13 => gpio::Gpio13::new().downgrade_output(),
+                14 => gpio::Gpio14::new().downgrade_output(),
+                15 => gpio::Gpio15::new().downgrade_output(),
+                16 => gpio::Gpio16::new().downgrade_output(),
+                17 => gpio::Gpio17::new().downgrade_output(),
+                18 => gpio::Gpio18::new().downgrade_output(),
+                19 => gpio::Gpio19::new().downgrade_output(),
+                21 => gpio::Gpio21::new().downgrade_output(),
+                22 => gpio::Gpio22::new().downgrade_output(),
+                23 => gpio::Gpio23::new().downgrade_output(),
+                25 => gpio::Gpio25::new().downgrade_output(),
+                26 => gpio::Gpio26::new().downgrade_output(),
+                27 => gpio::Gpio27::new().downgrade_output(),
+                32 => gpio::Gpio32::new().downgrade_output(),
+                33 => gpio::Gpio33::new().downgrade_output(),
+                _ => unreachable!("checked by is_output_pin"),
+            }
+        };
+        Ok(pin)
+    }
+}
+
+/// Whether GPIO `number` of the ESP32 can drive a display or backlight pin
+/// Excluded are the UART0 pins of the console (1, 3), the SPI flash pins (6-11),
+/// the input-only pins (34-39) and the numbers without a pin.
+pub fn is_output_pin(number: u8) -> bool {
+    matches!(number, 0 | 2 | 4 | 5 | 12..=19 | 21..=23 | 25..=27 | 32 | 33)
+}
+
+/// Visible area of an ST7789 panel in the default (portrait) orientation
+/// Panels smaller than the 240x320 frame memory only show a window of it, starting at the offset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PanelConfig {
+    /// Width in pixels
+    pub width: u16,
+    /// Height in pixels
+    pub height: u16,
+    /// First visible column of the frame memory
+    pub offset_x: u16,
+    /// First visible row of the frame memory
+    pub offset_y: u16,
+    /// SPI clock in MHz, lower it if the screen shows noise
+    pub spi_mhz: u32,
+}
+
+impl PanelConfig {
+    /// The 240x320 panel, it covers the whole frame memory
+    pub const ST7789_240X320: Self = Self {
+        width: DISPLAY_WIDTH,
+        height: DISPLAY_HEIGHT,
+        offset_x: 0,
+        offset_y: 0,
+        spi_mhz: DEFAULT_SPI_MHZ,
+    };
+
+    /// The 135x240 panel of TTGO-style boards, centered in the frame memory
+    pub const ST7789_135X240: Self = Self {
+        width: 135,
+        height: 240,
+        offset_x: 52,
+        offset_y: 40,
+        spi_mhz: DEFAULT_SPI_MHZ,
+    };
+
+    /// Whether the visible area lies within the 240x320 frame memory
+    pub fn fits_frame_memory(&self) -> bool {
+        self.width > 0
+            && self.height > 0
+            && self.width as u32 + self.offset_x as u32 <= DISPLAY_WIDTH as u32
+            && self.height as u32 + self.offset_y as u32 <= DISPLAY_HEIGHT as u32
+    }
+
+    /// The SPI clock limited to `SPI_MHZ_RANGE`
+    pub fn clamped_spi_mhz(&self) -> u32 {
+        self.spi_mhz
+            .clamp(*SPI_MHZ_RANGE.start(), *SPI_MHZ_RANGE.end())
+    }
+}
+
+impl Default for PanelConfig {
+    fn default() -> Self {
+        Self::ST7789_240X320
+    }
+}
+
+/// An initialized ST7789 display
+/// Draws like the underlying mipidsi display and keeps the reset pin driven high.
+pub struct St7789Display<'d> {
+    display: Display<'d>,
+    /// Dropping the driver would release the pin and could reset the panel
+    _rst: PinDriver<'d, AnyOutputPin, Output>,
+}
+
+/// Reset and initialize the ST7789 display (240x320, 26 MHz SPI, inverted colors)
+/// Can only be called once, the driver uses a static batch buffer.
+///
+/// # Arguments
+/// * `spi` - SPI peripheral, e.g. `peripherals.spi2`
+/// * `pins` - Pins the display is connected to
+/// * `orientation` - Rotation/mirroring of the display
+///
+/// # Returns
+/// * `Ok(St7789Display)` - The initialized display
+/// * `Err` - GPIO, SPI or display initialization error
+pub fn build_display<'d, SPI: SpiAnyPins>(
+    spi: impl Peripheral<P = SPI> + 'd,
+    pins: DisplayPins,
+    orientation: Orientation,
+) -> anyhow::Result<St7789Display<'d>> {
+    build_display_for_panel(spi, pins, orientation, PanelConfig::default())
+}
+
+/// Like `build_display`, for panels of other sizes, e.g. `PanelConfig::ST7789_135X240`
+/// The SPI clock of the panel is clamped to `SPI_MHZ_RANGE`.
+///
+/// # Returns
+/// * `Ok(St7789Display)` - The initialized display, its size follows the panel and orientation
+/// * `Err` - The panel does not fit the frame memory, or a GPIO, SPI or display initialization error
+pub fn build_display_for_panel<'d, SPI: SpiAnyPins>(
+    spi: impl Peripheral<P = SPI> + 'd,
+    pins: DisplayPins,
+    orientation: Orientation,
+    panel: PanelConfig,
+) -> anyhow::Result<St7789Display<'d>> {
+    if !panel.fits_frame_memory() {
+        anyhow::bail!(
+            "Panel {}x{} at offset {},{} exceeds the {}x{} frame memory",
+            panel.width,
+            panel.height,
+            panel.offset_x,
+            panel.offset_y,
+            DISPLAY_WIDTH,
+            DISPLAY_HEIGHT
+        );
+    }
+    if DISPLAY_TAKEN.swap(true, Ordering::SeqCst) {
+        anyhow::bail!("Display already initialized");
+    }
+
+    // Perform a hardware reset on the display
+    let mut rst = PinDriver::output(pins.rst)?;
+    rst.set_low()?;
+    FreeRtos::delay_ms(50);
+    rst.set_high()?;
+    FreeRtos::delay_ms(200);
+
+    // Configure the SPI bus, the display never sends data back (no MISO)
+    let spi_config = Config::new().baudrate(panel.clamped_spi_mhz().MHz().into());
+    let spi_driver = SpiDriver::new(
+        spi,
+        pins.sclk,
+        pins.mosi,
+        None::<AnyIOPin>,
+        &SpiDriverConfig::new(),
+    )?;
+    let spi_device = SpiDeviceDriver::new(spi_driver, Some(pins.cs), &spi_config)?;
+    let spi_wrapper = SpiWrapper { spi: spi_device };
+    let dc_wrapper = DcPinWrapper {
+        pin: PinDriver::output(pins.dc)?,
+    };
+
+    // On the heap so that the buffer is not on the stack (stack overflow prevention).
+    // The display lives until the reboot, leaking gives the 'static borrow it needs.
+    let buffer: &'static mut [u8] = Box::leak(vec![0u8; DISPLAY_BUFFER_LEN].into_boxed_slice());
+    let di = SpiInterface::new(spi_wrapper, dc_wrapper, buffer);
+
+    let display = Builder::new(ST7789, di)
+        .display_size(panel.width, panel.height)
+        // Smaller panels show a window of the frame memory, mipidsi adjusts the
+        // offset to the orientation
+        .display_offset(panel.offset_x, panel.offset_y)
+        .orientation(orientation)
+        .color_order(ColorOrder::Rgb)
+        .invert_colors(ColorInversion::Inverted)
+        .init(&mut FreeRtos)
+        .map_err(|e| anyhow::anyhow!("Display initialization failed: {:?}", e))?;
+
+    Ok(St7789Display { display, _rst: rst })
+}
+
+impl OriginDimensions for St7789Display<'_> {
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+impl<'d> DrawTarget for St7789Display<'d> {
+    type Color = Rgb565;
+    type Error = <Display<'d> as DrawTarget>::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.display.draw_iter(pixels)
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.display.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.display.fill_solid(area, color)
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.display.clear(color)
+    }
+}
+
+// ===============================================================================
+// BACKLIGHT
+// ===============================================================================
+
+/// PWM frequency of the backlight, high enough to not flicker on camera
+const BACKLIGHT_PWM_HZ: u32 = 5_000;
+
+/// Backlight brightness via an LEDC PWM channel on the panel's BL pin
+pub struct Backlight<'d> {
+    pwm: LedcDriver<'d>,
+}
+
+impl<'d> Backlight<'d> {
+    /// Set up the PWM on `pin`, the backlight starts at full brightness
+    ///
+    /// # Arguments
+    /// * `timer` - LEDC timer, e.g. `peripherals.ledc.timer0`
+    /// * `channel` - LEDC channel, e.g. `peripherals.ledc.channel0`
+    /// * `pin` - The pin the BL input of the panel is wired to
+    pub fn new<T: LedcTimer + 'd, C: LedcChannel<SpeedMode = T::SpeedMode>>(
+        timer: impl Peripheral<P = T> + 'd,
+        channel: impl Peripheral<P = C> + 'd,
+        pin: AnyOutputPin,
+    ) -> anyhow::Result<Self> {
+        let timer_config = TimerConfig::new().frequency(BACKLIGHT_PWM_HZ.Hz().into());
+        let timer = LedcTimerDriver::new(timer, &timer_config)?;
+        let mut backlight = Self {
+            pwm: LedcDriver::new(channel, timer, pin)?,
+        };
+        backlight.set_backlight(100)?;
+        Ok(backlight)
+    }
+
+    /// Set the brightness in percent, values above 100 are treated as 100
+    pub fn set_backlight(&mut self, percent: u8) -> anyhow::Result<()> {
+        let duty = backlight_duty(percent, self.pwm.get_max_duty());
+        self.pwm.set_duty(duty)?;
+        Ok(())
+    }
+}
+
+/// PWM duty cycle for `percent` brightness, out of `max_duty`
+pub fn backlight_duty(percent: u8, max_duty: u32) -> u32 {
+    (max_duty as u64 * percent.min(100) as u64 / 100) as u32
+}
+
+// ===============================================================================
+// PANIC SCREEN
+// ===============================================================================
+
+/// Display the panic hook draws on, null while none is registered
+static PANIC_DISPLAY: AtomicPtr<St7789Display<'static>> = AtomicPtr::new(ptr::null_mut());
+
+/// Thread that registered the display, the only one allowed to draw on it
+static PANIC_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+/// Set once the panic hook has been installed
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Keeps the display registered for the panic screen, unregisters it when dropped
+pub struct PanicScreenGuard {
+    _private: (),
+}
+
+impl Drop for PanicScreenGuard {
+    fn drop(&mut self) {
+        PANIC_DISPLAY.store(ptr::null_mut(), Ordering::SeqCst);
+    }
+}
+
+/// Show panics on the display: the message and location are drawn in red after the
+/// default hook has logged them, then the device aborts and reboots as before.
+///
+/// Only panics on the calling thread are drawn. Another thread could panic while this one
+/// is in the middle of a display update, so panics elsewhere are only logged. A panic during
+/// a display update on this thread can garble the screen, but the text still ends up on it.
+///
+/// # Safety
+/// `display` must stay at its address and must not be dropped while the returned guard
+/// is alive. Declaring the guard after the display in the same scope ensures this,
+/// since locals are dropped in reverse order.
+pub unsafe fn register_panic_display(display: &mut St7789Display<'static>) -> PanicScreenGuard {
+    *PANIC_THREAD.lock().unwrap_or_else(PoisonError::into_inner) = Some(thread::current().id());
+    PANIC_DISPLAY.store(display, Ordering::SeqCst);
+
+    if !PANIC_HOOK_INSTALLED.swap(true, Ordering::SeqCst) {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+
+            // Never wait for the lock in a panic, a failed try_lock only skips the screen
+            let owner = PANIC_THREAD.try_lock().ok().and_then(|owner| *owner);
+            if owner != Some(thread::current().id()) {
+                return;
+            }
+
+            // Taking the pointer also keeps a panic while drawing from drawing again
+            let display = PANIC_DISPLAY.swap(ptr::null_mut(), Ordering::SeqCst);
+            if display.is_null() {
+                return;
+            }
+            // SAFETY: the pointer is valid while registered (see above). The registering thread
+            // is the one panicking, so no other thread can be using the display right now.
+            let display = unsafe { &mut *display };
+
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("(no message)");
+            let location = info
+                .location()
+                .map(|location| format!("at {}:{}", location.file(), location.line()))
+                .unwrap_or_default();
+            draw_panic_screen(display, message, &location);
+        }));
+    }
+
+    PanicScreenGuard { _private: () }
+}
+
+/// Height of a text line on the panic screen (FONT_6X10)
+const PANIC_LINE_HEIGHT: i32 = 10;
+
+/// Draw "PANIC", the wrapped message and its location in red on black
+fn draw_panic_screen(display: &mut impl DrawTarget<Color = Rgb565>, message: &str, location: &str) {
+    use embedded_graphics::mono_font::{ascii::FONT_6X10, MonoTextStyle};
+    use embedded_graphics::text::{Baseline, Text};
+
+    let size = display.bounding_box().size;
+    let max_chars = (size.width / 6).max(1) as usize;
+    // Title, blank line and location take three lines
+    let max_lines = (size.height as i32 / PANIC_LINE_HEIGHT - 3).max(1) as usize;
+
+    let _ = display.clear(Rgb565::BLACK);
+    let style = MonoTextStyle::new(&FONT_6X10, Rgb565::RED);
+
+    let mut lines = vec!["PANIC".to_string(), String::new()];
+    lines.extend(wrap_panic_text(message, max_chars, max_lines - 1));
+    lines.extend(wrap_panic_text(location, max_chars, 1));
+
+    for (i, line) in lines.iter().enumerate() {
+        let position = Point::new(0, i as i32 * PANIC_LINE_HEIGHT);
+        let _ = Text::with_baseline(line, position, style, Baseline::Top).draw(display);
+    }
+}
+
+/// Splits `text` into lines of at most `max_chars` characters, breaking at newlines and
+/// else mid-word (panic messages are often paths or debug output without spaces).
+/// Text beyond `max_lines` lines is cut off, the last line then ends with "...".
+fn wrap_panic_text(text: &str, max_chars: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = text
+        .lines()
+        .flat_map(|line| {
+            let chars: Vec<char> = line.chars().collect();
+            chars
+                .chunks(max_chars)
+                .map(|chunk| chunk.iter().collect::<String>())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            let keep = max_chars.saturating_sub(3);
+            *last = last.chars().take(keep).collect::<String>() + "...";
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_panic_text() {
+        assert_eq!(
+            wrap_panic_text("called `Option::unwrap()` on a `None` value", 20, 5),
+            vec!["called `Option::unwr", "ap()` on a `None` va", "lue"]
+        );
+        assert_eq!(
+            wrap_panic_text("first\nsecond", 20, 5),
+            vec!["first", "second"]
+        );
+        assert_eq!(wrap_panic_text("", 20, 5), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_is_output_pin() {
+        let numbers = DisplayPinNumbers::default();
+        for pin in [
+            numbers.sclk,
+            numbers.mosi,
+            numbers.cs,
+            numbers.dc,
+            numbers.rst,
+        ] {
+            assert!(is_output_pin(pin));
+        }
+        assert!(is_output_pin(0));
+        assert!(is_output_pin(33));
+        assert!(!is_output_pin(1));
+        assert!(!is_output_pin(6));
+        assert!(!is_output_pin(20));
+        assert!(!is_output_pin(34));
+        assert!(!is_output_pin(64));
+    }
+
+    #[test]
+    fn test_backlight_duty() {
+        assert_eq!(backlight_duty(100, 8191), 8191);
+        assert_eq!(backlight_duty(20, 8191), 1638);
+        assert_eq!(backlight_duty(0, 8191), 0);
+        assert_eq!(backlight_duty(150, 8191), 8191);
+    }
+
+    #[test]
+    fn test_clamped_spi_mhz() {
+        let panel = |spi_mhz| PanelConfig {
+            spi_mhz,
+            ..PanelConfig::default()
+        };
+        assert_eq!(PanelConfig::default().clamped_spi_mhz(), DEFAULT_SPI_MHZ);
+        assert_eq!(panel(40).clamped_spi_mhz(), 40);
+        assert_eq!(panel(0).clamped_spi_mhz(), 1);
+        assert_eq!(panel(120).clamped_spi_mhz(), 80);
+    }
+
+    #[test]
+    fn test_wrap_panic_text_cut_off() {
+        assert_eq!(
+            wrap_panic_text("abcdefghijklmnopqrstuvwxyz", 10, 2),
+            vec!["abcdefghij", "klmnopq..."]
+        );
+    }
+}
@@ -10,6 +10,14 @@ def save_icon(icon_name, draw_function):
     img.save(f"{icon_name}.png")
     img.show()
 
+def save_rgb_icon(icon_name, draw_function):
+    # Optional full-color variant, picked up by icons_to_rust_code.py as <name>_rgb.png
+    img = Image.new("RGB", (icon_size, icon_size), (0, 0, 0))
+    draw = ImageDraw.Draw(img)
+    draw_function(draw)
+    img.save(f"{icon_name}_rgb.png")
+    img.show()
+
 def sun(draw):
     draw.ellipse([8, 8, 32, 32], outline=0, width=2)
     for angle in range(0, 360, 45):
@@ -19,6 +27,16 @@ def sun(draw):
         y2 = 20 + 16 * round(math.sin(math.radians(angle)))
         draw.line([x1, y1, x2, y2], fill=0)
 
+def sun_rgb(draw):
+    # Yellow disc with orange rim and rays
+    draw.ellipse([8, 8, 32, 32], fill=(255, 255, 0), outline=(255, 165, 0), width=2)
+    for angle in range(0, 360, 45):
+        x1 = 20 + 12 * round(math.cos(math.radians(angle)))
+        y1 = 20 + 12 * round(math.sin(math.radians(angle)))
+        x2 = 20 + 16 * round(math.cos(math.radians(angle)))
+        y2 = 20 + 16 * round(math.sin(math.radians(angle)))
+        draw.line([x1, y1, x2, y2], fill=(255, 165, 0))
+
 def moon(draw):
     draw.ellipse([8, 8, 32, 32], outline=0, width=2)
     draw.ellipse([14, 8, 32, 32], fill=1)  # Sichel-Mond-Effekt
@@ -75,3 +93,6 @@ save_icon('rain', rain)
 save_icon('thunder', thunder)
 save_icon('snow', snow)
 save_icon('fog', fog)
+
+# Farbvarianten
+save_rgb_icon('sun', sun_rgb)
@@ -1,3 +1,5 @@
+import os
+
 from PIL import Image
 
 ICON_MAP = {
@@ -23,6 +25,7 @@ ICON_MAP = {
 
 ICON_SIZE = 40  # px
 BYTES_PER_ICON = ICON_SIZE * ICON_SIZE // 8  # 200 bytes
+BYTES_PER_ICON_4BPP = ICON_SIZE * ICON_SIZE // 2  # 800 bytes, optional color variant
 
 def png_to_bytes(filename):
     img = Image.open(filename).convert("1")  # 1-bit monochrome
@@ -39,8 +42,27 @@ def png_to_bytes(filename):
             data.append(byte)
     return data
 
+def png_to_4bpp(filename):
+    # Color variant: reduced to at most 16 colors, 2 pixels per byte (high nibble left)
+    img = Image.open(filename).convert("RGB").quantize(colors=16)
+    pixels = img.load()
+    palette = img.getpalette()[: 3 * len(img.getcolors())]
+    data = []
+    for y in range(ICON_SIZE):
+        for x in range(0, ICON_SIZE, 2):
+            data.append((pixels[x, y] << 4) | pixels[x + 1, y])
+    colors = [tuple(palette[i:i + 3]) for i in range(0, len(palette), 3)]
+    return data, colors
+
+# Icons with an additional <name>_rgb.png get a full-color variant
+RGB_ICONS = sorted(
+    icon for icon in set(ICON_MAP.values()) if os.path.exists(f"{icon}_rgb.png")
+)
+
 with open("weather_icons.rs", "w") as f:
     f.write("// Auto-generated Rust file\n\n")
+    f.write("use embedded_graphics::pixelcolor::Rgb565;\n")
+    f.write("use weather_common::icon_bitmap::{expand_4bpp, ICON_4BPP_BYTES, ICON_PIXELS};\n\n")
 
     # Arrays für alle Icons
     for icon_name in set(ICON_MAP.values()):
@@ -59,3 +81,51 @@ with open("weather_icons.rs", "w") as f:
     f.write("        _ => None,\n")
     f.write("    }\n")
     f.write("}\n")
+
+    # Farbvarianten: 4bpp-Daten, Palette und die daraus erzeugten Rgb565-Pixel
+    for icon_name in RGB_ICONS:
+        data, colors = png_to_4bpp(f"{icon_name}_rgb.png")
+        name = icon_name.upper()
+        f.write(f"\nconst {name}_4BPP: [u8; ICON_4BPP_BYTES] = [\n")
+        for i in range(0, len(data), 16):
+            line = ", ".join(f"0x{b:02X}" for b in data[i:i+16])
+            f.write(f"    {line},\n")
+        f.write("];\n\n")
+        f.write(f"const {name}_PALETTE: [Rgb565; {len(colors)}] = [\n")
+        for r, g, b in colors:
+            f.write(f"    Rgb565::new({r >> 3}, {g >> 2}, {b >> 3}),\n")
+        f.write("];\n\n")
+        f.write(f"static {name}_RGB: [Rgb565; ICON_PIXELS] = expand_4bpp(&{name}_4BPP, &{name}_PALETTE);\n")
+
+    # Farbicon per Code, None -> 1bpp-Icon verwenden
+    f.write("\npub fn get_weather_icon_rgb(code: &str) -> Option<&'static [Rgb565]> {\n")
+    f.write("    match code {\n")
+    for code, icon in ICON_MAP.items():
+        if icon in RGB_ICONS:
+            f.write(f"        \"{code}\" => Some(&{icon.upper()}_RGB),\n")
+    f.write("        _ => None,\n")
+    f.write("    }\n")
+    f.write("}\n")
+
+    # Selbsttest: jedes Icon hat genau die Länge, die der Decoder erwartet
+    f.write("\n#[cfg(test)]\n")
+    f.write("mod tests {\n")
+    f.write("    use super::*;\n")
+    f.write("    use weather_common::icon_bitmap::ICON_BYTES;\n\n")
+    f.write("    const KNOWN_CODES: [&str; %d] = [\n" % len(ICON_MAP))
+    codes = [f"\"{code}\"" for code in ICON_MAP]
+    for i in range(0, len(codes), 13):
+        f.write(f"        {', '.join(codes[i:i+13])},\n")
+    f.write("    ];\n\n")
+    f.write("    #[test]\n")
+    f.write("    fn test_icon_lengths() {\n")
+    f.write("        for code in KNOWN_CODES {\n")
+    f.write("            let icon = get_weather_icon(code).unwrap_or_else(|| panic!(\"no icon for {}\", code));\n")
+    f.write("            assert_eq!(icon.len(), ICON_BYTES, \"icon {}\", code);\n")
+    f.write("            if let Some(pixels) = get_weather_icon_rgb(code) {\n")
+    f.write("                assert_eq!(pixels.len(), ICON_PIXELS, \"color icon {}\", code);\n")
+    f.write("            }\n")
+    f.write("        }\n")
+    f.write("        assert!(get_weather_icon(\"99x\").is_none());\n")
+    f.write("    }\n")
+    f.write("}\n")
@@ -0,0 +1,114 @@
+// backoff.rs
+use core::fmt::Debug;
+use core::time::Duration;
+use log::*;
+
+/// The longest delay between two attempts
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Returns the delay after the given (zero-based) failed attempt: 1s, 2s, 4s, ... capped at 60s
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let secs = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
+    Duration::from_secs(secs).min(MAX_BACKOFF)
+}
+
+/// Calls `attempt` up to `max_retries` times, sleeping with exponential backoff in between.
+///
+/// # Arguments
+/// * `max_retries` - Total number of attempts
+/// * `attempt` - The fallible operation
+/// * `sleep` - Called with the delay before the next attempt
+///
+/// # Returns
+/// * `Ok(())` - One of the attempts succeeded
+/// * `Err` - All attempts failed, contains the last error
+pub fn retry_with_backoff<E: Debug>(
+    max_retries: u32,
+    mut attempt: impl FnMut() -> Result<(), E>,
+    mut sleep: impl FnMut(Duration),
+) -> anyhow::Result<()> {
+    let mut last_error = None;
+
+    for i in 0..max_retries {
+        info!("Attempt {}/{}...", i + 1, max_retries);
+        match attempt() {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                warn!("Attempt {}/{} failed: {:?}", i + 1, max_retries, e);
+                last_error = Some(e);
+            }
+        }
+
+        // No need to wait after the last attempt
+        if i + 1 < max_retries {
+            let delay = backoff_delay(i);
+            info!("Retrying in {}s", delay.as_secs());
+            sleep(delay);
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "Giving up after {} attempts, last error: {:?}",
+        max_retries,
+        last_error
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(0), Duration::from_secs(1));
+        assert_eq!(backoff_delay(1), Duration::from_secs(2));
+        assert_eq!(backoff_delay(2), Duration::from_secs(4));
+        assert_eq!(backoff_delay(5), Duration::from_secs(32));
+        // Capped at 60 seconds
+        assert_eq!(backoff_delay(6), MAX_BACKOFF);
+        assert_eq!(backoff_delay(100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_retry_succeeds_after_failures() {
+        // Simulate connect() failing twice before succeeding
+        let mut calls = 0;
+        let mut sleeps = Vec::new();
+        let result = retry_with_backoff(
+            5,
+            || {
+                calls += 1;
+                if calls < 3 {
+                    Err("connect failed")
+                } else {
+                    Ok(())
+                }
+            },
+            |delay| sleeps.push(delay),
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(calls, 3);
+        assert_eq!(sleeps, vec![Duration::from_secs(1), Duration::from_secs(2)]);
+    }
+
+    #[test]
+    fn test_retry_gives_up() {
+        // Simulate connect() never succeeding
+        let mut calls = 0;
+        let mut sleeps = Vec::new();
+        let result = retry_with_backoff(
+            4,
+            || {
+                calls += 1;
+                Err("connect failed")
+            },
+            |delay| sleeps.push(delay),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(calls, 4);
+        // No sleep after the final attempt
+        assert_eq!(sleeps.len(), 3);
+    }
+}
@@ -0,0 +1,91 @@
+// geocoding.rs
+use crate::http_utils::urlencode;
+use core::fmt;
+use serde::Deserialize;
+
+/// Maximum number of locations requested from the geocoding API
+const GEO_LIMIT: u32 = 5;
+
+/// A location returned by the OpenWeatherMap geocoding API (`/geo/1.0/direct`)
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct GeoCandidate {
+    pub name: String,
+    /// Only sent for some countries, e.g. the US states
+    #[serde(default)]
+    pub state: Option<String>,
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl fmt::Display for GeoCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(state) = &self.state {
+            write!(f, ", {}", state)?;
+        }
+        write!(
+            f,
+            ", {} (lat = {:.4}, lon = {:.4})",
+            self.country, self.lat, self.lon
+        )
+    }
+}
+
+/// Builds the geocoding URL that lists the locations matching `city`.
+/// The city can be qualified as "City,CC" or "City,State,CC" (ISO 3166 country code).
+pub fn direct_url(city: &str, api_key: &str) -> String {
+    format!(
+        "https://api.openweathermap.org/geo/1.0/direct?q={}&limit={}&appid={}",
+        urlencode(city),
+        GEO_LIMIT,
+        urlencode(api_key)
+    )
+}
+
+/// Formats the resolved location of a weather response for the display, e.g. "Springfield, US"
+pub fn location_label(name: &str, country: &str) -> String {
+    if country.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}, {}", name, country)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_candidates() {
+        let json = r#"[
+            {"name":"Springfield","local_names":{"en":"Springfield"},"lat":39.7990175,"lon":-89.6439575,"country":"US","state":"Illinois"},
+            {"name":"Springfield","lat":-27.6533,"lon":152.9175,"country":"AU"}
+        ]"#;
+        let candidates: Vec<GeoCandidate> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(
+            candidates[0].to_string(),
+            "Springfield, Illinois, US (lat = 39.7990, lon = -89.6440)"
+        );
+        assert_eq!(
+            candidates[1].to_string(),
+            "Springfield, AU (lat = -27.6533, lon = 152.9175)"
+        );
+    }
+
+    #[test]
+    fn test_direct_url() {
+        assert_eq!(
+            direct_url("Springfield,US", "key"),
+            "https://api.openweathermap.org/geo/1.0/direct?q=Springfield%2CUS&limit=5&appid=key"
+        );
+    }
+
+    #[test]
+    fn test_location_label() {
+        assert_eq!(location_label("Springfield", "US"), "Springfield, US");
+        assert_eq!(location_label("Springfield", ""), "Springfield");
+    }
+}
@@ -0,0 +1,239 @@
+// http_utils.rs
+use core::time::Duration;
+use embedded_svc::http::client::Client;
+use embedded_svc::http::Headers;
+use esp_idf_svc::http::client::{Configuration as HttpConfiguration, EspHttpConnection};
+use log::*;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+
+/// Size of a single read from the HTTP connection
+const READ_CHUNK_LEN: usize = 512;
+
+/// Upper bound for a response body, protects the heap from runaway responses
+pub const MAX_BODY_LEN: usize = 64 * 1024;
+
+/// Reads a complete HTTP body by calling `read` until it returns 0.
+///
+/// # Arguments
+/// * `content_length` - Value of the `Content-Length` header, if present
+/// * `read` - Reads the next chunk into the buffer and returns the number of bytes
+///
+/// # Returns
+/// * `Ok(Vec<u8>)` - The complete body
+/// * `Err` - A read error, or the body exceeds `MAX_BODY_LEN`
+pub fn read_body<E>(
+    content_length: Option<u64>,
+    mut read: impl FnMut(&mut [u8]) -> Result<usize, E>,
+) -> anyhow::Result<Vec<u8>>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let expected = content_length.map(|len| len as usize);
+    if let Some(len) = expected {
+        if len > MAX_BODY_LEN {
+            anyhow::bail!("HTTP body of {} bytes exceeds {} bytes", len, MAX_BODY_LEN);
+        }
+    }
+
+    let mut body = Vec::with_capacity(expected.unwrap_or(READ_CHUNK_LEN));
+    let mut chunk = [0u8; READ_CHUNK_LEN];
+
+    loop {
+        // Never read past the announced length
+        let to_read = match expected {
+            Some(len) => (len - body.len()).min(chunk.len()),
+            None => chunk.len(),
+        };
+        if to_read == 0 {
+            break;
+        }
+
+        let bytes_read = read(&mut chunk[..to_read])?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        body.extend_from_slice(&chunk[..bytes_read]);
+        if body.len() > MAX_BODY_LEN {
+            anyhow::bail!("HTTP body exceeds {} bytes", MAX_BODY_LEN);
+        }
+    }
+
+    if let Some(len) = expected {
+        if body.len() < len {
+            warn!("HTTP body truncated: got {} of {} bytes", body.len(), len);
+        }
+    }
+
+    Ok(body)
+}
+
+/// Converts a complete body to text
+/// Decode only after `read_body`: a single read may end in the middle of a multibyte
+/// character (e.g. the "ö" of "Köln"). Invalid bytes that remain are replaced with U+FFFD
+/// and logged, so that one broken character does not fail the whole request.
+pub fn decode_body(body: &[u8]) -> Cow<'_, str> {
+    let text = String::from_utf8_lossy(body);
+    if let Cow::Owned(_) = text {
+        warn!("HTTP body is not valid UTF-8, replaced the invalid bytes");
+    }
+    text
+}
+
+/// Percent-encodes a query string component (RFC 3986).
+/// Everything except unreserved characters is encoded byte by byte, so UTF-8 works too.
+pub fn urlencode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// A non-200 HTTP response
+/// Carries the body so callers can turn API-specific error objects into their own errors.
+#[derive(Debug)]
+pub struct HttpStatusError {
+    pub status: u16,
+    pub body: String,
+}
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP {}", self.status)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// Performs an HTTPS GET request and returns the body.
+/// Uses the ESP-IDF certificate bundle, so no server certificate has to be configured.
+///
+/// # Arguments
+/// * `url` - Complete request URL including the query string
+/// * `timeout` - Network timeout of the connection
+///
+/// # Returns
+/// * `Ok(Vec<u8>)` - The body of a 200 response
+/// * `Err` - Network error, or `HttpStatusError` for non-200 responses
+pub fn http_get(url: &str, timeout: Duration) -> anyhow::Result<Vec<u8>> {
+    let connection = EspHttpConnection::new(&HttpConfiguration {
+        use_global_ca_store: true,
+        crt_bundle_attach: Some(esp_idf_sys::esp_crt_bundle_attach),
+        timeout: Some(timeout),
+        ..Default::default()
+    })?;
+
+    let mut client = Client::wrap(connection);
+    let request = client.get(url)?;
+    let mut response = request.submit()?;
+
+    let status = response.status();
+    info!("HTTP response status: {}", status);
+
+    // Read the complete response body, it may arrive in several chunks
+    let content_length = response.content_len();
+    let body = read_body(content_length, |buf| response.read(buf))?;
+
+    if status != 200 {
+        return Err(HttpStatusError {
+            status,
+            body: decode_body(&body).into_owned(),
+        }
+        .into());
+    }
+
+    Ok(body)
+}
+
+/// Performs an HTTPS GET request and deserializes the JSON response, see `http_get`.
+///
+/// # Returns
+/// * `Ok(T)` - The deserialized body of a 200 response
+/// * `Err` - Network or parsing error, or `HttpStatusError` for non-200 responses
+pub fn http_get_json<T: DeserializeOwned>(url: &str, timeout: Duration) -> anyhow::Result<T> {
+    let body = http_get(url, timeout)?;
+    Ok(serde_json::from_str(&decode_body(&body))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a reader that hands out the given chunks one per call, then 0
+    fn chunked_reader(
+        chunks: Vec<&'static [u8]>,
+    ) -> impl FnMut(&mut [u8]) -> Result<usize, std::io::Error> {
+        let mut chunks = chunks.into_iter();
+        let mut pending: &[u8] = &[];
+        move |buf: &mut [u8]| {
+            if pending.is_empty() {
+                pending = chunks.next().unwrap_or(&[]);
+            }
+            let len = pending.len().min(buf.len());
+            buf[..len].copy_from_slice(&pending[..len]);
+            pending = &pending[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn test_read_body_multiple_reads() {
+        let chunks: Vec<&'static [u8]> = vec![b"{\"name\":", b"\"Frank", b"furt\"}"];
+        let body = read_body(None, chunked_reader(chunks)).unwrap();
+        assert_eq!(body, b"{\"name\":\"Frankfurt\"}");
+    }
+
+    #[test]
+    fn test_read_body_respects_content_length() {
+        // Trailing bytes beyond Content-Length are not read
+        let chunks: Vec<&'static [u8]> = vec![b"{\"a\":", b"1}garbage"];
+        let body = read_body(Some(7), chunked_reader(chunks)).unwrap();
+        assert_eq!(body, b"{\"a\":1}");
+    }
+
+    #[test]
+    fn test_read_body_larger_than_one_chunk() {
+        static LARGE: [u8; 3000] = [b'x'; 3000];
+        let body = read_body(None, chunked_reader(vec![&LARGE])).unwrap();
+        assert_eq!(body.len(), 3000);
+    }
+
+    #[test]
+    fn test_read_body_too_large() {
+        assert!(read_body(Some(MAX_BODY_LEN as u64 + 1), chunked_reader(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_decode_body_split_codepoint() {
+        // The two bytes of "ö" arrive in separate reads
+        let chunks: Vec<&'static [u8]> = vec![b"{\"name\":\"K\xC3", b"\xB6ln\"}"];
+        let body = read_body(None, chunked_reader(chunks)).unwrap();
+        assert_eq!(decode_body(&body), "{\"name\":\"Köln\"}");
+    }
+
+    #[test]
+    fn test_decode_body_invalid_bytes() {
+        // A truncated character is replaced instead of failing the whole body
+        assert_eq!(
+            decode_body(b"{\"name\":\"K\xC3\"}"),
+            "{\"name\":\"K\u{FFFD}\"}"
+        );
+        assert!(matches!(decode_body(b"plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn test_urlencode() {
+        assert_eq!(urlencode("Berlin"), "Berlin");
+        assert_eq!(urlencode("Frankfurt am Main"), "Frankfurt%20am%20Main");
+        assert_eq!(urlencode("London,GB"), "London%2CGB");
+        assert_eq!(urlencode("München"), "M%C3%BCnchen");
+        assert_eq!(urlencode("a&b=c"), "a%26b%3Dc");
+    }
+}
@@ -0,0 +1,131 @@
+// icon_bitmap.rs
+use core::sync::atomic::{AtomicBool, Ordering};
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+use log::*;
+
+/// Width and height of the weather icons in pixels
+pub const ICON_SIZE: usize = 40;
+
+/// Bytes per icon row, one bit per pixel
+const ROW_BYTES: usize = ICON_SIZE / 8;
+
+/// Expected length of an icon bitmap in bytes
+pub const ICON_BYTES: usize = ICON_SIZE * ROW_BYTES;
+
+/// Number of pixels of an icon, one Rgb565 color each in the full-color variant
+pub const ICON_PIXELS: usize = ICON_SIZE * ICON_SIZE;
+
+/// Length of a 4-bit-per-pixel (16 color) icon in bytes
+pub const ICON_4BPP_BYTES: usize = ICON_PIXELS / 2;
+
+/// Set after the first icon with an unexpected length was reported
+static LENGTH_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Iterates over the set pixels of a 40x40 1-bit-per-pixel icon, relative to its top-left corner.
+/// Rows are stored top to bottom, the most significant bit is the leftmost pixel.
+/// Nothing is allocated, so the pixels can be passed straight to `DrawTarget::draw_iter`.
+/// A bitmap of the wrong length is a broken icon table and panics in debug builds.
+/// Release builds warn once, pixels beyond the end of a truncated bitmap stay blank.
+pub fn set_pixels(data: &[u8]) -> impl Iterator<Item = Point> + '_ {
+    debug_assert_eq!(
+        data.len(),
+        ICON_BYTES,
+        "weather icon must be 40x40 with 1 bit per pixel"
+    );
+    if data.len() != ICON_BYTES && !LENGTH_WARNED.swap(true, Ordering::Relaxed) {
+        warn!(
+            "Weather icon has {} bytes instead of {} (40x40, 1 bit per pixel)",
+            data.len(),
+            ICON_BYTES
+        );
+    }
+
+    (0..ICON_SIZE).flat_map(move |y| {
+        (0..ICON_SIZE).filter_map(move |x| {
+            let byte = *data.get(y * ROW_BYTES + x / 8)?;
+            let bit_index = 7 - (x % 8);
+            ((byte >> bit_index) & 1 == 1).then_some(Point::new(x as i32, y as i32))
+        })
+    })
+}
+
+/// Expands a 40x40 4-bit-per-pixel icon into one Rgb565 color per pixel.
+/// Each nibble indexes `palette`, the high nibble is the left pixel of the pair.
+/// Evaluated at compile time for the generated color icons, so they end up in flash
+/// ready for `DrawTarget::fill_contiguous`.
+pub const fn expand_4bpp(
+    data: &[u8; ICON_4BPP_BYTES],
+    palette: &[Rgb565],
+) -> [Rgb565; ICON_PIXELS] {
+    let mut pixels = [Rgb565::BLACK; ICON_PIXELS];
+    let mut i = 0;
+    while i < ICON_4BPP_BYTES {
+        pixels[2 * i] = palette[(data[i] >> 4) as usize];
+        pixels[2 * i + 1] = palette[(data[i] & 0x0F) as usize];
+        i += 1;
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_pixels() {
+        let mut data = [0u8; ICON_BYTES];
+        // Row 0: leftmost pixel and the last pixel of the first byte
+        data[0] = 0b1000_0001;
+        // Row 1: rightmost pixel of the row
+        data[ROW_BYTES + ROW_BYTES - 1] = 0b0000_0001;
+        // Last row: first pixel of the second byte
+        data[(ICON_SIZE - 1) * ROW_BYTES + 1] = 0b1000_0000;
+
+        let pixels: Vec<Point> = set_pixels(&data).collect();
+        assert_eq!(
+            pixels,
+            vec![
+                Point::new(0, 0),
+                Point::new(7, 0),
+                Point::new(39, 1),
+                Point::new(8, 39),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_pixels_empty() {
+        assert_eq!(set_pixels(&[0u8; ICON_BYTES]).count(), 0);
+        assert_eq!(set_pixels(&[0xFFu8; ICON_BYTES]).count(), 1600);
+    }
+
+    #[test]
+    #[cfg_attr(
+        debug_assertions,
+        should_panic(expected = "40x40 with 1 bit per pixel")
+    )]
+    fn test_set_pixels_truncated() {
+        // Only the first two rows are present, the rest stays blank
+        let data = [0xFFu8; 2 * ROW_BYTES];
+        let pixels: Vec<Point> = set_pixels(&data).collect();
+        assert_eq!(pixels.len(), 2 * ICON_SIZE);
+        assert_eq!(pixels.last(), Some(&Point::new(39, 1)));
+    }
+
+    #[test]
+    fn test_expand_4bpp() {
+        let palette = [Rgb565::BLACK, Rgb565::RED, Rgb565::YELLOW];
+        let mut data = [0u8; ICON_4BPP_BYTES];
+        // Row 0: red, yellow in the first two pixels
+        data[0] = 0x12;
+        // Last pixel of the icon
+        data[ICON_4BPP_BYTES - 1] = 0x02;
+
+        let pixels = expand_4bpp(&data, &palette);
+        assert_eq!(pixels[0], Rgb565::RED);
+        assert_eq!(pixels[1], Rgb565::YELLOW);
+        assert_eq!(pixels[2], Rgb565::BLACK);
+        assert_eq!(pixels[ICON_PIXELS - 2], Rgb565::BLACK);
+        assert_eq!(pixels[ICON_PIXELS - 1], Rgb565::YELLOW);
+    }
+}
@@ -0,0 +1,14 @@
+// ===============================================================================
+// Shared helpers for the OpenWeather display apps
+// ===============================================================================
+// `http_utils` runs the TLS GET requests and parses JSON responses, `geocoding`
+// builds the OpenWeather geocoding URLs on top of it. `backoff` retries failed
+// operations with growing pauses, `scheduler` tracks the main loop intervals and
+// `icon_bitmap` decodes the packed weather icon bitmaps.
+// ===============================================================================
+
+pub mod backoff;
+pub mod geocoding;
+pub mod http_utils;
+pub mod icon_bitmap;
+pub mod scheduler;
@@ -0,0 +1,122 @@
+// scheduler.rs
+use core::time::Duration;
+
+/// A recurring task of the main loop, e.g. the clock redraw or the weather fetch
+///
+/// The main loop sleeps `tick_ms` between iterations and asks every cadence whether
+/// its task is due. Runs happen on multiples of the interval of the given clock, so a
+/// 1000 ms cadence fed with the wall clock redraws right after every second change.
+/// Runs that were missed during a long iteration are skipped, not made up.
+pub struct Cadence {
+    interval: Duration,
+    /// `None` until the first run or after `trigger`
+    next_due: Option<Duration>,
+}
+
+impl Cadence {
+    /// A zero interval is treated as 1 ms
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval: interval.max(Duration::from_millis(1)),
+            next_due: None,
+        }
+    }
+
+    /// Whether the task is due at `now`; always true before the first run
+    ///
+    /// A clock that went back by more than an interval (e.g. a time sync) makes the
+    /// task due as well, instead of waiting for the old due time.
+    pub fn is_due(&self, now: Duration) -> bool {
+        match self.next_due {
+            None => true,
+            Some(due) => now >= due || due > now + self.interval,
+        }
+    }
+
+    /// Records a run at `now`, the next one is due at the following multiple of the interval
+    pub fn mark_run(&mut self, now: Duration) {
+        let interval = self.interval.as_millis();
+        let next = (now.as_millis() / interval + 1) * interval;
+        self.next_due = Some(Duration::from_millis(next as u64));
+    }
+
+    /// Records a run and returns true if the task is due at `now`
+    pub fn poll(&mut self, now: Duration) -> bool {
+        let due = self.is_due(now);
+        if due {
+            self.mark_run(now);
+        }
+        due
+    }
+
+    /// Makes the task due right away, e.g. after a configuration change
+    pub fn trigger(&mut self) {
+        self.next_due = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_poll_on_interval_boundaries() {
+        let mut clock = Cadence::new(ms(1000));
+        // The first poll is always due, the next one after the following second change
+        assert!(clock.poll(ms(12_345)));
+        assert!(!clock.poll(ms(12_999)));
+        assert!(clock.poll(ms(13_050)));
+        assert!(!clock.poll(ms(13_950)));
+        assert!(clock.poll(ms(14_000)));
+    }
+
+    #[test]
+    fn test_missed_runs_are_skipped() {
+        let mut weather = Cadence::new(ms(60_000));
+        assert!(weather.poll(ms(0)));
+        // A slow iteration overran several intervals: one run, then back on the grid
+        assert!(weather.poll(ms(250_000)));
+        assert!(!weather.poll(ms(299_999)));
+        assert!(weather.poll(ms(300_000)));
+    }
+
+    #[test]
+    fn test_is_due_without_run() {
+        let mut weather = Cadence::new(ms(1000));
+        weather.mark_run(ms(500));
+        // Checking alone does not consume the run, e.g. while a failed fetch is retried
+        assert!(weather.is_due(ms(1000)));
+        assert!(weather.is_due(ms(1500)));
+        weather.mark_run(ms(1500));
+        assert!(!weather.is_due(ms(1500)));
+    }
+
+    #[test]
+    fn test_trigger() {
+        let mut weather = Cadence::new(ms(900_000));
+        assert!(weather.poll(ms(1000)));
+        assert!(!weather.is_due(ms(2000)));
+        weather.trigger();
+        assert!(weather.poll(ms(2000)));
+        assert!(!weather.is_due(ms(3000)));
+    }
+
+    #[test]
+    fn test_clock_going_back() {
+        let mut clock = Cadence::new(ms(1000));
+        assert!(clock.poll(ms(1_700_000_000_000)));
+        assert!(clock.poll(ms(5000)));
+        assert!(!clock.poll(ms(5500)));
+    }
+
+    #[test]
+    fn test_zero_interval() {
+        let mut cadence = Cadence::new(Duration::ZERO);
+        assert!(cadence.poll(ms(10)));
+        assert!(cadence.poll(ms(11)));
+    }
+}
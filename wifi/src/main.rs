@@ -1,57 +1,84 @@
+use anyhow::Context;
 use esp_idf_svc::hal::prelude::Peripherals;
 use esp_idf_svc::wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi};
 use esp_idf_svc::{eventloop::EspSystemEventLoop, nvs::EspDefaultNvsPartition};
 use log::*;
 
-// It is recommended to use a secrets.toml file to store credentials.
-// Create a secrets.toml file in the root of the project with the following content:
+// Credentials from the workspace secrets.toml, embedded at compile time:
 //
 // [wifi]
 // ssid = "Your_SSID"
 // password = "Your_Password"
+//
+// A list of [[wifi.networks]] works as well, this example connects to the first one.
+mod secrets;
+use secrets::Secrets;
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     // Initialize the ESP-IDF services
     esp_idf_svc::sys::link_patches();
     esp_idf_svc::log::EspLogger::initialize_default();
 
     info!("Hello, ESP32 with WiFi!");
 
+    // Load secrets (embedded at compile time)
+    let secrets = Secrets::load()?;
+    let ssid = secrets.wifi.ssid.as_str();
+    let password = secrets.wifi.password.as_str();
+
     // Take the peripherals
-    let peripherals = Peripherals::take().unwrap();
-    let sys_loop = EspSystemEventLoop::take().unwrap();
-    let nvs = EspDefaultNvsPartition::take().unwrap();
+    let peripherals = Peripherals::take().context("failed to take peripherals")?;
+    let sys_loop = EspSystemEventLoop::take().context("failed to take the system event loop")?;
+    let nvs = EspDefaultNvsPartition::take().context("failed to take the NVS partition")?;
 
     // Create a new WiFi driver
     let mut wifi = BlockingWifi::wrap(
-        EspWifi::new(peripherals.modem, sys_loop.clone(), Some(nvs)).unwrap(),
+        EspWifi::new(peripherals.modem, sys_loop.clone(), Some(nvs))
+            .context("failed to create the WiFi driver")?,
         sys_loop,
     )
-    .unwrap();
+    .context("failed to wrap the WiFi driver")?;
 
     // Configure the WiFi client
+    // The heapless strings reject values that are too long, without an error type to wrap
     let wifi_config = Configuration::Client(ClientConfiguration {
-        ssid: "SSID".try_into().unwrap(),
-        password: "PASSWORD".try_into().unwrap(),
-        auth_method: AuthMethod::WPA2Personal,
+        ssid: ssid
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("SSID '{}' is longer than 32 bytes", ssid))?,
+        password: password
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("password is longer than 64 bytes"))?,
+        // Open networks have no password
+        auth_method: if password.is_empty() {
+            AuthMethod::None
+        } else {
+            AuthMethod::WPA2Personal
+        },
         ..Default::default()
     });
 
     // Set the WiFi configuration
-    wifi.set_configuration(&wifi_config).unwrap();
+    wifi.set_configuration(&wifi_config)
+        .context("failed to set the WiFi configuration")?;
     // Start the WiFi driver
-    wifi.start().unwrap();
+    wifi.start().context("failed to start WiFi")?;
     info!("WiFi started");
 
     // Connect to the WiFi network
-    wifi.connect().unwrap();
+    wifi.connect()
+        .with_context(|| format!("failed to connect to '{}'", ssid))?;
     info!("WiFi connected!");
 
     // Wait for the network interface to be up
-    wifi.wait_netif_up().unwrap();
+    wifi.wait_netif_up()
+        .with_context(|| format!("no IP address from '{}'", ssid))?;
 
     // Get the IP address
-    let ip_info = wifi.wifi().sta_netif().get_ip_info().unwrap();
+    let ip_info = wifi
+        .wifi()
+        .sta_netif()
+        .get_ip_info()
+        .context("failed to read the IP address")?;
     info!("IP address: {:?}", ip_info.ip);
 
     // Main loop
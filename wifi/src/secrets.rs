@@ -0,0 +1,60 @@
+use serde::Deserialize;
+
+// Secrets direkt aus Datei zur Compile-Zeit einlesen
+// build.rs löst den Pfad auf: $SECRETS_FILE oder die secrets.toml im Workspace
+const SECRETS_TOML: &str = include_str!(env!("SECRETS_FILE"));
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Secrets {
+    pub wifi: WiFiConfig,
+}
+
+/// Akzeptiert ein einzelnes `ssid`/`password`-Paar oder eine Liste `[[wifi.networks]]`,
+/// von der dieses Beispiel nur das erste Netz verwendet
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "RawWiFiConfig")]
+pub struct WiFiConfig {
+    pub ssid: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WiFiNetwork {
+    pub ssid: String,
+    pub password: String,
+}
+
+/// Die beiden TOML-Formen des [wifi]-Abschnitts
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawWiFiConfig {
+    Multiple { networks: Vec<WiFiNetwork> },
+    Single { ssid: String, password: String },
+}
+
+impl TryFrom<RawWiFiConfig> for WiFiConfig {
+    type Error = String;
+
+    fn try_from(raw: RawWiFiConfig) -> Result<Self, Self::Error> {
+        match raw {
+            RawWiFiConfig::Multiple { networks } => networks
+                .into_iter()
+                .next()
+                .map(|network| Self {
+                    ssid: network.ssid,
+                    password: network.password,
+                })
+                .ok_or_else(|| "wifi.networks enthält kein Netz".to_string()),
+            RawWiFiConfig::Single { ssid, password } => Ok(Self { ssid, password }),
+        }
+    }
+}
+
+impl Secrets {
+    /// Lädt Secrets die zur Compile-Zeit eingebettet wurden
+    pub fn load() -> anyhow::Result<Self> {
+        let secrets: Secrets = toml::from_str(SECRETS_TOML)
+            .map_err(|e| anyhow::anyhow!("Fehler beim Parsen von secrets.toml: {}", e))?;
+        Ok(secrets)
+    }
+}
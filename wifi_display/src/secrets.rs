@@ -1,11 +1,15 @@
 use serde::Deserialize;
 
 // Secrets direkt aus Datei zur Compile-Zeit einlesen
-const SECRETS_TOML: &str = include_str!("../../secrets.toml");
+// build.rs löst den Pfad auf: $SECRETS_FILE oder die secrets.toml im Workspace
+const SECRETS_TOML: &str = include_str!(env!("SECRETS_FILE"));
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Secrets {
     pub wifi: WiFiConfig,
+    /// Verdrahtung des Displays (optionaler Abschnitt)
+    #[serde(default)]
+    pub display: DisplayConfig,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -14,6 +18,64 @@ pub struct WiFiConfig {
     pub password: String,
 }
 
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DisplayConfig {
+    /// GPIO-Nummern der Display-Anschlüsse (optionaler Abschnitt `[display.pins]`)
+    #[serde(default)]
+    pub pins: DisplayPinsConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DisplayPinsConfig {
+    /// SPI-Takt, SCL am Display (Standard: 18)
+    #[serde(default = "default_sclk_pin")]
+    pub sclk: u8,
+    /// SPI-Daten, SDA am Display (Standard: 23)
+    #[serde(default = "default_mosi_pin")]
+    pub mosi: u8,
+    /// Chip Select (Standard: 15)
+    #[serde(default = "default_cs_pin")]
+    pub cs: u8,
+    /// Umschaltung Daten/Befehl (Standard: 21)
+    #[serde(default = "default_dc_pin")]
+    pub dc: u8,
+    /// Hardware-Reset (Standard: 22)
+    #[serde(default = "default_rst_pin")]
+    pub rst: u8,
+}
+
+fn default_sclk_pin() -> u8 {
+    18
+}
+
+fn default_mosi_pin() -> u8 {
+    23
+}
+
+fn default_cs_pin() -> u8 {
+    15
+}
+
+fn default_dc_pin() -> u8 {
+    21
+}
+
+fn default_rst_pin() -> u8 {
+    22
+}
+
+impl Default for DisplayPinsConfig {
+    fn default() -> Self {
+        Self {
+            sclk: default_sclk_pin(),
+            mosi: default_mosi_pin(),
+            cs: default_cs_pin(),
+            dc: default_dc_pin(),
+            rst: default_rst_pin(),
+        }
+    }
+}
+
 impl Secrets {
     /// Lädt Secrets die zur Compile-Zeit eingebettet wurden
     pub fn load() -> anyhow::Result<Self> {
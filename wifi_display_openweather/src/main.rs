@@ -1,45 +1,45 @@
 // === IMPORTS ===
-use crate::secrets::Secrets;
-use core::ptr::addr_of_mut;
+use crate::secrets::{OpenWeatherConfig, Secrets};
+use display_hal::{build_display, DisplayPinNumbers, DisplayPins, GpioPool};
 use embedded_graphics::{
-    mono_font::{iso_8859_1::FONT_10X20, MonoTextStyle},
+    mono_font::{
+        iso_8859_1::{FONT_10X20, FONT_6X10},
+        MonoTextStyle,
+    },
     pixelcolor::Rgb565,
     prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
     text::Text,
 };
-use embedded_hal::digital::OutputPin as OutputPinTrait;
-use embedded_hal::spi::SpiDevice;
-use embedded_svc::http::client::Client;
-use esp_idf_svc::hal::{
-    delay::FreeRtos,
-    gpio::{AnyIOPin, OutputPin, PinDriver},
-    peripherals::Peripherals,
-    prelude::*,
-    spi::{config::Config, SpiDeviceDriver, SpiDriver, SpiDriverConfig},
-};
-use esp_idf_svc::http::client::{Configuration as HttpConfiguration, EspHttpConnection};
+use esp_idf_svc::hal::{delay::FreeRtos, peripherals::Peripherals};
+use esp_idf_svc::sntp::{EspSntp, SyncStatus};
 use esp_idf_svc::wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi};
 use esp_idf_svc::{eventloop::EspSystemEventLoop, nvs::EspDefaultNvsPartition};
 use log::*;
-use mipidsi::{
-    models::ST7789,
-    options::{ColorInversion, ColorOrder},
-    Builder,
-};
+use mipidsi::options::Orientation;
 use profont::PROFONT_24_POINT;
 use serde::Deserialize;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use weather_common::scheduler::Cadence;
+use weather_common::{backoff, geocoding, http_utils, icon_bitmap};
 
 mod secrets;
+mod text_wrap;
+mod time_utils;
 mod weather_icons; // importiert weather_icons.rs
 
-use weather_icons::get_weather_icon;
+use weather_icons::{get_weather_icon, get_weather_icon_rgb};
 // === OPENWEATHERMAP DATA STRUCTURES ===
 #[derive(Deserialize, Debug)]
 struct WeatherResponse {
     weather: Vec<Weather>,
     main: Main,
-    wind: Wind,
+    /// Missing in some station responses
+    #[serde(default)]
+    wind: Option<Wind>,
     name: String,
+    #[serde(default)]
+    sys: Sys,
 }
 
 #[derive(Deserialize, Debug)]
@@ -51,7 +51,12 @@ struct Weather {
 #[derive(Deserialize, Debug)]
 struct Main {
     temp: f32,
-    humidity: i32,
+    /// Missing in some station responses
+    #[serde(default)]
+    feels_like: Option<f32>,
+    /// Missing in some station responses
+    #[serde(default)]
+    humidity: Option<i32>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -59,6 +64,12 @@ struct Wind {
     speed: f32,
 }
 
+#[derive(Deserialize, Debug, Default)]
+struct Sys {
+    #[serde(default)]
+    country: String,
+}
+
 // === WEATHER SYMBOL MAPPING ===
 fn get_weather_symbol(icon_code: &str) -> &'static str {
     match icon_code {
@@ -79,105 +90,383 @@ fn get_weather_symbol(icon_code: &str) -> &'static str {
 }
 
 // === WEATHER FETCH FUNCTION ===
-fn get_weather(api_key: &str, city: &str) -> anyhow::Result<WeatherResponse> {
+fn get_weather(config: &OpenWeatherConfig) -> anyhow::Result<WeatherResponse> {
+    // Coordinates are more precise than the city name, prefer them when configured
+    let location = match config.coordinates() {
+        Some((lat, lon)) => format!("lat={}&lon={}", lat, lon),
+        None => format!("q={}", http_utils::urlencode(&config.city)),
+    };
+
     let url = format!(
-        "https://api.openweathermap.org/data/2.5/weather?q={}&appid={}&units=metric&lang=de",
-        city, api_key
+        "https://api.openweathermap.org/data/2.5/weather?{}&appid={}&units={}&lang={}",
+        location,
+        http_utils::urlencode(&config.api_key),
+        http_utils::urlencode(config.units()),
+        http_utils::urlencode(config.lang())
     );
 
-    let connection = EspHttpConnection::new(&HttpConfiguration {
-        use_global_ca_store: true,
-        crt_bundle_attach: Some(esp_idf_sys::esp_crt_bundle_attach),
-        timeout: Some(core::time::Duration::from_secs(30)),
-        ..Default::default()
-    })?;
-    let mut client = Client::wrap(connection);
+    // OWM reports errors as a JSON body, e.g. `{"cod":401,"message":"Invalid API key"}`
+    let result =
+        http_utils::http_get_json(&url, config.http_timeout()).map_err(OwmError::from_http_error);
+
+    // An unknown city name is a configuration error, list what the name could have meant
+    if let Err(e) = &result {
+        let not_found = e
+            .downcast_ref::<OwmError>()
+            .is_some_and(|owm_error| owm_error.cod == 404);
+        if not_found && config.coordinates().is_none() {
+            log_city_candidates(config);
+        }
+    }
+    result
+}
 
-    let request = client.get(&url)?;
-    let mut response = request.submit()?;
+/// Resolves the configured city with the OpenWeatherMap geocoding API and logs the matches,
+/// so the right one can be put into `city = "City,CC"` or `lat`/`lon`.
+fn log_city_candidates(config: &OpenWeatherConfig) {
+    let url = geocoding::direct_url(&config.city, &config.api_key);
+    match http_utils::http_get_json::<Vec<geocoding::GeoCandidate>>(&url, config.http_timeout()) {
+        Ok(candidates) if candidates.is_empty() => {
+            warn!(
+                "City '{}' not found by the geocoding API either",
+                config.city
+            );
+        }
+        Ok(candidates) => {
+            warn!("City '{}' not found, candidates:", config.city);
+            for candidate in &candidates {
+                warn!("  {}", candidate);
+            }
+        }
+        Err(e) => warn!("City lookup for '{}' failed: {}", config.city, e),
+    }
+}
 
-    let status = response.status();
-    info!("Weather API response status: {}", status);
+// === WIFI RECONNECT ===
+/// Maximum number of WiFi reconnect attempts before giving up.
+const WIFI_MAX_RETRIES: u32 = 8;
+
+/// Reconnects to WiFi, retrying with exponential backoff (1s, 2s, 4s, ... capped at 60s).
+/// Returns an error once the retry budget is exhausted so the caller can decide whether to reboot.
+fn reconnect_wifi_with_backoff(
+    wifi: &mut BlockingWifi<EspWifi<'static>>,
+    max_retries: u32,
+) -> anyhow::Result<()> {
+    backoff::retry_with_backoff(
+        max_retries,
+        || {
+            wifi.connect()?;
+            wifi.wait_netif_up()
+        },
+        |delay| FreeRtos::delay_ms(delay.as_millis() as u32),
+    )
+}
 
-    let mut body_buf = vec![0u8; 4096];
-    let bytes_read = response.read(&mut body_buf)?;
+/// Reads the IPv4 address of the station interface, `None` while no address is assigned.
+fn get_ip(wifi: &BlockingWifi<EspWifi<'static>>) -> Option<String> {
+    let ip_info = wifi.wifi().sta_netif().get_ip_info().ok()?;
+    (!ip_info.ip.is_unspecified()).then(|| ip_info.ip.to_string())
+}
 
-    let body_str = std::str::from_utf8(&body_buf[..bytes_read])?;
+/// Reads the IP address after a (re)connect and logs it if it differs from `ip`.
+fn update_ip(wifi: &BlockingWifi<EspWifi<'static>>, ip: &mut String) {
+    let current = get_ip(wifi).unwrap_or_default();
+    if current != *ip {
+        info!("IP-Adresse: {}", current);
+        *ip = current;
+    }
+}
 
-    let weather: WeatherResponse = serde_json::from_str(body_str)?;
-    Ok(weather)
+// === WEATHER API ERROR ===
+/// Error body returned by OpenWeatherMap for non-200 responses.
+/// `cod` is sent as a number for some errors and as a string for others.
+#[derive(Deserialize, Debug)]
+struct OwmError {
+    #[serde(deserialize_with = "deserialize_owm_code")]
+    cod: u16,
+    message: String,
 }
 
-// === CUSTOM ERROR TYPE ===
-#[derive(Debug)]
-struct CustomError;
+impl OwmError {
+    /// Builds the error from a non-200 response, falling back to the HTTP status
+    /// if the body is not an OWM error object.
+    fn from_response(status: u16, body: &str) -> Self {
+        serde_json::from_str(body).unwrap_or_else(|_| OwmError {
+            cod: status,
+            message: "unexpected response".to_string(),
+        })
+    }
 
-impl embedded_hal::spi::Error for CustomError {
-    fn kind(&self) -> embedded_hal::spi::ErrorKind {
-        embedded_hal::spi::ErrorKind::Other
+    /// Replaces an `HttpStatusError` with the OWM error object from its body,
+    /// other errors are passed through unchanged.
+    fn from_http_error(error: anyhow::Error) -> anyhow::Error {
+        match error.downcast::<http_utils::HttpStatusError>() {
+            Ok(http_error) => Self::from_response(http_error.status, &http_error.body).into(),
+            Err(error) => error,
+        }
     }
 }
 
-impl embedded_hal::digital::Error for CustomError {
-    fn kind(&self) -> embedded_hal::digital::ErrorKind {
-        embedded_hal::digital::ErrorKind::Other
+impl std::fmt::Display for OwmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OWM {}: {}", self.cod, self.message)
     }
 }
 
-// === SPI WRAPPER ===
-struct SpiWrapper<'a> {
-    spi: SpiDeviceDriver<'a, SpiDriver<'a>>,
+impl std::error::Error for OwmError {}
+
+/// Accepts the OWM `cod` field as either a number or a numeric string.
+fn deserialize_owm_code<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Code {
+        Number(u16),
+        Text(String),
+    }
+
+    match Code::deserialize(deserializer)? {
+        Code::Number(code) => Ok(code),
+        Code::Text(code) => code.parse().map_err(serde::de::Error::custom),
+    }
 }
 
-impl embedded_hal::spi::ErrorType for SpiWrapper<'_> {
-    type Error = CustomError;
+// === WEATHER FETCH RETRY ===
+/// Maximum number of weather fetch attempts per update.
+const WEATHER_MAX_ATTEMPTS: u32 = 3;
+/// Delay between two weather fetch attempts in milliseconds.
+const WEATHER_RETRY_DELAY_MS: u32 = 2000;
+
+/// Checks whether a weather fetch error is worth retrying.
+/// Network errors, timeouts and 5xx responses are transient;
+/// 4xx responses (e.g. 401 bad API key, 404 unknown city) and parse errors are not.
+fn is_transient_error(error: &anyhow::Error) -> bool {
+    if let Some(owm_error) = error.downcast_ref::<OwmError>() {
+        return owm_error.cod >= 500;
+    }
+    error.downcast_ref::<serde_json::Error>().is_none()
 }
 
-impl SpiDevice for SpiWrapper<'_> {
-    fn transaction(
-        &mut self,
-        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
-    ) -> Result<(), Self::Error> {
-        for op in operations {
-            match op {
-                embedded_hal::spi::Operation::Write(data) => {
-                    if !data.is_empty() {
-                        self.spi.write(data).map_err(|_| CustomError)?;
-                    }
-                }
-                embedded_hal::spi::Operation::Transfer(read, write) => {
-                    if !write.is_empty() {
-                        self.spi.transfer(read, write).map_err(|_| CustomError)?;
-                    }
-                }
-                embedded_hal::spi::Operation::TransferInPlace(data) => {
-                    if !data.is_empty() {
-                        let temp = data.to_vec();
-                        self.spi.transfer(data, &temp).map_err(|_| CustomError)?;
-                    }
-                }
-                _ => {}
+/// Fetches the weather, retrying transient failures up to `max_attempts` times.
+/// Returns the last error if all attempts fail.
+fn get_weather_retrying(
+    config: &OpenWeatherConfig,
+    max_attempts: u32,
+) -> anyhow::Result<WeatherResponse> {
+    let mut attempt = 1;
+    loop {
+        match get_weather(config) {
+            Ok(weather) => return Ok(weather),
+            Err(e) if attempt < max_attempts && is_transient_error(&e) => {
+                warn!(
+                    "Weather fetch attempt {}/{} failed, retrying: {}",
+                    attempt, max_attempts, e
+                );
+                FreeRtos::delay_ms(WEATHER_RETRY_DELAY_MS);
+                attempt += 1;
             }
+            Err(e) => return Err(e),
         }
-        Ok(())
     }
 }
 
-// === DC PIN WRAPPER ===
-struct DcPinWrapper<'a> {
-    pin: PinDriver<'a, esp_idf_svc::hal::gpio::AnyOutputPin, esp_idf_svc::hal::gpio::Output>,
+// === DISPLAY STATE ===
+/// Texts currently shown on the display.
+/// Comparing it with the previous state lets us redraw only the fields that changed.
+#[derive(Clone, PartialEq, Debug)]
+struct DisplayState {
+    city_name: String,
+    weather_temp: String,
+    feels_like_str: String,
+    weather_desc: String,
+    weather_icon: String,
+    wind_str: String,
+    hum_str: String,
+    time_str: String,
+    /// IP address in the status line, empty unless `display.show_ip` is set
+    ip_str: String,
 }
 
-impl embedded_hal::digital::ErrorType for DcPinWrapper<'_> {
-    type Error = CustomError;
+impl DisplayState {
+    /// Creates an empty state, so the first render draws every field.
+    fn new() -> Self {
+        Self {
+            city_name: String::new(),
+            weather_temp: String::new(),
+            feels_like_str: String::new(),
+            weather_desc: String::new(),
+            weather_icon: String::new(),
+            wind_str: String::new(),
+            hum_str: String::new(),
+            time_str: String::new(),
+            ip_str: String::new(),
+        }
+    }
 }
 
-impl OutputPinTrait for DcPinWrapper<'_> {
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.pin.set_low().map_err(|_| CustomError)
+// === DISPLAY RENDERING ===
+/// Top-left corner of the weather icon.
+const ICON_ORIGIN: Point = Point::new(160, 70);
+/// Baseline of the clock, below the weather fields.
+const CLOCK_POSITION: Point = Point::new(10, 220);
+/// Baseline of the small status line at the bottom edge.
+const STATUS_LINE_POSITION: Point = Point::new(10, 312);
+/// Shown instead of the time until SNTP has set the clock.
+const CLOCK_UNSYNCED: &str = "--:--:--";
+
+/// Redraws the fields that differ between `previous` and `current`.
+fn render_display(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    current: &DisplayState,
+    previous: &DisplayState,
+    text_style: MonoTextStyle<Rgb565>,
+    symbol_style: MonoTextStyle<Rgb565>,
+) {
+    let fields = [
+        (&previous.city_name, &current.city_name, Point::new(10, 30)),
+        (
+            &previous.weather_temp,
+            &current.weather_temp,
+            Point::new(10, 60),
+        ),
+        (
+            &previous.feels_like_str,
+            &current.feels_like_str,
+            Point::new(10, 90),
+        ),
+        (
+            &previous.weather_desc,
+            &current.weather_desc,
+            Point::new(10, 120),
+        ),
+        (&previous.wind_str, &current.wind_str, Point::new(10, 150)),
+        (&previous.hum_str, &current.hum_str, Point::new(10, 180)),
+    ];
+    for (old, new, position) in fields {
+        redraw_text(display, old, new, position, text_style);
     }
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.pin.set_high().map_err(|_| CustomError)
+    let small_style = MonoTextStyle::new(&FONT_6X10, Rgb565::WHITE);
+    redraw_text(
+        display,
+        &previous.ip_str,
+        &current.ip_str,
+        STATUS_LINE_POSITION,
+        small_style,
+    );
+
+    if previous.weather_icon != current.weather_icon {
+        clear_weather_icon(display, &previous.weather_icon, symbol_style);
+        render_weather_icon(display, &current.weather_icon, symbol_style);
+    }
+}
+
+/// Redraws a single text if it changed, clearing only the bounding box of the old text.
+fn redraw_text(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    old: &str,
+    new: &str,
+    position: Point,
+    style: MonoTextStyle<Rgb565>,
+) {
+    if old == new {
+        return;
+    }
+    let old_area = Text::new(old, position, style).bounding_box();
+    old_area
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+        .draw(display)
+        .ok();
+    Text::new(new, position, style).draw(display).ok();
+}
+
+/// Clears the area of the previously drawn icon (bitmap or fallback symbol).
+fn clear_weather_icon(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    icon_code: &str,
+    symbol_style: MonoTextStyle<Rgb565>,
+) {
+    let clear = PrimitiveStyle::with_fill(Rgb565::BLACK);
+    Rectangle::new(ICON_ORIGIN, Size::new(40, 40))
+        .into_styled(clear)
+        .draw(display)
+        .ok();
+    if !icon_code.is_empty() && get_weather_icon(icon_code).is_none() {
+        Text::new(get_weather_symbol(icon_code), ICON_ORIGIN, symbol_style)
+            .bounding_box()
+            .into_styled(clear)
+            .draw(display)
+            .ok();
+    }
+}
+
+/// Determines the icon color based on the weather condition.
+fn get_weather_icon_color(icon_code: &str) -> Rgb565 {
+    match icon_code.get(..2).unwrap_or_default() {
+        "01" => Rgb565::YELLOW,                // Sun / Moon
+        "02" => Rgb565::YELLOW,                // Few clouds
+        "03" | "04" => Rgb565::CSS_LIGHT_GRAY, // Clouds
+        "09" | "10" => Rgb565::BLUE,           // Rain
+        "11" => Rgb565::YELLOW,                // Thunder
+        "13" => Rgb565::WHITE,                 // Snow
+        "50" => Rgb565::CSS_GRAY,              // Fog
+        _ => Rgb565::WHITE,
+    }
+}
+
+/// Draws the full-color weather icon, the 1-bit icon bitmap if there is no color variant,
+/// or the Unicode symbol if no bitmap exists.
+fn render_weather_icon(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    icon_code: &str,
+    symbol_style: MonoTextStyle<Rgb565>,
+) {
+    if let Some(icon_pixels) = get_weather_icon_rgb(icon_code) {
+        // Blit the whole 40x40 area, the icons come with their own black background
+        let area = Rectangle::new(ICON_ORIGIN, Size::new(40, 40));
+        display
+            .fill_contiguous(&area, icon_pixels.iter().copied())
+            .ok();
+    } else if let Some(icon_data) = get_weather_icon(icon_code) {
+        let icon_color = get_weather_icon_color(icon_code);
+
+        // Draw the icon pixels on the display, decoded on the fly
+        let pixels =
+            icon_bitmap::set_pixels(icon_data).map(|point| Pixel(ICON_ORIGIN + point, icon_color));
+        display.draw_iter(pixels).ok();
+    } else {
+        // Fallback: Use Unicode symbol if icon not found
+        let symbol = get_weather_symbol(icon_code);
+        Text::new(symbol, ICON_ORIGIN, symbol_style)
+            .draw(display)
+            .ok();
+    }
+}
+
+// === ERROR SCREEN ===
+/// Characters per line with FONT_10X20 and a 10 px margin on both sides.
+const ERROR_LINE_CHARS: usize = (240 - 2 * 10) / 10;
+/// Lines below the header that fit on the 320 px high display.
+const ERROR_MAX_LINES: usize = 12;
+
+/// Shows a red "Error" header and the word-wrapped error message below it.
+fn render_error(display: &mut impl DrawTarget<Color = Rgb565>, msg: &str) {
+    let text_style = MonoTextStyle::new(&FONT_10X20, Rgb565::WHITE);
+
+    display.clear(Rgb565::BLACK).ok();
+    Rectangle::new(Point::zero(), Size::new(240, 40))
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+        .draw(display)
+        .ok();
+    Text::new("Error", Point::new(10, 28), text_style)
+        .draw(display)
+        .ok();
+
+    let lines = text_wrap::wrap_text(msg, ERROR_LINE_CHARS, ERROR_MAX_LINES);
+    for (i, line) in lines.iter().enumerate() {
+        let position = Point::new(10, 70 + i as i32 * 20);
+        Text::new(line, position, text_style).draw(display).ok();
     }
 }
 
@@ -219,54 +508,34 @@ fn main() -> anyhow::Result<()> {
     wifi.connect()?;
     info!("WiFi connected!");
     wifi.wait_netif_up()?;
-    let ip_info = wifi.wifi().sta_netif().get_ip_info()?;
-    info!("IP-Adresse: {:?}", ip_info.ip);
+    let mut ip_address = String::new();
+    update_ip(&wifi, &mut ip_address);
+
+    // The clock shows a placeholder until the first sync, SNTP keeps trying in the background
+    let sntp = EspSntp::new_default()?;
+    let mut clock_synced = false;
 
     // ==================== DISPLAY SETUP ====================
     info!("Setting up display...");
-    let sclk = peripherals.pins.gpio18;
-    let mosi = peripherals.pins.gpio23;
-    let cs = peripherals.pins.gpio15;
-    let dc = peripherals.pins.gpio21;
-    let mut rst = PinDriver::output(peripherals.pins.gpio22)?;
-    info!("Pins configured");
-
-    rst.set_low()?;
-    FreeRtos::delay_ms(50);
-    rst.set_high()?;
-    FreeRtos::delay_ms(200);
-
-    let spi_config = Config::new().baudrate(26.MHz().into());
-    let spi_driver = SpiDriver::new(
-        peripherals.spi2,
-        sclk,
-        mosi,
-        None::<AnyIOPin>,
-        &SpiDriverConfig::new(),
+    let mut gpios = GpioPool::new(peripherals.pins);
+    let pin_config = &secrets.display.pins;
+    let pins = DisplayPins::take(
+        &mut gpios,
+        DisplayPinNumbers {
+            sclk: pin_config.sclk,
+            mosi: pin_config.mosi,
+            cs: pin_config.cs,
+            dc: pin_config.dc,
+            rst: pin_config.rst,
+        },
     )?;
-    let spi_device = SpiDeviceDriver::new(spi_driver, Some(cs), &spi_config)?;
+    info!("Pins configured");
 
-    let spi_wrapper = SpiWrapper { spi: spi_device };
-    let dc_wrapper = DcPinWrapper {
-        pin: PinDriver::output(dc.downgrade_output())?,
-    };
+    let mut display = build_display(peripherals.spi2, pins, Orientation::new())?;
 
-    static mut DISPLAY_BUFFER: [u8; 240 * 10 * 2] = [0u8; 240 * 10 * 2];
-    let di = unsafe {
-        mipidsi::interface::SpiInterface::new(
-            spi_wrapper,
-            dc_wrapper,
-            &mut *addr_of_mut!(DISPLAY_BUFFER),
-        )
-    };
-
-    let mut display = Builder::new(ST7789, di)
-        .display_size(240, 320)
-        .display_offset(0, 0)
-        .color_order(ColorOrder::Rgb)
-        .invert_colors(ColorInversion::Inverted)
-        .init(&mut FreeRtos)
-        .map_err(|e| anyhow::anyhow!("Display init failed: {:?}", e))?;
+    // Show panic messages on the display, not only on the serial console
+    // SAFETY: `display` stays in place until the end of `main`, the guard is dropped before it
+    let _panic_screen = unsafe { display_hal::register_panic_display(&mut display) };
 
     info!("Display initialized!");
     display.clear(Rgb565::BLACK).ok();
@@ -275,112 +544,139 @@ fn main() -> anyhow::Result<()> {
     // ==================== MAIN LOOP ====================
     let text_style = MonoTextStyle::new(&FONT_10X20, Rgb565::WHITE);
     let symbol_style = MonoTextStyle::new(&PROFONT_24_POINT, Rgb565::YELLOW);
+    let mut previous_state = DisplayState::new();
+    let mut error_shown = false;
+    // The loop wakes every `tick_ms`. The clock follows the wall clock, so that it redraws
+    // right after a second change. The weather follows the uptime, which does not jump
+    // when SNTP sets the time.
+    let schedule = &secrets.schedule;
+    let loop_started = Instant::now();
+    let mut clock_cadence = Cadence::new(Duration::from_millis(schedule.clock_redraw_ms.into()));
+    let mut weather_cadence =
+        Cadence::new(Duration::from_secs(schedule.weather_interval_mins * 60));
 
     loop {
+        if !clock_synced && sntp.get_sync_status() == SyncStatus::Completed {
+            info!("Time synchronized!");
+            clock_synced = true;
+        }
+
+        // The error screen covers the clock, it comes back with the weather
+        let since_the_epoch = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        if clock_cadence.poll(since_the_epoch) && !error_shown {
+            let time_str = if clock_synced {
+                let (_, _, _, hour, minute, second) =
+                    time_utils::utc_to_berlin(since_the_epoch.as_secs() as i64);
+                time_utils::format_time(hour, minute, second)
+            } else {
+                CLOCK_UNSYNCED.to_string()
+            };
+            redraw_text(
+                &mut display,
+                &previous_state.time_str,
+                &time_str,
+                CLOCK_POSITION,
+                text_style,
+            );
+            previous_state.time_str = time_str;
+        }
+
+        if !weather_cadence.poll(loop_started.elapsed()) {
+            FreeRtos::delay_ms(schedule.tick_ms);
+            continue;
+        }
+
         // Reconnect Wi-Fi if disconnected
         if !wifi.is_connected()? {
             warn!("WiFi disconnected, reconnecting...");
-            wifi.connect()?;
-            wifi.wait_netif_up()?;
+            if let Err(e) = reconnect_wifi_with_backoff(&mut wifi, WIFI_MAX_RETRIES) {
+                error!("WiFi reconnect failed, rebooting: {}", e);
+                esp_idf_svc::hal::reset::restart();
+            }
+            // The router may hand out another address
+            update_ip(&wifi, &mut ip_address);
         }
 
         info!("Fetching weather data...");
-        match get_weather(&secrets.openweather.api_key, &secrets.openweather.city) {
-            Ok(weather) => {
-                info!("Successfully fetched weather for {}", weather.name);
-                display.clear(Rgb565::BLACK).ok();
-
-                // Inside the main loop, after fetching weather data
-                let icon_code = &weather.weather[0].icon;
-
-                // Determine icon color based on type
-                let icon_color = match &icon_code[..2] {
-                    "01" => Rgb565::YELLOW,                // Sun / Moon
-                    "02" => Rgb565::YELLOW,                // Few clouds
-                    "03" | "04" => Rgb565::CSS_LIGHT_GRAY, // Clouds
-                    "09" | "10" => Rgb565::BLUE,           // Rain
-                    "11" => Rgb565::YELLOW,                // Thunder
-                    "13" => Rgb565::WHITE,                 // Snow
-                    "50" => Rgb565::CSS_GRAY,              // Fog
-                    _ => Rgb565::WHITE,
-                };
-
-                // ---------------- Display City ----------------
-                Text::new(&weather.name, Point::new(10, 30), text_style)
-                    .draw(&mut display)
-                    .ok();
-
-                // ---------------- Display Temperature ----------------
-                let temp_str = format!("{:.1}°C", weather.main.temp);
-                Text::new(&temp_str, Point::new(10, 60), text_style)
-                    .draw(&mut display)
-                    .ok();
-
-                // ---------------- Display Weather Description ----------------
-                Text::new(
-                    &weather.weather[0].description,
-                    Point::new(10, 90),
-                    text_style,
-                )
-                .draw(&mut display)
-                .ok();
-
-                // ---------------- Display Wind ----------------
-                let wind_str = format!("Wind: {:.1} m/s", weather.wind.speed);
-                Text::new(&wind_str, Point::new(10, 120), text_style)
-                    .draw(&mut display)
-                    .ok();
-
-                // ---------------- Display Humidity ----------------
-                let humidity_str = format!("Feuchte: {}%", weather.main.humidity);
-                Text::new(&humidity_str, Point::new(10, 150), text_style)
-                    .draw(&mut display)
-                    .ok();
-
-                // ---------------- Display Weather Icon ----------------
-                if let Some(icon_data) = get_weather_icon(&weather.weather[0].icon) {
-                    // Icon dimensions
-                    let icon_width = 40;
-                    let icon_height = 40;
-
-                    // Prepare pixel vector for embedded-graphics
-                    let mut pixels = Vec::with_capacity(icon_width * icon_height);
-
-                    for y in 0..icon_height {
-                        for x in 0..icon_width {
-                            let byte_index = y * (icon_width / 8) + (x / 8);
-                            let bit_index = 7 - (x % 8);
-                            let pixel_on = (icon_data[byte_index] >> bit_index) & 1 == 1;
-
-                            if pixel_on {
-                                pixels.push(Pixel(
-                                    Point::new(160 + x as i32, 70 + y as i32),
-                                    icon_color,
-                                ));
-                            }
-                        }
+        match get_weather_retrying(&secrets.openweather, WEATHER_MAX_ATTEMPTS) {
+            Ok(weather) => match weather.weather.first() {
+                // A partial response without conditions is skipped instead of panicking
+                None => warn!(
+                    "Weather for {} contains no conditions, skipping this update",
+                    weather.name
+                ),
+                Some(condition) => {
+                    info!("Successfully fetched weather for {}", weather.name);
+
+                    // After the error screen everything has to be repainted, the clock
+                    // follows with its next redraw
+                    if error_shown {
+                        display.clear(Rgb565::BLACK).ok();
+                        previous_state = DisplayState::new();
+                        error_shown = false;
+                        clock_cadence.trigger();
                     }
 
-                    // Draw the icon pixels on the display
-                    display.draw_iter(pixels.iter().cloned()).ok();
-                } else {
-                    // Fallback: Use Unicode symbol if icon not found
-                    let symbol = get_weather_symbol(&weather.weather[0].icon);
-                    Text::new(symbol, Point::new(160, 70), symbol_style)
-                        .draw(&mut display)
-                        .ok();
+                    let current_state = DisplayState {
+                        city_name: geocoding::location_label(&weather.name, &weather.sys.country),
+                        weather_temp: format!(
+                            "{:.1}{}",
+                            weather.main.temp,
+                            secrets.openweather.temperature_unit()
+                        ),
+                        feels_like_str: match weather.main.feels_like {
+                            Some(feels_like) => format!(
+                                "Gefühlt: {:.1}{}",
+                                feels_like,
+                                secrets.openweather.temperature_unit()
+                            ),
+                            None => String::new(),
+                        },
+                        weather_desc: condition.description.clone(),
+                        weather_icon: condition.icon.clone(),
+                        wind_str: match &weather.wind {
+                            Some(wind) => format!(
+                                "Wind: {:.1} {}",
+                                wind.speed,
+                                secrets.openweather.wind_speed_unit()
+                            ),
+                            None => "Wind: --".to_string(),
+                        },
+                        hum_str: match weather.main.humidity {
+                            Some(humidity) => format!("Feuchte: {}%", humidity),
+                            None => "Feuchte: --".to_string(),
+                        },
+                        time_str: previous_state.time_str.clone(),
+                        ip_str: if secrets.display.show_ip && !ip_address.is_empty() {
+                            format!("IP: {}", ip_address)
+                        } else {
+                            String::new()
+                        },
+                    };
+
+                    // Only the fields that changed are redrawn
+                    if current_state != previous_state {
+                        render_display(
+                            &mut display,
+                            &current_state,
+                            &previous_state,
+                            text_style,
+                            symbol_style,
+                        );
+                        previous_state = current_state;
+                    }
                 }
-            }
+            },
             Err(e) => {
                 error!("Failed to fetch weather: {}", e);
-                display.clear(Rgb565::RED).ok(); // Signal error on display
-                Text::new("Error", Point::new(10, 30), text_style)
-                    .draw(&mut display)
-                    .ok();
+                render_error(&mut display, &format!("{:#}", e)); // Signal error on display
+                error_shown = true;
             }
         }
 
-        info!("Waiting for next update (15 minutes)...");
-        FreeRtos::delay_ms(15 * 60 * 1000); // Wait 15 minutes
+        info!(
+            "Waiting for next update ({} minutes)...",
+            schedule.weather_interval_mins
+        );
     }
 }
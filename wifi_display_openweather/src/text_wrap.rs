@@ -0,0 +1,75 @@
+// text_wrap.rs
+
+/// Marker appended to the last line when text had to be cut off
+const ELLIPSIS: &str = "...";
+
+/// Word-wraps `text` into lines of at most `max_chars` characters.
+/// Words longer than a line are split. If more than `max_lines` lines would be
+/// needed, the last visible line ends with "..." instead.
+pub fn wrap_text(text: &str, max_chars: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        // Move to a new line if the word does not fit behind the current one
+        let line_len = line.chars().count();
+        if line_len > 0 && line_len + 1 + word.len() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        // Split words that are longer than a whole line
+        while word.len() > max_chars {
+            let rest = word.split_off(max_chars);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            let keep = max_chars.saturating_sub(ELLIPSIS.len());
+            *last = last.chars().take(keep).collect::<String>() + ELLIPSIS;
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(
+            wrap_text("OWM 401: Invalid API key", 12, 5),
+            vec!["OWM 401:", "Invalid API", "key"]
+        );
+        // Short text stays on one line
+        assert_eq!(wrap_text("Error", 22, 5), vec!["Error"]);
+        assert!(wrap_text("", 22, 5).is_empty());
+    }
+
+    #[test]
+    fn test_wrap_text_long_word() {
+        assert_eq!(wrap_text("abcdefghij", 4, 5), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_wrap_text_truncates() {
+        let lines = wrap_text("one two three four five six", 9, 2);
+        assert_eq!(lines, vec!["one two", "three..."]);
+        assert!(lines.iter().all(|line| line.chars().count() <= 9));
+    }
+}
@@ -0,0 +1,253 @@
+// time_utils.rs
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike, Utc, Weekday};
+
+/// Describes a DST changeover as "the nth weekday of a month at a given UTC hour".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionRule {
+    /// The month of the changeover (1-12).
+    pub month: u32,
+    /// The occurrence of the weekday within the month (1-4, 5 = last).
+    pub week: u32,
+    /// The weekday of the changeover.
+    pub weekday: Weekday,
+    /// The hour (UTC) at which the changeover happens.
+    pub utc_hour: u32,
+}
+
+impl TransitionRule {
+    /// Returns the day of the month on which the changeover happens in `year`.
+    pub fn day_in(&self, year: i32) -> u32 {
+        let date = if self.week >= 5 {
+            last_weekday_of_month(year, self.month, self.weekday)
+        } else {
+            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, self.week as u8)
+        };
+        date.map(|date| date.day()).unwrap_or(1)
+    }
+}
+
+/// Finds the last occurrence of `weekday` in the given month.
+/// Walks backwards from the last day of the month, so it is exact for every year.
+pub fn last_weekday_of_month(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+
+    // The day before the first of the next month is the last day of this month
+    let mut date = NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()?;
+    while date.weekday() != weekday {
+        date = date.pred_opt()?;
+    }
+    Some(date)
+}
+
+/// The start and end of daylight saving time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DstRules {
+    /// When daylight saving time starts.
+    pub start: TransitionRule,
+    /// When daylight saving time ends.
+    pub end: TransitionRule,
+    /// The additional offset during daylight saving time in seconds.
+    pub dst_offset_secs: i64,
+}
+
+/// Describes a time zone by its base UTC offset and optional DST rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeZoneRules {
+    /// The base (standard time) UTC offset in seconds.
+    pub base_offset_secs: i64,
+    /// The DST rules, `None` if the zone does not observe DST.
+    pub dst: Option<DstRules>,
+}
+
+/// Berlin (CET/CEST).
+/// Summer: Last Sunday on March 1:00 UTC to last Sunday on October 1:00 UTC
+pub const BERLIN: TimeZoneRules = TimeZoneRules {
+    base_offset_secs: 3600,
+    dst: Some(DstRules {
+        start: TransitionRule {
+            month: 3,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        end: TransitionRule {
+            month: 10,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        dst_offset_secs: 3600,
+    }),
+};
+
+impl TimeZoneRules {
+    /// Calculates whether a given UTC time is in Daylight Saving Time.
+    pub fn is_dst(&self, year: i32, month: u32, day: u32, hour: u32) -> bool {
+        let dst = match self.dst {
+            Some(dst) => dst,
+            None => return false,
+        };
+
+        let now = (month, day, hour);
+        let start = (dst.start.month, dst.start.day_in(year), dst.start.utc_hour);
+        let end = (dst.end.month, dst.end.day_in(year), dst.end.utc_hour);
+
+        if start <= end {
+            // Northern hemisphere: DST lies within the calendar year
+            now >= start && now < end
+        } else {
+            // Southern hemisphere: DST spans the turn of the year
+            now >= start || now < end
+        }
+    }
+
+    /// Returns the total UTC offset in seconds for the given UTC time.
+    pub fn offset_secs(&self, year: i32, month: u32, day: u32, hour: u32) -> i64 {
+        match self.dst {
+            Some(dst) if self.is_dst(year, month, day, hour) => {
+                self.base_offset_secs + dst.dst_offset_secs
+            }
+            _ => self.base_offset_secs,
+        }
+    }
+}
+
+/// Converts UTC time to local time according to the given time zone rules
+pub fn utc_to_local(utc_timestamp: i64, tz: TimeZoneRules) -> (i32, u32, u32, u32, u32, u32) {
+    let utc_time = Utc.timestamp_opt(utc_timestamp, 0).unwrap();
+
+    // Determine the offset (including DST) for this instant
+    let offset_secs = tz.offset_secs(
+        utc_time.year(),
+        utc_time.month(),
+        utc_time.day(),
+        utc_time.hour(),
+    );
+
+    // Add offset
+    let local_timestamp = utc_timestamp + offset_secs;
+    let local_time = Utc.timestamp_opt(local_timestamp, 0).unwrap();
+
+    (
+        local_time.year(),
+        local_time.month(),
+        local_time.day(),
+        local_time.hour(),
+        local_time.minute(),
+        local_time.second(),
+    )
+}
+
+/// Converts UTC time to Berlin time (CET/CEST)
+pub fn utc_to_berlin(utc_timestamp: i64) -> (i32, u32, u32, u32, u32, u32) {
+    utc_to_local(utc_timestamp, BERLIN)
+}
+
+/// Formats the time as a string "HH:MM:SS"
+pub fn format_time(hour: u32, minute: u32, second: u32) -> String {
+    format!("{:02}:{:02}:{:02}", hour, minute, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dst_calculation() {
+        // March 15, 2024, 10:00 UTC -> CET (before changeover)
+        assert!(!BERLIN.is_dst(2024, 3, 15, 10));
+
+        // March 31, 2024, 03:00 UTC -> CEST (after changeover)
+        assert!(BERLIN.is_dst(2024, 3, 31, 3));
+
+        // July 15, 2024, 12:00 UTC -> CEST
+        assert!(BERLIN.is_dst(2024, 7, 15, 12));
+
+        // October 27, 2024, 02:00 UTC -> CET (after changeover)
+        assert!(!BERLIN.is_dst(2024, 10, 27, 4));
+
+        // December 15, 2024, 18:00 UTC -> CET
+        assert!(!BERLIN.is_dst(2024, 12, 15, 18));
+    }
+
+    #[test]
+    fn test_last_sunday_regression() {
+        // Known changeover dates (last Sunday in March / October)
+        let known = [
+            (2023, 26, 29),
+            (2024, 31, 27),
+            (2027, 28, 31),
+            // 2100 is not a leap year, the Gauss approximation is off by one day here
+            (2100, 28, 31),
+        ];
+
+        for (year, march_day, october_day) in known {
+            assert_eq!(
+                last_weekday_of_month(year, 3, Weekday::Sun).map(|d| d.day()),
+                Some(march_day)
+            );
+            assert_eq!(
+                last_weekday_of_month(year, 10, Weekday::Sun).map(|d| d.day()),
+                Some(october_day)
+            );
+
+            // CET before, CEST after the March changeover
+            assert!(!BERLIN.is_dst(year, 3, march_day - 1, 12));
+            assert!(BERLIN.is_dst(year, 3, march_day, 2));
+
+            // CEST before, CET after the October changeover
+            assert!(BERLIN.is_dst(year, 10, october_day - 1, 12));
+            assert!(!BERLIN.is_dst(year, 10, october_day, 3));
+        }
+
+        // December must not overflow into the next year
+        assert_eq!(
+            last_weekday_of_month(2024, 12, Weekday::Tue).map(|d| d.day()),
+            Some(31)
+        );
+    }
+
+    #[test]
+    fn test_eu_changeover_at_one_utc() {
+        // 2024-03-31: 00:59:59 UTC is 01:59:59 CET, one second later it is 03:00:00 CEST
+        assert_eq!(utc_to_berlin(1_711_846_799), (2024, 3, 31, 1, 59, 59));
+        assert_eq!(utc_to_berlin(1_711_846_800), (2024, 3, 31, 3, 0, 0));
+
+        // 2024-10-27: 00:59:59 UTC is 02:59:59 CEST, one second later it is 02:00:00 CET
+        assert_eq!(utc_to_berlin(1_729_990_799), (2024, 10, 27, 2, 59, 59));
+        assert_eq!(utc_to_berlin(1_729_990_800), (2024, 10, 27, 2, 0, 0));
+
+        // The hours from 01:00 to 03:00 UTC already follow the new offset
+        for (month, day) in [(3, 31), (10, 27)] {
+            let dst_before = month == 10;
+            assert_eq!(BERLIN.is_dst(2024, month, day, 0), dst_before);
+            for hour in 1..=3 {
+                assert_eq!(BERLIN.is_dst(2024, month, day, hour), !dst_before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_utc_to_local() {
+        // 2024-07-15 12:00:00 UTC
+        let summer = 1_721_044_800;
+        assert_eq!(utc_to_local(summer, BERLIN), (2024, 7, 15, 14, 0, 0));
+
+        // 2024-01-15 03:00:00 UTC
+        let winter = 1_705_287_600;
+        assert_eq!(utc_to_local(winter, BERLIN), (2024, 1, 15, 4, 0, 0));
+
+        // The thin wrapper must agree with the Berlin preset
+        assert_eq!(utc_to_berlin(summer), utc_to_local(summer, BERLIN));
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(7, 5, 9), "07:05:09");
+        assert_eq!(format_time(23, 59, 59), "23:59:59");
+    }
+}
@@ -1,5 +1,15 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Felder, die diese App in secrets.toml braucht, als "abschnitt.feld"
+/// Bei "a|b" genügt eines der beiden Felder.
+const REQUIRED_FIELDS: &[&str] = &[
+    "wifi.ssid",
+    "wifi.password",
+    "openweather.api_key",
+    "openweather.city",
+];
 
 fn main() {
     // WICHTIG: ESP-IDF Build-System initialisieren
@@ -9,7 +19,13 @@ fn main() {
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let workspace_root = PathBuf::from(&manifest_dir).parent().unwrap().to_path_buf();
 
-    let secrets_path = workspace_root.join("secrets.toml");
+    // Pfad zur secrets.toml, kann mit SECRETS_FILE überschrieben werden
+    // (relative Pfade gelten ab Workspace-Root)
+    println!("cargo:rerun-if-env-changed=SECRETS_FILE");
+    let secrets_path = match env::var("SECRETS_FILE") {
+        Ok(path) => workspace_root.join(path),
+        Err(_) => workspace_root.join("secrets.toml"),
+    };
 
     println!("cargo:rerun-if-changed={}", secrets_path.display());
 
@@ -23,8 +39,65 @@ fn main() {
             \n\
             Erstelle die Datei:\n\
             cp secrets.toml.example secrets.toml\n\
+            \n\
+            oder setze SECRETS_FILE auf eine vorhandene Datei.\n\
             ",
             secrets_path.display()
         );
     }
+
+    // Pflichtfelder prüfen, damit eine unvollständige secrets.toml beim Bauen
+    // auffällt und nicht erst als Parse-Fehler auf dem Gerät
+    check_required_fields(&secrets_path);
+
+    // Aufgelösten Pfad für `include_str!` in secrets.rs bereitstellen
+    println!("cargo:rustc-env=SECRETS_FILE={}", secrets_path.display());
+}
+
+/// Bricht den Build ab, wenn secrets.toml kein gültiges TOML ist oder Pflichtfelder fehlen
+fn check_required_fields(secrets_path: &Path) {
+    let content = fs::read_to_string(secrets_path).unwrap_or_else(|e| {
+        panic!(
+            "\n\n❌ ERROR: {} nicht lesbar: {}\n",
+            secrets_path.display(),
+            e
+        )
+    });
+    let secrets: toml::Table = toml::from_str(&content).unwrap_or_else(|e| {
+        panic!(
+            "\n\n❌ ERROR: {} ist kein gültiges TOML:\n{}\n",
+            secrets_path.display(),
+            e
+        )
+    });
+
+    let missing: Vec<String> = REQUIRED_FIELDS
+        .iter()
+        .filter(|fields| !fields.split('|').any(|field| has_field(&secrets, field)))
+        .map(|fields| fields.replace('|', " oder "))
+        .collect();
+    if !missing.is_empty() {
+        panic!(
+            "\n\n\
+            ❌ ERROR: In {} fehlt:\n\
+            \n\
+            {}\n\
+            \n\
+            Vorlage: secrets.toml.example\n\
+            ",
+            secrets_path.display(),
+            missing.join("\n")
+        );
+    }
+}
+
+/// Ob `field` ("abschnitt.feld") in der Tabelle vorhanden ist
+fn has_field(secrets: &toml::Table, field: &str) -> bool {
+    match field.split_once('.') {
+        Some((section, key)) => secrets
+            .get(section)
+            .and_then(|section| section.get(key))
+            .is_some(),
+        None => secrets.contains_key(field),
+    }
 }
@@ -1,41 +1,32 @@
 // === IMPORTS ===
-use crate::secrets::Secrets;
-use core::ptr::addr_of_mut;
+use crate::secrets::{OpenWeatherConfig, Secrets};
+use display_hal::{build_display, DisplayPinNumbers, DisplayPins, GpioPool};
 use embedded_graphics::{
     mono_font::{iso_8859_1::FONT_10X20, MonoTextStyle, MonoTextStyleBuilder},
     pixelcolor::Rgb565,
     prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
     text::Text,
 };
-use embedded_hal::digital::OutputPin as OutputPinTrait;
-use embedded_hal::spi::SpiDevice;
-use embedded_svc::http::client::Client;
-use esp_idf_svc::hal::{
-    delay::FreeRtos,
-    gpio::{AnyIOPin, OutputPin, PinDriver},
-    peripherals::Peripherals,
-    prelude::*,
-    spi::{config::Config, SpiDeviceDriver, SpiDriver, SpiDriverConfig},
-};
-use esp_idf_svc::http::client::{Configuration as HttpConfiguration, EspHttpConnection};
-use esp_idf_svc::sntp::{EspSntp, SyncStatus};
+use esp_idf_svc::hal::{delay::FreeRtos, peripherals::Peripherals};
+use esp_idf_svc::sntp::{EspSntp, SntpConf, SyncMode, SyncStatus};
 use esp_idf_svc::wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi};
 use esp_idf_svc::{eventloop::EspSystemEventLoop, nvs::EspDefaultNvsPartition};
 use log::*;
-use mipidsi::{
-    models::ST7789,
-    options::{ColorInversion, ColorOrder},
-    Builder,
-};
+use mipidsi::options::Orientation;
 use profont::PROFONT_24_POINT;
 use serde::Deserialize;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use weather_common::scheduler::Cadence;
+use weather_common::{backoff, geocoding, http_utils, icon_bitmap};
 
 mod secrets;
 mod time_utils;
 mod weather_icons;
+mod wifi_retry;
 
-use weather_icons::get_weather_icon;
+use weather_icons::{get_weather_icon, get_weather_icon_rgb};
+use wifi_retry::WifiRetry;
 
 // === OPENWEATHERMAP DATA STRUCTURES ===
 /// Represents the overall weather response from the OpenWeatherMap API.
@@ -45,10 +36,14 @@ struct WeatherResponse {
     weather: Vec<Weather>,
     /// The main weather data (temperature, humidity, etc.).
     main: Main,
-    /// The wind data.
-    wind: Wind,
+    /// The wind data, missing in some station responses.
+    #[serde(default)]
+    wind: Option<Wind>,
     /// The name of the city.
     name: String,
+    /// The country, sunrise and sunset of the city.
+    #[serde(default)]
+    sys: Sys,
 }
 
 /// Represents a single weather condition.
@@ -65,8 +60,12 @@ struct Weather {
 struct Main {
     /// The temperature in Celsius.
     temp: f32,
-    /// The humidity in percent.
-    humidity: i32,
+    /// The perceived temperature in Celsius, missing in some station responses.
+    #[serde(default)]
+    feels_like: Option<f32>,
+    /// The humidity in percent, missing in some station responses.
+    #[serde(default)]
+    humidity: Option<i32>,
 }
 
 /// Represents the wind data.
@@ -76,6 +75,20 @@ struct Wind {
     speed: f32,
 }
 
+/// Represents the location details of the response.
+#[derive(Deserialize, Debug, Default)]
+struct Sys {
+    /// The ISO 3166 country code of the city, e.g. "US".
+    #[serde(default)]
+    country: String,
+    /// The sunrise of the current day (UTC timestamp).
+    #[serde(default)]
+    sunrise: i64,
+    /// The sunset of the current day (UTC timestamp).
+    #[serde(default)]
+    sunset: i64,
+}
+
 // === WEATHER SYMBOL MAPPING ===
 /// Returns a weather symbol for a given icon code.
 fn get_weather_symbol(icon_code: &str) -> &'static str {
@@ -98,108 +111,291 @@ fn get_weather_symbol(icon_code: &str) -> &'static str {
 
 // === WEATHER FETCH FUNCTION ===
 /// Fetches the weather from the OpenWeatherMap API.
-fn get_weather(api_key: &str, city: &str) -> anyhow::Result<WeatherResponse> {
+fn get_weather(config: &OpenWeatherConfig) -> anyhow::Result<WeatherResponse> {
+    // Coordinates are more precise than the city name, prefer them when configured
+    let location = match config.coordinates() {
+        Some((lat, lon)) => format!("lat={}&lon={}", lat, lon),
+        None => format!("q={}", http_utils::urlencode(&config.city)),
+    };
+
     let url = format!(
-        "https://api.openweathermap.org/data/2.5/weather?q={}&appid={}&units=metric&lang=en",
-        city, api_key
+        "https://api.openweathermap.org/data/2.5/weather?{}&appid={}&units={}&lang={}",
+        location,
+        http_utils::urlencode(&config.api_key),
+        http_utils::urlencode(config.units()),
+        http_utils::urlencode(config.lang())
     );
 
-    let connection = EspHttpConnection::new(&HttpConfiguration {
-        use_global_ca_store: true,
-        crt_bundle_attach: Some(esp_idf_sys::esp_crt_bundle_attach),
-        timeout: Some(core::time::Duration::from_secs(30)),
-        ..Default::default()
-    })?;
-    let mut client = Client::wrap(connection);
+    // OWM reports errors as a JSON body, e.g. `{"cod":401,"message":"Invalid API key"}`
+    let result =
+        http_utils::http_get_json(&url, config.http_timeout()).map_err(OwmError::from_http_error);
+
+    // An unknown city name is a configuration error, list what the name could have meant
+    if let Err(e) = &result {
+        let not_found = e
+            .downcast_ref::<OwmError>()
+            .is_some_and(|owm_error| owm_error.cod == 404);
+        if not_found && config.coordinates().is_none() {
+            log_city_candidates(config);
+        }
+    }
+    result
+}
 
-    let request = client.get(&url)?;
-    let mut response = request.submit()?;
+/// Resolves the configured city with the OpenWeatherMap geocoding API and logs the matches,
+/// so the right one can be put into `city = "City,CC"` or `lat`/`lon`.
+fn log_city_candidates(config: &OpenWeatherConfig) {
+    let url = geocoding::direct_url(&config.city, &config.api_key);
+    match http_utils::http_get_json::<Vec<geocoding::GeoCandidate>>(&url, config.http_timeout()) {
+        Ok(candidates) if candidates.is_empty() => {
+            warn!(
+                "City '{}' not found by the geocoding API either",
+                config.city
+            );
+        }
+        Ok(candidates) => {
+            warn!("City '{}' not found, candidates:", config.city);
+            for candidate in &candidates {
+                warn!("  {}", candidate);
+            }
+        }
+        Err(e) => warn!("City lookup for '{}' failed: {}", config.city, e),
+    }
+}
 
-    let status = response.status();
-    info!("Weather API response status: {}", status);
+// === DAY/NIGHT GLYPH ===
+/// Top-left corner of the sun/moon glyph, right of the time.
+const DAY_NIGHT_ORIGIN: Point = Point::new(200, 24);
 
-    let mut body_buf = vec![0u8; 4096];
-    let bytes_read = response.read(&mut body_buf)?;
+/// Width and height of the sun/moon glyph in pixels.
+const DAY_NIGHT_SIZE: u32 = 14;
 
-    let body_str = std::str::from_utf8(&body_buf[..bytes_read])?;
+/// Draws a small sun (day) or crescent moon (night) at `DAY_NIGHT_ORIGIN`.
+fn draw_day_night_glyph(display: &mut impl DrawTarget<Color = Rgb565>, daytime: bool) {
+    let area = Rectangle::new(DAY_NIGHT_ORIGIN, Size::new(DAY_NIGHT_SIZE, DAY_NIGHT_SIZE));
+    area.into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+        .draw(display)
+        .ok();
 
-    let weather: WeatherResponse = serde_json::from_str(body_str)?;
-    Ok(weather)
+    // Clip to the glyph area, the moon's shadow disc reaches beyond it
+    let mut display = display.clipped(&area);
+    if daytime {
+        Circle::new(DAY_NIGHT_ORIGIN, DAY_NIGHT_SIZE)
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::YELLOW))
+            .draw(&mut display)
+            .ok();
+    } else {
+        Circle::new(DAY_NIGHT_ORIGIN, DAY_NIGHT_SIZE)
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::CSS_LIGHT_GRAY))
+            .draw(&mut display)
+            .ok();
+        Circle::new(DAY_NIGHT_ORIGIN + Point::new(5, -2), DAY_NIGHT_SIZE)
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+            .draw(&mut display)
+            .ok();
+    }
 }
 
-// === CUSTOM ERROR TYPE ===
-/// A custom error type for the SPI and digital pin wrappers.
-#[derive(Debug)]
-struct CustomError;
+// === SNTP ===
+/// Maximum time to wait for the initial SNTP synchronization at boot.
+const SNTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Shown instead of the date while the clock is not synchronized.
+/// Not longer than a date line, so the date fully overwrites it once synchronized.
+const CLOCK_UNSYNCED_BANNER: &str = "No time sync";
 
-impl embedded_hal::spi::Error for CustomError {
-    fn kind(&self) -> embedded_hal::spi::ErrorKind {
-        embedded_hal::spi::ErrorKind::Other
+/// Waits until SNTP has synchronized the system clock.
+/// Gives up after `timeout` so that a blocked NTP server does not hang the boot.
+/// SNTP keeps running in the background, so the clock may still be synchronized later.
+fn wait_for_sntp(sntp: &EspSntp, timeout: Duration) -> anyhow::Result<()> {
+    let start = Instant::now();
+    while sntp.get_sync_status() != SyncStatus::Completed {
+        if start.elapsed() >= timeout {
+            anyhow::bail!("SNTP sync timed out after {}s", timeout.as_secs());
+        }
+        FreeRtos::delay_ms(100);
     }
+    info!("Time synchronized after {} ms", start.elapsed().as_millis());
+    Ok(())
 }
 
-impl embedded_hal::digital::Error for CustomError {
-    fn kind(&self) -> embedded_hal::digital::ErrorKind {
-        embedded_hal::digital::ErrorKind::Other
+/// Start SNTP with smooth corrections, resynchronizing every `resync_interval`
+/// The first sync sets the clock, later ones slew it gradually (adjtime) so that the
+/// displayed seconds never jump. Every resync logs how far the clock was off and its drift.
+fn start_sntp(resync_interval: Duration) -> anyhow::Result<EspSntp<'static>> {
+    // SAFETY: only stores the interval, the SNTP client reads it when scheduling the next sync
+    unsafe {
+        esp_idf_svc::sys::sntp_set_sync_interval(resync_interval.as_millis() as u32);
     }
+
+    let conf = SntpConf {
+        sync_mode: SyncMode::Smooth,
+        ..Default::default()
+    };
+    // NTP time of the previous sync
+    let mut last_sync: Option<Duration> = None;
+    let sntp = EspSntp::new_with_callback(&conf, move |synced: Duration| {
+        // In smooth mode the clock has not been corrected yet when the callback runs
+        let system = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let offset_ms = system.as_millis() as i64 - synced.as_millis() as i64;
+        match last_sync {
+            Some(previous) => {
+                let elapsed_secs = synced.saturating_sub(previous).as_secs();
+                info!(
+                    "SNTP resync: clock was {} ms {} after {} min ({:.1} ppm), correcting",
+                    offset_ms.abs(),
+                    if offset_ms >= 0 { "ahead" } else { "behind" },
+                    elapsed_secs / 60,
+                    time_utils::clock_drift_ppm(offset_ms, elapsed_secs).unwrap_or_default()
+                );
+            }
+            None => info!("SNTP sync: clock set"),
+        }
+        last_sync = Some(synced);
+    })?;
+    Ok(sntp)
 }
 
-// === SPI WRAPPER ===
-/// A wrapper around the SPI device driver to implement the `embedded-hal` traits.
-struct SpiWrapper<'a> {
-    spi: SpiDeviceDriver<'a, SpiDriver<'a>>,
+// === WIFI CONNECT ===
+/// Maximum time to wait for the WiFi connection at boot, the device starts offline after it.
+const WIFI_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Starts connecting to the configured network without waiting for the connection.
+/// The driver connects in the background, `wait_for_wifi` or `is_up` tell when it is done.
+fn start_wifi_connect(wifi: &mut BlockingWifi<EspWifi<'static>>) -> anyhow::Result<()> {
+    // Ends a pending attempt, fails harmlessly if there is none
+    let _ = wifi.wifi_mut().disconnect();
+    wifi.wifi_mut().connect()?;
+    Ok(())
 }
 
-impl embedded_hal::spi::ErrorType for SpiWrapper<'_> {
-    type Error = CustomError;
+/// Waits until WiFi is connected and has an IP address.
+/// Gives up after `timeout` so that a missing access point does not hang the boot.
+fn wait_for_wifi(wifi: &BlockingWifi<EspWifi<'static>>, timeout: Duration) -> anyhow::Result<()> {
+    let start = Instant::now();
+    while !wifi.is_up()? {
+        if start.elapsed() >= timeout {
+            anyhow::bail!("WiFi connection timed out after {}s", timeout.as_secs());
+        }
+        FreeRtos::delay_ms(100);
+    }
+    Ok(())
 }
 
-impl SpiDevice for SpiWrapper<'_> {
-    fn transaction(
-        &mut self,
-        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
-    ) -> Result<(), Self::Error> {
-        for op in operations {
-            match op {
-                embedded_hal::spi::Operation::Write(data) => {
-                    if !data.is_empty() {
-                        self.spi.write(data).map_err(|_| CustomError)?;
-                    }
-                }
-                embedded_hal::spi::Operation::Transfer(read, write) => {
-                    if !write.is_empty() {
-                        self.spi.transfer(read, write).map_err(|_| CustomError)?;
-                    }
-                }
-                embedded_hal::spi::Operation::TransferInPlace(data) => {
-                    if !data.is_empty() {
-                        let temp = data.to_vec();
-                        self.spi.transfer(data, &temp).map_err(|_| CustomError)?;
-                    }
-                }
-                _ => {}
-            }
+// === WIFI RECONNECT ===
+/// Maximum number of WiFi reconnect attempts before giving up.
+const WIFI_MAX_RETRIES: u32 = 8;
+
+/// Reconnects to WiFi, retrying with exponential backoff (1s, 2s, 4s, ... capped at 60s).
+/// Returns an error once the retry budget is exhausted so the caller can decide whether to reboot.
+fn reconnect_wifi_with_backoff(
+    wifi: &mut BlockingWifi<EspWifi<'static>>,
+    max_retries: u32,
+) -> anyhow::Result<()> {
+    backoff::retry_with_backoff(
+        max_retries,
+        || {
+            wifi.connect()?;
+            wifi.wait_netif_up()
+        },
+        |delay| FreeRtos::delay_ms(delay.as_millis() as u32),
+    )
+}
+
+// === WEATHER API ERROR ===
+/// Error body returned by OpenWeatherMap for non-200 responses.
+/// `cod` is sent as a number for some errors and as a string for others.
+#[derive(Deserialize, Debug)]
+struct OwmError {
+    #[serde(deserialize_with = "deserialize_owm_code")]
+    cod: u16,
+    message: String,
+}
+
+impl OwmError {
+    /// Builds the error from a non-200 response, falling back to the HTTP status
+    /// if the body is not an OWM error object.
+    fn from_response(status: u16, body: &str) -> Self {
+        serde_json::from_str(body).unwrap_or_else(|_| OwmError {
+            cod: status,
+            message: "unexpected response".to_string(),
+        })
+    }
+
+    /// Replaces an `HttpStatusError` with the OWM error object from its body,
+    /// other errors are passed through unchanged.
+    fn from_http_error(error: anyhow::Error) -> anyhow::Error {
+        match error.downcast::<http_utils::HttpStatusError>() {
+            Ok(http_error) => Self::from_response(http_error.status, &http_error.body).into(),
+            Err(error) => error,
         }
-        Ok(())
     }
 }
 
-// === DC PIN WRAPPER ===
-/// A wrapper around the DC pin to implement the `embedded-hal` traits.
-struct DcPinWrapper<'a> {
-    pin: PinDriver<'a, esp_idf_svc::hal::gpio::AnyOutputPin, esp_idf_svc::hal::gpio::Output>,
+impl std::fmt::Display for OwmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OWM {}: {}", self.cod, self.message)
+    }
 }
 
-impl embedded_hal::digital::ErrorType for DcPinWrapper<'_> {
-    type Error = CustomError;
+impl std::error::Error for OwmError {}
+
+/// Accepts the OWM `cod` field as either a number or a numeric string.
+fn deserialize_owm_code<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Code {
+        Number(u16),
+        Text(String),
+    }
+
+    match Code::deserialize(deserializer)? {
+        Code::Number(code) => Ok(code),
+        Code::Text(code) => code.parse().map_err(serde::de::Error::custom),
+    }
 }
 
-impl OutputPinTrait for DcPinWrapper<'_> {
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.pin.set_low().map_err(|_| CustomError)
+// === WEATHER FETCH RETRY ===
+/// Maximum number of weather fetch attempts per update.
+const WEATHER_MAX_ATTEMPTS: u32 = 3;
+/// Delay between two weather fetch attempts in milliseconds.
+const WEATHER_RETRY_DELAY_MS: u32 = 2000;
+
+/// Checks whether a weather fetch error is worth retrying.
+/// Network errors, timeouts and 5xx responses are transient;
+/// 4xx responses (e.g. 401 bad API key, 404 unknown city) and parse errors are not.
+fn is_transient_error(error: &anyhow::Error) -> bool {
+    if let Some(owm_error) = error.downcast_ref::<OwmError>() {
+        return owm_error.cod >= 500;
     }
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.pin.set_high().map_err(|_| CustomError)
+    error.downcast_ref::<serde_json::Error>().is_none()
+}
+
+/// Fetches the weather, retrying transient failures up to `max_attempts` times.
+/// Returns the last error if all attempts fail.
+fn get_weather_retrying(
+    config: &OpenWeatherConfig,
+    max_attempts: u32,
+) -> anyhow::Result<WeatherResponse> {
+    let mut attempt = 1;
+    loop {
+        match get_weather(config) {
+            Ok(weather) => return Ok(weather),
+            Err(e) if attempt < max_attempts && is_transient_error(&e) => {
+                warn!(
+                    "Weather fetch attempt {}/{} failed, retrying: {}",
+                    attempt, max_attempts, e
+                );
+                FreeRtos::delay_ms(WEATHER_RETRY_DELAY_MS);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
     }
 }
 
@@ -216,6 +412,31 @@ fn main() -> anyhow::Result<()> {
     // Take peripherals
     let peripherals = Peripherals::take()?;
 
+    // ==================== DISPLAY SETUP ====================
+    // Before WiFi, so that the clock is shown even if the network never comes up
+    let mut gpios = GpioPool::new(peripherals.pins);
+    let pin_config = &secrets.display.pins;
+    let pins = DisplayPins::take(
+        &mut gpios,
+        DisplayPinNumbers {
+            sclk: pin_config.sclk,
+            mosi: pin_config.mosi,
+            cs: pin_config.cs,
+            dc: pin_config.dc,
+            rst: pin_config.rst,
+        },
+    )?;
+
+    // Reset and initialize the display
+    let mut display = build_display(peripherals.spi2, pins, Orientation::new())?;
+
+    // Show panic messages on the display, not only on the serial console
+    // SAFETY: `display` stays in place until the end of `main`, the guard is dropped before it
+    let _panic_screen = unsafe { display_hal::register_panic_display(&mut display) };
+
+    // Clear the display
+    display.clear(Rgb565::BLACK).ok();
+
     // === WiFi Setup ===
     info!("Starting WiFi...");
     let sys_loop = EspSystemEventLoop::take()?;
@@ -237,70 +458,38 @@ fn main() -> anyhow::Result<()> {
         ..Default::default()
     });
     wifi.set_configuration(&wifi_config)?;
-    // Start WiFi and connect
+    // Start WiFi and connect, without a connection the device starts offline
     wifi.start()?;
-    wifi.connect()?;
-    // Wait for connection
-    wifi.wait_netif_up()?;
-    info!("WiFi connected!");
-
-    // ==================== SNTP SETUP ====================
-    let sntp = EspSntp::new_default()?;
-    info!("Waiting for SNTP time synchronization...");
-    while sntp.get_sync_status() != SyncStatus::Completed {
-        FreeRtos::delay_ms(100);
-    }
-    info!("Time synchronized!");
-
-    // ==================== DISPLAY SETUP ====================
-    let sclk = peripherals.pins.gpio18;
-    let mosi = peripherals.pins.gpio23;
-    let cs = peripherals.pins.gpio15;
-    let dc = peripherals.pins.gpio21;
-    let mut rst = PinDriver::output(peripherals.pins.gpio22)?;
-
-    // Reset the display
-    rst.set_low()?;
-    FreeRtos::delay_ms(50);
-    rst.set_high()?;
-    FreeRtos::delay_ms(200);
-
-    // Configure SPI
-    let spi_config = Config::new().baudrate(26.MHz().into());
-    let spi_driver = SpiDriver::new(
-        peripherals.spi2,
-        sclk,
-        mosi,
-        None::<AnyIOPin>,
-        &SpiDriverConfig::new(),
-    )?;
-    let spi_device = SpiDeviceDriver::new(spi_driver, Some(cs), &spi_config)?;
-    let spi_wrapper = SpiWrapper { spi: spi_device };
-    let dc_wrapper = DcPinWrapper {
-        pin: PinDriver::output(dc.downgrade_output())?,
+    let mut offline = match start_wifi_connect(&mut wifi)
+        .and_then(|_| wait_for_wifi(&wifi, WIFI_CONNECT_TIMEOUT))
+    {
+        Ok(()) => {
+            info!("WiFi connected!");
+            false
+        }
+        Err(e) => {
+            warn!("{}, starting offline", e);
+            true
+        }
     };
 
-    // Initialize the display
-    static mut DISPLAY_BUFFER: [u8; 240 * 10 * 2] = [0u8; 240 * 10 * 2];
-    let di = unsafe {
-        mipidsi::interface::SpiInterface::new(
-            spi_wrapper,
-            dc_wrapper,
-            &mut *addr_of_mut!(DISPLAY_BUFFER),
-        )
+    // ==================== SNTP SETUP ====================
+    let sntp = start_sntp(Duration::from_secs(secrets.time.resync_hours * 3600))?;
+    // Offline the clock runs unsynchronized until WiFi connects in the background
+    let mut clock_synced = if offline {
+        warn!("WiFi is offline, continuing with an unsynchronized clock");
+        false
+    } else {
+        info!("Waiting for SNTP time synchronization...");
+        match wait_for_sntp(&sntp, SNTP_TIMEOUT) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}, continuing with an unsynchronized clock", e);
+                false
+            }
+        }
     };
 
-    let mut display = Builder::new(ST7789, di)
-        .display_size(240, 320)
-        .display_offset(0, 0)
-        .color_order(ColorOrder::Rgb)
-        .invert_colors(ColorInversion::Inverted)
-        .init(&mut FreeRtos)
-        .map_err(|e| anyhow::anyhow!("Display init failed: {:?}", e))?;
-
-    // Clear the display
-    display.clear(Rgb565::BLACK).ok();
-
     // ==================== STYLES ====================
     let text_style = MonoTextStyleBuilder::new()
         .font(&FONT_10X20)
@@ -311,10 +500,26 @@ fn main() -> anyhow::Result<()> {
     let symbol_style = MonoTextStyle::new(&PROFONT_24_POINT, Rgb565::YELLOW);
 
     // ==================== MAIN LOOP ====================
-    let mut last_weather_fetch = 0u64;
-    let weather_interval = 15 * 60; // 15 minutes
+    // The clock follows the wall clock, so that it redraws right after a second change.
+    // The weather follows the uptime, which does not jump when SNTP sets the time.
+    let schedule = &secrets.schedule;
+    let loop_started = Instant::now();
+    let mut clock_cadence = Cadence::new(Duration::from_millis(schedule.clock_redraw_ms.into()));
+    let mut weather_cadence =
+        Cadence::new(Duration::from_secs(schedule.weather_interval_mins * 60));
+    // Sunrise and sunset of the last weather response, and the glyph currently shown
+    let mut sun_times: Option<(i64, i64)> = None;
+    let mut shown_daytime: Option<bool> = None;
+    // Connection attempts while offline
+    let mut wifi_retry = WifiRetry::new(Duration::ZERO);
 
     loop {
+        // SNTP keeps trying in the background after a timeout at boot
+        if !clock_synced && sntp.get_sync_status() == SyncStatus::Completed {
+            info!("Time synchronized!");
+            clock_synced = true;
+        }
+
         // Get the current time
         let now = SystemTime::now();
         let since_the_epoch = now.duration_since(UNIX_EPOCH)?;
@@ -324,97 +529,143 @@ fn main() -> anyhow::Result<()> {
         let (year, month, day, hour, minute, second) =
             time_utils::utc_to_berlin(utc_timestamp as i64);
 
-        // Fetch weather data every 15 minutes
-        if utc_timestamp >= last_weather_fetch + weather_interval || last_weather_fetch == 0 {
+        // Without a connection since boot the clock keeps running while the driver connects
+        // in the background. A connection lost later is handled by the weather update.
+        let uptime = loop_started.elapsed();
+        if offline {
+            if wifi.is_up().unwrap_or(false) {
+                info!("WiFi connected after {} attempts", wifi_retry.attempts());
+                offline = false;
+                wifi_retry.reset(uptime);
+            } else if wifi_retry.poll(uptime) {
+                info!("Offline, trying WiFi in the background");
+                if let Err(e) = start_wifi_connect(&mut wifi) {
+                    warn!("Failed to start the WiFi connection: {}", e);
+                }
+            }
+        }
+
+        // Fetch weather data every `weather_interval_mins`, a failed fetch is retried next tick
+        if !offline && weather_cadence.is_due(uptime) {
             info!("Updating Weather...");
 
             // Reconnect to WiFi if necessary
             if !wifi.is_connected()? {
-                wifi.connect().ok();
-                wifi.wait_netif_up().ok();
+                warn!("WiFi disconnected, reconnecting...");
+                if let Err(e) = reconnect_wifi_with_backoff(&mut wifi, WIFI_MAX_RETRIES) {
+                    error!("WiFi reconnect failed, rebooting: {}", e);
+                    esp_idf_svc::hal::reset::restart();
+                }
             }
 
             // Get weather data
-            match get_weather(&secrets.openweather.api_key, &secrets.openweather.city) {
+            match get_weather_retrying(&secrets.openweather, WEATHER_MAX_ATTEMPTS) {
                 Ok(weather) => {
-                    // --- DISPLAY LOGIC ---
-                    display.clear(Rgb565::BLACK).ok();
-
-                    let icon_code = &weather.weather[0].icon;
-
-                    // Set the icon color based on the weather condition
-                    let icon_color = match &icon_code[..2] {
-                        "01" | "02" | "11" => Rgb565::YELLOW,
-                        "09" | "10" => Rgb565::BLUE,
-                        "13" => Rgb565::WHITE,
-                        "03" | "04" | "50" => Rgb565::CSS_GRAY,
-                        _ => Rgb565::WHITE,
-                    };
-
-                    // Display city name
-                    Text::new(&weather.name, Point::new(10, 60), text_style)
-                        .draw(&mut display)
-                        .ok();
-
-                    // Display temperature
-                    let temp_str = format!("{:.1}°C", weather.main.temp);
-                    Text::new(&temp_str, Point::new(10, 90), text_style)
-                        .draw(&mut display)
-                        .ok();
-
-                    // Display weather description
-                    Text::new(
-                        &weather.weather[0].description,
-                        Point::new(10, 120),
-                        text_style,
-                    )
-                    .draw(&mut display)
-                    .ok();
-
-                    // Display wind speed
-                    let wind_str = format!("W: {:.1}m/s", weather.wind.speed);
-                    Text::new(&wind_str, Point::new(10, 150), text_style)
-                        .draw(&mut display)
-                        .ok();
-
-                    // Display humidity
-                    let hum_str = format!("H: {}%", weather.main.humidity);
-                    Text::new(&hum_str, Point::new(10, 180), text_style)
-                        .draw(&mut display)
-                        .ok();
-
-                    // Display weather icon
-                    if let Some(icon_data) = get_weather_icon(&weather.weather[0].icon) {
-                        let icon_width: usize = 40;
-                        let icon_height: usize = 40;
-
-                        let mut pixels = Vec::with_capacity(icon_width * icon_height);
-
-                        for y in 0..icon_height {
-                            for x in 0..icon_width {
-                                let byte_index = y * (icon_width / 8) + (x / 8);
-                                let bit_index = 7 - (x % 8);
-
-                                if byte_index < icon_data.len() {
-                                    if (icon_data[byte_index] >> bit_index) & 1 == 1 {
-                                        pixels.push(Pixel(
-                                            Point::new(160 + x as i32, 70 + y as i32),
-                                            icon_color,
-                                        ));
-                                    }
-                                }
+                    match weather.weather.first() {
+                        // A partial response without conditions is skipped instead of panicking
+                        None => warn!(
+                            "Weather for {} contains no conditions, skipping this update",
+                            weather.name
+                        ),
+                        Some(condition) => {
+                            // --- DISPLAY LOGIC ---
+                            display.clear(Rgb565::BLACK).ok();
+
+                            let icon_code = &condition.icon;
+
+                            // Set the icon color based on the weather condition
+                            let icon_color = match icon_code.get(..2).unwrap_or_default() {
+                                "01" | "02" | "11" => Rgb565::YELLOW,
+                                "09" | "10" => Rgb565::BLUE,
+                                "13" => Rgb565::WHITE,
+                                "03" | "04" | "50" => Rgb565::CSS_GRAY,
+                                _ => Rgb565::WHITE,
+                            };
+
+                            // Display city name and country
+                            let city_str =
+                                geocoding::location_label(&weather.name, &weather.sys.country);
+                            Text::new(&city_str, Point::new(10, 60), text_style)
+                                .draw(&mut display)
+                                .ok();
+
+                            // Display temperature
+                            let temp_str = format!(
+                                "{:.1}{}",
+                                weather.main.temp,
+                                secrets.openweather.temperature_unit()
+                            );
+                            Text::new(&temp_str, Point::new(10, 90), text_style)
+                                .draw(&mut display)
+                                .ok();
+
+                            // Display perceived temperature, left out if the response has none
+                            if let Some(feels_like) = weather.main.feels_like {
+                                let feels_like_str = format!(
+                                    "feels {:.1}{}",
+                                    feels_like,
+                                    secrets.openweather.temperature_unit()
+                                );
+                                Text::new(&feels_like_str, Point::new(10, 120), text_style)
+                                    .draw(&mut display)
+                                    .ok();
                             }
+
+                            // Display weather description
+                            Text::new(&condition.description, Point::new(10, 150), text_style)
+                                .draw(&mut display)
+                                .ok();
+
+                            // Display wind speed
+                            let wind_str = match &weather.wind {
+                                Some(wind) => format!(
+                                    "W: {:.1}{}",
+                                    wind.speed,
+                                    secrets.openweather.wind_speed_unit()
+                                ),
+                                None => "W: --".to_string(),
+                            };
+                            Text::new(&wind_str, Point::new(10, 180), text_style)
+                                .draw(&mut display)
+                                .ok();
+
+                            // Display humidity
+                            let hum_str = match weather.main.humidity {
+                                Some(humidity) => format!("H: {}%", humidity),
+                                None => "H: --".to_string(),
+                            };
+                            Text::new(&hum_str, Point::new(10, 210), text_style)
+                                .draw(&mut display)
+                                .ok();
+
+                            // Display weather icon
+                            let icon_origin = Point::new(160, 70);
+                            if let Some(icon_pixels) = get_weather_icon_rgb(icon_code) {
+                                // Full-color variant, blitted including its black background
+                                let area = Rectangle::new(icon_origin, Size::new(40, 40));
+                                display
+                                    .fill_contiguous(&area, icon_pixels.iter().copied())
+                                    .ok();
+                            } else if let Some(icon_data) = get_weather_icon(icon_code) {
+                                let pixels = icon_bitmap::set_pixels(icon_data)
+                                    .map(|point| Pixel(icon_origin + point, icon_color));
+                                display.draw_iter(pixels).ok();
+                            } else {
+                                // Fallback to weather symbol if icon is not available
+                                let symbol = get_weather_symbol(icon_code);
+                                Text::new(symbol, icon_origin, symbol_style)
+                                    .draw(&mut display)
+                                    .ok();
+                            }
+
+                            // The display was cleared, the clock and the glyph are redrawn below
+                            sun_times = Some((weather.sys.sunrise, weather.sys.sunset));
+                            shown_daytime = None;
+                            clock_cadence.trigger();
                         }
-                        display.draw_iter(pixels.iter().cloned()).ok();
-                    } else {
-                        // Fallback to weather symbol if icon is not available
-                        let symbol = get_weather_symbol(&weather.weather[0].icon);
-                        Text::new(symbol, Point::new(160, 70), symbol_style)
-                            .draw(&mut display)
-                            .ok();
                     }
 
-                    last_weather_fetch = utc_timestamp;
+                    weather_cadence.mark_run(uptime);
                 }
                 Err(e) => {
                     error!("Weather Error: {}", e);
@@ -422,24 +673,53 @@ fn main() -> anyhow::Result<()> {
             }
         }
 
-        // Display time and date
-        let time_str = time_utils::format_time(hour, minute, second);
-        let date_str = time_utils::format_date(day, month, year);
-        let tz_str = time_utils::get_timezone_str(year, month, day, hour);
-
-        Text::new(
-            &format!("{} {}", date_str, tz_str),
-            Point::new(10, 20),
-            text_style,
-        )
-        .draw(&mut display)
-        .ok();
+        // Display time and date every `clock_redraw_ms`
+        if clock_cadence.poll(since_the_epoch) {
+            // Without SNTP the clock starts in 1970, show a banner instead of a bogus date
+            // and the time of the RTC, it counts up from boot
+            let (date_str, time_str) = if clock_synced {
+                (
+                    format!(
+                        "{} {}",
+                        time_utils::format_date_with(
+                            day,
+                            month,
+                            year,
+                            secrets.display.date_format()
+                        ),
+                        time_utils::get_timezone_str(year, month, day, hour)
+                    ),
+                    time_utils::format_time(hour, minute, second),
+                )
+            } else {
+                (
+                    CLOCK_UNSYNCED_BANNER.to_string(),
+                    time_utils::format_unsynced_time(utc_timestamp),
+                )
+            };
+
+            Text::new(&date_str, Point::new(10, 20), text_style)
+                .draw(&mut display)
+                .ok();
+
+            Text::new(&time_str, Point::new(10, 40), text_style)
+                .draw(&mut display)
+                .ok();
+        }
 
-        Text::new(&time_str, Point::new(10, 40), text_style)
-            .draw(&mut display)
-            .ok();
+        // Day/night from the real sunrise/sunset, the icon's d/n suffix lags behind
+        let daytime = sun_times
+            .filter(|_| clock_synced)
+            .and_then(|(sunrise, sunset)| {
+                time_utils::is_daytime(utc_timestamp as i64, sunrise, sunset)
+            });
+        if daytime != shown_daytime {
+            if let Some(daytime) = daytime {
+                draw_day_night_glyph(&mut display, daytime);
+            }
+            shown_daytime = daytime;
+        }
 
-        // Wait for 1 second
-        FreeRtos::delay_ms(1000);
+        FreeRtos::delay_ms(schedule.tick_ms);
     }
 }
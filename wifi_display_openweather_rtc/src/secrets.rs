@@ -1,12 +1,23 @@
+use crate::time_utils::DateFormat;
 use serde::Deserialize;
 
 // Secrets direkt aus Datei zur Compile-Zeit einlesen
-const SECRETS_TOML: &str = include_str!("../../secrets.toml");
+// build.rs löst den Pfad auf: $SECRETS_FILE oder die secrets.toml im Workspace
+const SECRETS_TOML: &str = include_str!(env!("SECRETS_FILE"));
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Secrets {
     pub wifi: WiFiConfig,
     pub openweather: OpenWeatherConfig,
+    /// Zeitsynchronisation (optionaler Abschnitt)
+    #[serde(default)]
+    pub time: TimeConfig,
+    /// Zeitsteuerung der Hauptschleife (optionaler Abschnitt)
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    /// Verdrahtung des Displays (optionaler Abschnitt)
+    #[serde(default)]
+    pub display: DisplayConfig,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -19,6 +30,251 @@ pub struct WiFiConfig {
 pub struct OpenWeatherConfig {
     pub api_key: String,
     pub city: String,
+    /// Einheitensystem: "metric", "imperial" oder "standard" (Standard: metric)
+    pub units: Option<String>,
+    /// Sprache der Wetterbeschreibung, z.B. "en" oder "de" (Standard: en)
+    pub lang: Option<String>,
+    /// Breitengrad des Standorts, wird zusammen mit `lon` statt `city` verwendet
+    pub lat: Option<f64>,
+    /// Längengrad des Standorts, wird zusammen mit `lat` statt `city` verwendet
+    pub lon: Option<f64>,
+    /// Netzwerk-Timeout einer Wetteranfrage in Sekunden (Standard: 30)
+    #[serde(default = "default_http_timeout_secs")]
+    pub http_timeout_secs: u64,
+}
+
+/// Von der OpenWeather API unterstützte Einheitensysteme
+const OPENWEATHER_UNITS: [&str; 3] = ["metric", "imperial", "standard"];
+
+/// Erlaubter Bereich des HTTP-Timeouts in Sekunden
+const HTTP_TIMEOUT_SECS_RANGE: core::ops::RangeInclusive<u64> = 5..=120;
+
+/// Standard-HTTP-Timeout in Sekunden
+fn default_http_timeout_secs() -> u64 {
+    30
+}
+
+impl OpenWeatherConfig {
+    /// Liefert die Koordinaten, wenn `lat` und `lon` gesetzt sind
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.lat.zip(self.lon)
+    }
+
+    /// Prüft die Werte, die serde nicht selbst validieren kann
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.api_key.trim().is_empty() {
+            anyhow::bail!("openweather.api_key darf nicht leer sein");
+        }
+        if self.city.trim().is_empty() && self.coordinates().is_none() {
+            anyhow::bail!("openweather.city darf nicht leer sein, wenn lat/lon fehlen");
+        }
+        if !OPENWEATHER_UNITS.contains(&self.units()) {
+            anyhow::bail!(
+                "Ungültige openweather.units '{}', erwartet: {:?}",
+                self.units(),
+                OPENWEATHER_UNITS
+            );
+        }
+        if self.lat.is_some() != self.lon.is_some() {
+            anyhow::bail!("openweather.lat und openweather.lon müssen zusammen gesetzt werden");
+        }
+        if let Some(lat) = self.lat {
+            if !(-90.0..=90.0).contains(&lat) {
+                anyhow::bail!("Ungültiger openweather.lat {}, erwartet: [-90, 90]", lat);
+            }
+        }
+        if let Some(lon) = self.lon {
+            if !(-180.0..=180.0).contains(&lon) {
+                anyhow::bail!("Ungültiger openweather.lon {}, erwartet: [-180, 180]", lon);
+            }
+        }
+        if !HTTP_TIMEOUT_SECS_RANGE.contains(&self.http_timeout_secs) {
+            anyhow::bail!(
+                "Ungültiger openweather.http_timeout_secs {}, erwartet: {:?}",
+                self.http_timeout_secs,
+                HTTP_TIMEOUT_SECS_RANGE
+            );
+        }
+        Ok(())
+    }
+
+    /// Liefert den Netzwerk-Timeout einer Wetteranfrage
+    pub fn http_timeout(&self) -> core::time::Duration {
+        core::time::Duration::from_secs(self.http_timeout_secs)
+    }
+
+    /// Liefert das Einheitensystem, Standard ist metric
+    pub fn units(&self) -> &str {
+        self.units.as_deref().unwrap_or("metric")
+    }
+
+    /// Liefert die Sprache, Standard ist Englisch
+    pub fn lang(&self) -> &str {
+        self.lang.as_deref().unwrap_or("en")
+    }
+
+    /// Liefert die Temperatureinheit passend zum Einheitensystem
+    pub fn temperature_unit(&self) -> &'static str {
+        match self.units() {
+            "imperial" => "°F",
+            "standard" => "K",
+            _ => "°C",
+        }
+    }
+
+    /// Liefert die Windgeschwindigkeitseinheit passend zum Einheitensystem
+    pub fn wind_speed_unit(&self) -> &'static str {
+        match self.units() {
+            "imperial" => "mph",
+            _ => "m/s",
+        }
+    }
+}
+
+/// Erlaubter Bereich des SNTP-Resync-Intervalls in Stunden
+const RESYNC_HOURS_RANGE: core::ops::RangeInclusive<u64> = 1..=168;
+
+/// Standard-Intervall des SNTP-Resyncs in Stunden
+fn default_resync_hours() -> u64 {
+    6
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimeConfig {
+    /// Intervall in Stunden, in dem SNTP die Uhr neu synchronisiert (Standard: 6)
+    #[serde(default = "default_resync_hours")]
+    pub resync_hours: u64,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            resync_hours: default_resync_hours(),
+        }
+    }
+}
+
+/// Erlaubter Bereich des Takts der Hauptschleife in Millisekunden
+const TICK_MS_RANGE: core::ops::RangeInclusive<u32> = 10..=1000;
+
+/// Standard-Takt der Hauptschleife in Millisekunden
+fn default_tick_ms() -> u32 {
+    100
+}
+
+/// Standard-Intervall, in dem die Uhr neu gezeichnet wird, in Millisekunden
+fn default_clock_redraw_ms() -> u32 {
+    1000
+}
+
+/// Standard-Intervall des Wetterabrufs in Minuten
+fn default_weather_interval_mins() -> u64 {
+    15
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScheduleConfig {
+    /// Pause zwischen zwei Durchläufen der Hauptschleife in Millisekunden (Standard: 100)
+    #[serde(default = "default_tick_ms")]
+    pub tick_ms: u32,
+    /// Intervall in Millisekunden, in dem die Uhr neu gezeichnet wird (Standard: 1000)
+    #[serde(default = "default_clock_redraw_ms")]
+    pub clock_redraw_ms: u32,
+    /// Intervall in Minuten, in dem das Wetter abgerufen wird (Standard: 15)
+    #[serde(default = "default_weather_interval_mins")]
+    pub weather_interval_mins: u64,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: default_tick_ms(),
+            clock_redraw_ms: default_clock_redraw_ms(),
+            weather_interval_mins: default_weather_interval_mins(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DisplayConfig {
+    /// GPIO-Nummern der Display-Anschlüsse (optionaler Abschnitt `[display.pins]`)
+    #[serde(default)]
+    pub pins: DisplayPinsConfig,
+    /// Datumsformat: "de" (TT.MM.JJJJ), "us" (MM/TT/JJJJ) oder "iso" (JJJJ-MM-TT) (Standard: de)
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+}
+
+/// Standard-Datumsformat: TT.MM.JJJJ
+fn default_date_format() -> String {
+    "de".to_string()
+}
+
+impl DisplayConfig {
+    /// Liefert das Datumsformat, bei ungültigem Wert (von `validate` abgelehnt) deutsch
+    pub fn date_format(&self) -> DateFormat {
+        DateFormat::parse(&self.date_format).unwrap_or_default()
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            pins: DisplayPinsConfig::default(),
+            date_format: default_date_format(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DisplayPinsConfig {
+    /// SPI-Takt, SCL am Display (Standard: 18)
+    #[serde(default = "default_sclk_pin")]
+    pub sclk: u8,
+    /// SPI-Daten, SDA am Display (Standard: 23)
+    #[serde(default = "default_mosi_pin")]
+    pub mosi: u8,
+    /// Chip Select (Standard: 15)
+    #[serde(default = "default_cs_pin")]
+    pub cs: u8,
+    /// Umschaltung Daten/Befehl (Standard: 21)
+    #[serde(default = "default_dc_pin")]
+    pub dc: u8,
+    /// Hardware-Reset (Standard: 22)
+    #[serde(default = "default_rst_pin")]
+    pub rst: u8,
+}
+
+fn default_sclk_pin() -> u8 {
+    18
+}
+
+fn default_mosi_pin() -> u8 {
+    23
+}
+
+fn default_cs_pin() -> u8 {
+    15
+}
+
+fn default_dc_pin() -> u8 {
+    21
+}
+
+fn default_rst_pin() -> u8 {
+    22
+}
+
+impl Default for DisplayPinsConfig {
+    fn default() -> Self {
+        Self {
+            sclk: default_sclk_pin(),
+            mosi: default_mosi_pin(),
+            cs: default_cs_pin(),
+            dc: default_dc_pin(),
+            rst: default_rst_pin(),
+        }
+    }
 }
 
 impl Secrets {
@@ -26,6 +282,55 @@ impl Secrets {
     pub fn load() -> anyhow::Result<Self> {
         let secrets: Secrets = toml::from_str(SECRETS_TOML)
             .map_err(|e| anyhow::anyhow!("Fehler beim Parsen von secrets.toml: {}", e))?;
+
+        secrets.validate()?;
+
         Ok(secrets)
     }
+
+    /// Prüft, ob die Konfiguration verwendbar ist, und nennt sonst das fehlerhafte Feld
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.wifi.ssid.is_empty() {
+            anyhow::bail!("wifi.ssid darf nicht leer sein");
+        }
+
+        self.openweather.validate()?;
+
+        if !RESYNC_HOURS_RANGE.contains(&self.time.resync_hours) {
+            anyhow::bail!(
+                "time.resync_hours muss zwischen {} und {} liegen, ist {}",
+                RESYNC_HOURS_RANGE.start(),
+                RESYNC_HOURS_RANGE.end(),
+                self.time.resync_hours
+            );
+        }
+
+        if DateFormat::parse(&self.display.date_format).is_none() {
+            anyhow::bail!(
+                "display.date_format muss \"de\", \"us\" oder \"iso\" sein, ist \"{}\"",
+                self.display.date_format
+            );
+        }
+
+        if !TICK_MS_RANGE.contains(&self.schedule.tick_ms) {
+            anyhow::bail!(
+                "schedule.tick_ms muss zwischen {} und {} liegen, ist {}",
+                TICK_MS_RANGE.start(),
+                TICK_MS_RANGE.end(),
+                self.schedule.tick_ms
+            );
+        }
+        if self.schedule.clock_redraw_ms < self.schedule.tick_ms {
+            anyhow::bail!(
+                "schedule.clock_redraw_ms muss mindestens schedule.tick_ms ({}) sein, ist {}",
+                self.schedule.tick_ms,
+                self.schedule.clock_redraw_ms
+            );
+        }
+        if self.schedule.weather_interval_mins == 0 {
+            anyhow::bail!("schedule.weather_interval_mins muss mindestens 1 sein");
+        }
+
+        Ok(())
+    }
 }
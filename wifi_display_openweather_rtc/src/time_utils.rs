@@ -1,64 +1,151 @@
 // time_utils.rs
-use chrono::{Datelike, TimeZone, Timelike, Utc};
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike, Utc, Weekday};
 //use log::*;
 
-/// Calculates whether a given time is in Daylight Saving Time (CEST).
-/// Summer time: Last Sunday in March 2:00 UTC to last Sunday in October 3:00 UTC
-pub fn is_dst(year: i32, month: u32, day: u32, hour: u32) -> bool {
-    // Last Sunday in March (start of CEST)
-    let march_last_sunday_day = 31 - ((5 * year / 4 + 4) % 7);
-
-    // Last Sunday in October (end of CEST)
-    let october_last_sunday_day = 31 - ((5 * year / 4 + 1) % 7);
-
-    match month {
-        1 | 2 => false, // January, February: always CET
-        3 => {
-            // March: CEST from last Sunday 2:00 UTC
-            if day < march_last_sunday_day as u32 {
-                false
-            } else if day > march_last_sunday_day as u32 {
-                true
-            } else {
-                // On the changeover day: from 2:00 UTC
-                hour >= 2
-            }
+/// Describes a DST changeover as "the nth weekday of a month at a given UTC hour".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionRule {
+    /// The month of the changeover (1-12).
+    pub month: u32,
+    /// The occurrence of the weekday within the month (1-4, 5 = last).
+    pub week: u32,
+    /// The weekday of the changeover.
+    pub weekday: Weekday,
+    /// The hour (UTC) at which the changeover happens.
+    pub utc_hour: u32,
+}
+
+impl TransitionRule {
+    /// Returns the day of the month on which the changeover happens in `year`.
+    pub fn day_in(&self, year: i32) -> u32 {
+        let date = if self.week >= 5 {
+            last_weekday_of_month(year, self.month, self.weekday)
+        } else {
+            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, self.week as u8)
+        };
+        date.map(|date| date.day()).unwrap_or(1)
+    }
+}
+
+/// Finds the last occurrence of `weekday` in the given month.
+/// Walks backwards from the last day of the month, so it is exact for every year.
+pub fn last_weekday_of_month(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+
+    // The day before the first of the next month is the last day of this month
+    let mut date = NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()?;
+    while date.weekday() != weekday {
+        date = date.pred_opt()?;
+    }
+    Some(date)
+}
+
+/// The start and end of daylight saving time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DstRules {
+    /// When daylight saving time starts.
+    pub start: TransitionRule,
+    /// When daylight saving time ends.
+    pub end: TransitionRule,
+    /// The additional offset during daylight saving time in seconds.
+    pub dst_offset_secs: i64,
+}
+
+/// Describes a time zone by its base UTC offset and optional DST rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeZoneRules {
+    /// The base (standard time) UTC offset in seconds.
+    pub base_offset_secs: i64,
+    /// The abbreviation during standard time, e.g. "CET".
+    pub std_abbr: &'static str,
+    /// The abbreviation during daylight saving time, e.g. "CEST".
+    pub dst_abbr: &'static str,
+    /// The DST rules, `None` if the zone does not observe DST.
+    pub dst: Option<DstRules>,
+}
+
+/// Berlin (CET/CEST).
+/// Summer: Last Sunday on March 1:00 UTC to last Sunday on October 1:00 UTC
+pub const BERLIN: TimeZoneRules = TimeZoneRules {
+    base_offset_secs: 3600,
+    std_abbr: "CET",
+    dst_abbr: "CEST",
+    dst: Some(DstRules {
+        start: TransitionRule {
+            month: 3,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        end: TransitionRule {
+            month: 10,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        dst_offset_secs: 3600,
+    }),
+};
+
+impl TimeZoneRules {
+    /// Calculates whether a given UTC time is in Daylight Saving Time.
+    pub fn is_dst(&self, year: i32, month: u32, day: u32, hour: u32) -> bool {
+        let dst = match self.dst {
+            Some(dst) => dst,
+            None => return false,
+        };
+
+        let now = (month, day, hour);
+        let start = (dst.start.month, dst.start.day_in(year), dst.start.utc_hour);
+        let end = (dst.end.month, dst.end.day_in(year), dst.end.utc_hour);
+
+        if start <= end {
+            // Northern hemisphere: DST lies within the calendar year
+            now >= start && now < end
+        } else {
+            // Southern hemisphere: DST spans the turn of the year
+            now >= start || now < end
         }
-        4..=9 => true, // April to September: always CEST
-        10 => {
-            // October: CEST until last Sunday 3:00 UTC
-            if day < october_last_sunday_day as u32 {
-                true
-            } else if day > october_last_sunday_day as u32 {
-                false
-            } else {
-                // On the changeover day: until 3:00 UTC
-                hour < 3
+    }
+
+    /// Returns the total UTC offset in seconds for the given UTC time.
+    pub fn offset_secs(&self, year: i32, month: u32, day: u32, hour: u32) -> i64 {
+        match self.dst {
+            Some(dst) if self.is_dst(year, month, day, hour) => {
+                self.base_offset_secs + dst.dst_offset_secs
             }
+            _ => self.base_offset_secs,
+        }
+    }
+
+    /// Returns the time zone abbreviation for the given UTC time.
+    pub fn abbreviation(&self, year: i32, month: u32, day: u32, hour: u32) -> &'static str {
+        if self.is_dst(year, month, day, hour) {
+            self.dst_abbr
+        } else {
+            self.std_abbr
         }
-        11 | 12 => false, // November, December: always CET
-        _ => false,
     }
 }
 
-/// Converts UTC time to Berlin time (CET/CEST)
-pub fn utc_to_berlin(utc_timestamp: i64) -> (i32, u32, u32, u32, u32, u32) {
+/// Converts UTC time to local time according to the given time zone rules
+pub fn utc_to_local(utc_timestamp: i64, tz: TimeZoneRules) -> (i32, u32, u32, u32, u32, u32) {
     let utc_time = Utc.timestamp_opt(utc_timestamp, 0).unwrap();
 
-    let year = utc_time.year();
-    let month = utc_time.month();
-    let day = utc_time.day();
-    let hour = utc_time.hour();
-
-    // Check if daylight saving time is active
-    let offset_hours = if is_dst(year, month, day, hour) {
-        2 // CEST: UTC+2
-    } else {
-        1 // CET: UTC+1
-    };
+    // Determine the offset (including DST) for this instant
+    let offset_secs = tz.offset_secs(
+        utc_time.year(),
+        utc_time.month(),
+        utc_time.day(),
+        utc_time.hour(),
+    );
 
     // Add offset
-    let local_timestamp = utc_timestamp + (offset_hours * 3600);
+    let local_timestamp = utc_timestamp + offset_secs;
     let local_time = Utc.timestamp_opt(local_timestamp, 0).unwrap();
 
     (
@@ -71,23 +158,80 @@ pub fn utc_to_berlin(utc_timestamp: i64) -> (i32, u32, u32, u32, u32, u32) {
     )
 }
 
+/// Converts UTC time to Berlin time (CET/CEST)
+pub fn utc_to_berlin(utc_timestamp: i64) -> (i32, u32, u32, u32, u32, u32) {
+    utc_to_local(utc_timestamp, BERLIN)
+}
+
 /// Formats the time as a string "HH:MM:SS"
 pub fn format_time(hour: u32, minute: u32, second: u32) -> String {
     format!("{:02}:{:02}:{:02}", hour, minute, second)
 }
 
-/// Formats the date as a string "DD.MM.YYYY"
-pub fn format_date(day: u32, month: u32, year: i32) -> String {
-    format!("{:02}.{:02}.{}", day, month, year)
+/// Formats the unsynchronized system clock as "HH:MM:SS", without a time zone
+/// After a cold boot the RTC starts at the epoch, so the time counts up from 00:00:00.
+/// A soft restart keeps the RTC running and the time continues from its value.
+pub fn format_unsynced_time(utc_timestamp: u64) -> String {
+    let secs = utc_timestamp % 86_400;
+    format_time(
+        (secs / 3600) as u32,
+        (secs / 60 % 60) as u32,
+        (secs % 60) as u32,
+    )
+}
+
+/// Order and separators of a formatted date, all variants are 10 characters wide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateFormat {
+    /// "DD.MM.YYYY"
+    #[default]
+    German,
+    /// "MM/DD/YYYY"
+    Us,
+    /// "YYYY-MM-DD"
+    Iso,
+}
+
+impl DateFormat {
+    /// Parses a `date_format` config value: "de", "us" or "iso"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "de" => Some(Self::German),
+            "us" => Some(Self::Us),
+            "iso" => Some(Self::Iso),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the date in the given format, day and month zero-padded
+pub fn format_date_with(day: u32, month: u32, year: i32, format: DateFormat) -> String {
+    match format {
+        DateFormat::German => format!("{:02}.{:02}.{}", day, month, year),
+        DateFormat::Us => format!("{:02}/{:02}/{}", month, day, year),
+        DateFormat::Iso => format!("{}-{:02}-{:02}", year, month, day),
+    }
+}
+
+/// Returns whether the sun is up, given the sunrise and sunset of the current day
+/// (all UTC timestamps, as reported by OpenWeatherMap).
+/// `None` if the times are unknown (0), e.g. during polar day or night.
+pub fn is_daytime(utc_timestamp: i64, sunrise: i64, sunset: i64) -> Option<bool> {
+    if sunrise == 0 || sunset == 0 {
+        return None;
+    }
+    Some((sunrise..sunset).contains(&utc_timestamp))
+}
+
+/// Drift of the clock in ppm, from how far it was ahead of NTP (`offset_ms`, negative when
+/// behind) after running `elapsed_secs` since the previous sync. `None` without a previous sync.
+pub fn clock_drift_ppm(offset_ms: i64, elapsed_secs: u64) -> Option<f64> {
+    (elapsed_secs > 0).then(|| offset_ms as f64 * 1000.0 / elapsed_secs as f64)
 }
 
 /// Returns the current time zone as a string
 pub fn get_timezone_str(year: i32, month: u32, day: u32, hour: u32) -> &'static str {
-    if is_dst(year, month, day, hour) {
-        "CEST"
-    } else {
-        "CET"
-    }
+    BERLIN.abbreviation(year, month, day, hour)
 }
 
 #[cfg(test)]
@@ -97,18 +241,138 @@ mod tests {
     #[test]
     fn test_dst_calculation() {
         // March 15, 2024, 10:00 UTC -> CET (before changeover)
-        assert_eq!(is_dst(2024, 3, 15, 10), false);
+        assert_eq!(BERLIN.is_dst(2024, 3, 15, 10), false);
 
         // March 31, 2024, 03:00 UTC -> CEST (after changeover)
-        assert_eq!(is_dst(2024, 3, 31, 3), true);
+        assert_eq!(BERLIN.is_dst(2024, 3, 31, 3), true);
 
         // July 15, 2024, 12:00 UTC -> CEST
-        assert_eq!(is_dst(2024, 7, 15, 12), true);
+        assert_eq!(BERLIN.is_dst(2024, 7, 15, 12), true);
 
         // October 27, 2024, 02:00 UTC -> CET (after changeover)
-        assert_eq!(is_dst(2024, 10, 27, 4), false);
+        assert_eq!(BERLIN.is_dst(2024, 10, 27, 4), false);
 
         // December 15, 2024, 18:00 UTC -> CET
-        assert_eq!(is_dst(2024, 12, 15, 18), false);
+        assert_eq!(BERLIN.is_dst(2024, 12, 15, 18), false);
+    }
+
+    #[test]
+    fn test_last_sunday_regression() {
+        // Known changeover dates (last Sunday in March / October)
+        let known = [
+            (2023, 26, 29),
+            (2024, 31, 27),
+            (2027, 28, 31),
+            // 2100 is not a leap year, the Gauss approximation is off by one day here
+            (2100, 28, 31),
+        ];
+
+        for (year, march_day, october_day) in known {
+            assert_eq!(
+                last_weekday_of_month(year, 3, Weekday::Sun).map(|d| d.day()),
+                Some(march_day)
+            );
+            assert_eq!(
+                last_weekday_of_month(year, 10, Weekday::Sun).map(|d| d.day()),
+                Some(october_day)
+            );
+
+            // CET before, CEST after the March changeover
+            assert_eq!(BERLIN.is_dst(year, 3, march_day - 1, 12), false);
+            assert_eq!(BERLIN.is_dst(year, 3, march_day, 2), true);
+
+            // CEST before, CET after the October changeover
+            assert_eq!(BERLIN.is_dst(year, 10, october_day - 1, 12), true);
+            assert_eq!(BERLIN.is_dst(year, 10, october_day, 3), false);
+        }
+
+        // December must not overflow into the next year
+        assert_eq!(
+            last_weekday_of_month(2024, 12, Weekday::Tue).map(|d| d.day()),
+            Some(31)
+        );
+    }
+
+    #[test]
+    fn test_eu_changeover_at_one_utc() {
+        // 2024-03-31: 00:59:59 UTC is 01:59:59 CET, one second later it is 03:00:00 CEST
+        assert_eq!(utc_to_berlin(1_711_846_799), (2024, 3, 31, 1, 59, 59));
+        assert_eq!(utc_to_berlin(1_711_846_800), (2024, 3, 31, 3, 0, 0));
+
+        // 2024-10-27: 00:59:59 UTC is 02:59:59 CEST, one second later it is 02:00:00 CET
+        assert_eq!(utc_to_berlin(1_729_990_799), (2024, 10, 27, 2, 59, 59));
+        assert_eq!(utc_to_berlin(1_729_990_800), (2024, 10, 27, 2, 0, 0));
+
+        // The hours from 01:00 to 03:00 UTC already follow the new offset
+        for (month, day) in [(3, 31), (10, 27)] {
+            let dst_before = month == 10;
+            assert_eq!(BERLIN.is_dst(2024, month, day, 0), dst_before);
+            for hour in 1..=3 {
+                assert_eq!(BERLIN.is_dst(2024, month, day, hour), !dst_before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_utc_to_local() {
+        // 2024-07-15 12:00:00 UTC
+        let summer = 1_721_044_800;
+        assert_eq!(utc_to_local(summer, BERLIN), (2024, 7, 15, 14, 0, 0));
+
+        // 2024-01-15 03:00:00 UTC
+        let winter = 1_705_287_600;
+        assert_eq!(utc_to_local(winter, BERLIN), (2024, 1, 15, 4, 0, 0));
+
+        // The thin wrapper must agree with the Berlin preset
+        assert_eq!(utc_to_berlin(summer), utc_to_local(summer, BERLIN));
+    }
+
+    #[test]
+    fn test_format_date_with() {
+        assert_eq!(
+            format_date_with(5, 3, 2025, DateFormat::default()),
+            "05.03.2025"
+        );
+        assert_eq!(format_date_with(5, 3, 2025, DateFormat::Us), "03/05/2025");
+        assert_eq!(format_date_with(5, 3, 2025, DateFormat::Iso), "2025-03-05");
+    }
+
+    #[test]
+    fn test_date_format_parse() {
+        assert_eq!(DateFormat::parse("de"), Some(DateFormat::German));
+        assert_eq!(DateFormat::parse("us"), Some(DateFormat::Us));
+        assert_eq!(DateFormat::parse("iso"), Some(DateFormat::Iso));
+        assert_eq!(DateFormat::parse("DD.MM.YYYY"), None);
+    }
+
+    #[test]
+    fn test_is_daytime() {
+        // Berlin, 2024-06-21: sunrise 02:43 UTC, sunset 19:33 UTC
+        let sunrise = 1718937780;
+        let sunset = 1718998380;
+
+        assert_eq!(is_daytime(sunrise - 1, sunrise, sunset), Some(false));
+        assert_eq!(is_daytime(sunrise, sunrise, sunset), Some(true));
+        assert_eq!(is_daytime(sunset - 1, sunrise, sunset), Some(true));
+        assert_eq!(is_daytime(sunset, sunrise, sunset), Some(false));
+
+        // Unknown sunrise/sunset
+        assert_eq!(is_daytime(sunrise, 0, 0), None);
+    }
+
+    #[test]
+    fn test_format_unsynced_time() {
+        assert_eq!(format_unsynced_time(0), "00:00:00");
+        assert_eq!(format_unsynced_time(3 * 3600 + 25 * 60 + 7), "03:25:07");
+        // Past one day the time wraps around
+        assert_eq!(format_unsynced_time(86_400 + 61), "00:01:01");
+    }
+
+    #[test]
+    fn test_clock_drift_ppm() {
+        // 432 ms ahead after 6 hours
+        assert_eq!(clock_drift_ppm(432, 6 * 3600), Some(20.0));
+        assert_eq!(clock_drift_ppm(-216, 6 * 3600), Some(-10.0));
+        assert_eq!(clock_drift_ppm(100, 0), None);
     }
 }
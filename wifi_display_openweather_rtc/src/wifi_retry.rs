@@ -0,0 +1,87 @@
+// wifi_retry.rs
+use core::time::Duration;
+use weather_common::backoff::backoff_delay;
+
+/// Time the driver gets to connect before the next attempt starts over
+pub const ATTEMPT_TIME: Duration = Duration::from_secs(15);
+
+/// Schedules Wi-Fi connection attempts while offline
+///
+/// An attempt only starts the connection, the driver connects in the background while the
+/// main loop keeps the clock running. Attempts start at least `ATTEMPT_TIME` apart, and
+/// further apart with the backoff of repeated failures (up to 60s).
+pub struct WifiRetry {
+    failed_attempts: u32,
+    /// Uptime at which the next attempt starts
+    next_attempt: Duration,
+}
+
+impl WifiRetry {
+    /// The first attempt is due at `now`
+    pub fn new(now: Duration) -> Self {
+        Self {
+            failed_attempts: 0,
+            next_attempt: now,
+        }
+    }
+
+    /// Returns true if a new attempt should start at `now`, and schedules the one after it
+    pub fn poll(&mut self, now: Duration) -> bool {
+        if now < self.next_attempt {
+            return false;
+        }
+        self.next_attempt = now + backoff_delay(self.failed_attempts).max(ATTEMPT_TIME);
+        self.failed_attempts = self.failed_attempts.saturating_add(1);
+        true
+    }
+
+    /// Number of attempts since the last connection
+    pub fn attempts(&self) -> u32 {
+        self.failed_attempts
+    }
+
+    /// Call when connected, the next loss of the connection starts retrying right away
+    pub fn reset(&mut self, now: Duration) {
+        *self = Self::new(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_attempts_are_spaced() {
+        let mut retry = WifiRetry::new(secs(0));
+        assert!(retry.poll(secs(0)));
+        // An attempt gets ATTEMPT_TIME before the next one
+        assert!(!retry.poll(secs(14)));
+        assert!(retry.poll(secs(15)));
+        assert!(retry.poll(secs(30)));
+        assert!(retry.poll(secs(45)));
+        // From the 5th failure on the backoff (16s, 32s, 60s) is longer
+        assert!(retry.poll(secs(60)));
+        assert!(!retry.poll(secs(75)));
+        assert!(retry.poll(secs(76)));
+        assert!(!retry.poll(secs(107)));
+        assert!(retry.poll(secs(108)));
+        assert!(!retry.poll(secs(167)));
+        assert!(retry.poll(secs(168)));
+        assert_eq!(retry.attempts(), 8);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut retry = WifiRetry::new(secs(0));
+        for i in 0..10 {
+            retry.poll(secs(i * 60));
+        }
+        retry.reset(secs(1000));
+        assert_eq!(retry.attempts(), 0);
+        assert!(retry.poll(secs(1000)));
+    }
+}
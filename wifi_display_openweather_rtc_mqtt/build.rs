@@ -1,5 +1,24 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Fields this app needs in secrets.toml, as "section.field"
+/// For "a|b" either field is enough. The [mqtt] section is optional, its broker settings
+/// are checked on the device while MQTT is enabled.
+const REQUIRED_FIELDS: &[&str] = &[
+    "wifi.ssid|wifi.networks",
+    "wifi.password|wifi.networks",
+    "openweather.api_key",
+    "openweather.city|openweather.cities|openweather.lat",
+];
+
+/// PEM files named in secrets.toml ("section.field") and the file in OUT_DIR they are copied to
+/// The app embeds the copies with `include_bytes!`, an empty copy means the field is not set.
+const PEM_FILES: &[(&str, &str)] = &[
+    ("mqtt.ca_cert_path", "mqtt_ca_cert.pem"),
+    ("mqtt.client_cert", "mqtt_client_cert.pem"),
+    ("mqtt.client_key", "mqtt_client_key.pem"),
+];
 
 fn main() {
     // IMPORTANT: Initialize the ESP-IDF build system
@@ -9,8 +28,13 @@ fn main() {
     let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let workspace_root = PathBuf::from(&manifest_dir).parent().unwrap().to_path_buf();
 
-    // Path to the secrets.toml file
-    let secrets_path = workspace_root.join("secrets.toml");
+    // Path to the secrets.toml file, can be overridden with SECRETS_FILE
+    // (relative paths are resolved against the workspace root)
+    println!("cargo:rerun-if-env-changed=SECRETS_FILE");
+    let secrets_path = match env::var("SECRETS_FILE") {
+        Ok(path) => workspace_root.join(path),
+        Err(_) => workspace_root.join("secrets.toml"),
+    };
 
     // Rerun the build script if secrets.toml changes
     println!("cargo:rerun-if-changed={}", secrets_path.display());
@@ -25,8 +49,97 @@ fn main() {
             \n\
             Create the file:\n\
             cp secrets.toml.example secrets.toml\n\
+            \n\
+            or point SECRETS_FILE to an existing file.\n\
             ",
             secrets_path.display()
         );
     }
+
+    // Check the required fields, so that an incomplete secrets.toml fails the build
+    // instead of showing up as a parse error on the device
+    let secrets = read_secrets(&secrets_path);
+    check_required_fields(&secrets, &secrets_path);
+
+    // Copy the certificates and keys into OUT_DIR, the device has no file system
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    embed_pem_files(&secrets, &workspace_root, &out_dir);
+
+    // Make the resolved path available to `include_str!` in secrets.rs
+    println!("cargo:rustc-env=SECRETS_FILE={}", secrets_path.display());
+}
+
+/// Reads secrets.toml, failing the build if it is not valid TOML
+fn read_secrets(secrets_path: &Path) -> toml::Table {
+    let content = fs::read_to_string(secrets_path).unwrap_or_else(|e| {
+        panic!(
+            "\n\n❌ ERROR: Cannot read {}: {}\n",
+            secrets_path.display(),
+            e
+        )
+    });
+    toml::from_str(&content).unwrap_or_else(|e| {
+        panic!(
+            "\n\n❌ ERROR: {} is not valid TOML:\n{}\n",
+            secrets_path.display(),
+            e
+        )
+    })
+}
+
+/// Fails the build if secrets.toml lacks a required field
+fn check_required_fields(secrets: &toml::Table, secrets_path: &Path) {
+    let missing: Vec<String> = REQUIRED_FIELDS
+        .iter()
+        .filter(|fields| !fields.split('|').any(|field| has_field(secrets, field)))
+        .map(|fields| fields.replace('|', " or "))
+        .collect();
+    if !missing.is_empty() {
+        panic!(
+            "\n\n\
+            ❌ ERROR: {} is missing:\n\
+            \n\
+            {}\n\
+            \n\
+            See secrets.toml.example\n\
+            ",
+            secrets_path.display(),
+            missing.join("\n")
+        );
+    }
+}
+
+/// Whether `field` ("section.field") is present in the table
+fn has_field(secrets: &toml::Table, field: &str) -> bool {
+    get_field(secrets, field).is_some()
+}
+
+/// The value of `field` ("section.field") in the table
+fn get_field<'a>(secrets: &'a toml::Table, field: &str) -> Option<&'a toml::Value> {
+    match field.split_once('.') {
+        Some((section, key)) => secrets.get(section).and_then(|section| section.get(key)),
+        None => secrets.get(field),
+    }
+}
+
+/// Copies the PEM files named in secrets.toml into OUT_DIR with a terminating NUL,
+/// which ESP-IDF expects for PEM data. Relative paths are resolved against the workspace root.
+fn embed_pem_files(secrets: &toml::Table, workspace_root: &Path, out_dir: &Path) {
+    for (field, file_name) in PEM_FILES {
+        let mut pem = Vec::new();
+        if let Some(path) = get_field(secrets, field).and_then(|value| value.as_str()) {
+            let path = workspace_root.join(path);
+            println!("cargo:rerun-if-changed={}", path.display());
+            pem = fs::read(&path).unwrap_or_else(|e| {
+                panic!(
+                    "\n\n❌ ERROR: Cannot read {} ({}): {}\n",
+                    path.display(),
+                    field,
+                    e
+                )
+            });
+            pem.push(0);
+        }
+        fs::write(out_dir.join(file_name), pem).unwrap();
+    }
 }
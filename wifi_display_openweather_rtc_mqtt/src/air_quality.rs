@@ -0,0 +1,91 @@
+// air_quality.rs
+use embedded_graphics::pixelcolor::Rgb565;
+use serde::Deserialize;
+use weather_common::http_utils::urlencode;
+
+/// Response of the OpenWeatherMap `/data/2.5/air_pollution` endpoint
+#[derive(Deserialize, Debug)]
+pub struct AirPollutionResponse {
+    pub list: Vec<AirPollutionItem>,
+}
+
+/// One measurement of the air pollution response
+#[derive(Deserialize, Debug)]
+pub struct AirPollutionItem {
+    pub main: AirPollutionMain,
+}
+
+/// The air quality index of a measurement
+#[derive(Deserialize, Debug)]
+pub struct AirPollutionMain {
+    /// 1 = good, 2 = fair, 3 = moderate, 4 = poor, 5 = very poor
+    pub aqi: u8,
+}
+
+impl AirPollutionResponse {
+    /// Returns the index of the current measurement, `None` for an empty list or an index outside 1-5
+    pub fn aqi(&self) -> Option<u8> {
+        self.list
+            .first()
+            .map(|item| item.main.aqi)
+            .filter(|aqi| (1..=5).contains(aqi))
+    }
+}
+
+/// Builds the URL of the current air pollution at the given coordinates
+pub fn air_pollution_url(api_key: &str, lat: f64, lon: f64) -> String {
+    format!(
+        "https://api.openweathermap.org/data/2.5/air_pollution?lat={}&lon={}&appid={}",
+        lat,
+        lon,
+        urlencode(api_key)
+    )
+}
+
+/// Badge color of an air quality index, from green (1, good) to red (5, very poor)
+pub fn aqi_color(aqi: u8) -> Rgb565 {
+    match aqi {
+        1 => Rgb565::new(0, 50, 0),
+        2 => Rgb565::new(20, 60, 0),
+        3 => Rgb565::new(31, 63, 0),
+        4 => Rgb565::new(31, 32, 0),
+        _ => Rgb565::new(31, 0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::pixelcolor::RgbColor;
+
+    #[test]
+    fn test_parse_aqi() {
+        let json = r#"{
+            "coord":{"lon":8.6821,"lat":50.1109},
+            "list":[{"main":{"aqi":2},"components":{"co":201.94,"no2":0.77,"pm2_5":0.5},"dt":1605182400}]
+        }"#;
+        let response: AirPollutionResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.aqi(), Some(2));
+
+        let response: AirPollutionResponse = serde_json::from_str(r#"{"list":[]}"#).unwrap();
+        assert_eq!(response.aqi(), None);
+
+        let response: AirPollutionResponse =
+            serde_json::from_str(r#"{"list":[{"main":{"aqi":0}}]}"#).unwrap();
+        assert_eq!(response.aqi(), None);
+    }
+
+    #[test]
+    fn test_air_pollution_url() {
+        assert_eq!(
+            air_pollution_url("key", 50.1109, 8.6821),
+            "https://api.openweathermap.org/data/2.5/air_pollution?lat=50.1109&lon=8.6821&appid=key"
+        );
+    }
+
+    #[test]
+    fn test_aqi_color() {
+        assert_eq!(aqi_color(1), Rgb565::new(0, 50, 0));
+        assert_eq!(aqi_color(5), Rgb565::RED);
+    }
+}
@@ -0,0 +1,110 @@
+// alerts.rs
+use serde::Deserialize;
+use weather_common::http_utils::urlencode;
+
+/// Response of the OpenWeatherMap One Call 3.0 endpoint, reduced to the alerts
+/// The response has no `alerts` field while no alert is active.
+#[derive(Deserialize, Debug)]
+pub struct OneCallResponse {
+    #[serde(default)]
+    pub alerts: Vec<Alert>,
+}
+
+/// A severe weather warning of a national weather service
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// Short name, e.g. "Storm warning"
+    pub event: String,
+    /// Full text of the warning, only logged
+    #[serde(default)]
+    pub description: String,
+    /// End of the warning (UTC seconds)
+    pub end: i64,
+}
+
+impl Alert {
+    /// Whether the warning is still in effect at `now` (UTC seconds)
+    pub fn is_active(&self, now: i64) -> bool {
+        now < self.end
+    }
+}
+
+/// Builds the One Call 3.0 URL of the alerts at the given coordinates
+/// All other parts of the response are excluded, the forecast comes from the 2.5 API.
+pub fn onecall_alerts_url(api_key: &str, lat: f64, lon: f64) -> String {
+    format!(
+        "https://api.openweathermap.org/data/3.0/onecall?lat={}&lon={}&exclude=current,minutely,hourly,daily&appid={}",
+        lat,
+        lon,
+        urlencode(api_key)
+    )
+}
+
+/// Text of the alert banner: the first event, followed by "+n" for the other alerts
+/// The event is cut off so that the text has at most `max_chars` characters.
+pub fn banner_text(events: &[String], max_chars: usize) -> String {
+    let Some(first) = events.first() else {
+        return String::new();
+    };
+    let more = match events.len() {
+        1 => String::new(),
+        len => format!(" +{}", len - 1),
+    };
+    let room = max_chars.saturating_sub(more.chars().count());
+    let mut text: String = first.chars().take(room).collect();
+    text.push_str(&more);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_alerts() {
+        let json = r#"{
+            "lat":50.1109,"lon":8.6821,"timezone":"Europe/Berlin","timezone_offset":7200,
+            "alerts":[{
+                "sender_name":"Deutscher Wetterdienst",
+                "event":"Storm warning",
+                "start":1728900000,
+                "end":1728943200,
+                "description":"Gusts up to 90 km/h are expected.",
+                "tags":["Wind"]
+            }]
+        }"#;
+        let response: OneCallResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.alerts.len(), 1);
+        assert_eq!(response.alerts[0].event, "Storm warning");
+        assert_eq!(
+            response.alerts[0].description,
+            "Gusts up to 90 km/h are expected."
+        );
+        assert!(response.alerts[0].is_active(1728943199));
+        assert!(!response.alerts[0].is_active(1728943200));
+
+        // No alert active
+        let response: OneCallResponse =
+            serde_json::from_str(r#"{"lat":50.1109,"lon":8.6821}"#).unwrap();
+        assert!(response.alerts.is_empty());
+    }
+
+    #[test]
+    fn test_onecall_alerts_url() {
+        assert_eq!(
+            onecall_alerts_url("key", 50.1109, 8.6821),
+            "https://api.openweathermap.org/data/3.0/onecall?lat=50.1109&lon=8.6821&exclude=current,minutely,hourly,daily&appid=key"
+        );
+    }
+
+    #[test]
+    fn test_banner_text() {
+        let events = vec!["Storm warning".to_string(), "Frost".to_string()];
+        assert_eq!(banner_text(&events[..1], 18), "Storm warning");
+        assert_eq!(banner_text(&events, 18), "Storm warning +1");
+        // The count is kept, the event is cut off
+        assert_eq!(banner_text(&events, 10), "Storm w +1");
+        assert_eq!(banner_text(&["Sturmböen".to_string()], 6), "Sturmb");
+        assert_eq!(banner_text(&[], 18), "");
+    }
+}
@@ -0,0 +1,60 @@
+// boot.rs
+
+/// Name shown on the boot splash, short enough for the 135 pixel wide panel
+pub const FIRMWARE_NAME: &str = "ESP32 Weather";
+
+/// Initialization steps shown on the boot splash, in the order they run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BootStep {
+    Wifi,
+    TimeSync,
+    Mqtt,
+    Weather,
+}
+
+impl BootStep {
+    /// All steps in boot order
+    pub const ALL: [BootStep; 4] = [
+        BootStep::Wifi,
+        BootStep::TimeSync,
+        BootStep::Mqtt,
+        BootStep::Weather,
+    ];
+
+    /// What the device is waiting for during this step
+    pub fn label(self) -> &'static str {
+        match self {
+            BootStep::Wifi => "Joining Wi-Fi",
+            BootStep::TimeSync => "Syncing time",
+            BootStep::Mqtt => "Connecting MQTT",
+            BootStep::Weather => "Loading weather",
+        }
+    }
+
+    /// Status line of the splash, e.g. "Syncing time... 2/4"
+    pub fn status_text(self) -> String {
+        let number = Self::ALL.iter().position(|&step| step == self).unwrap_or(0) + 1;
+        format!("{}... {}/{}", self.label(), number, Self::ALL.len())
+    }
+}
+
+/// Version line of the splash, e.g. "v0.1.0"
+pub fn version_text() -> String {
+    format!("v{}", env!("CARGO_PKG_VERSION"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_text() {
+        assert_eq!(BootStep::Wifi.status_text(), "Joining Wi-Fi... 1/4");
+        assert_eq!(BootStep::Weather.status_text(), "Loading weather... 4/4");
+
+        // FONT_6X10 on the 135 pixel wide panel leaves room for 22 characters
+        for step in BootStep::ALL {
+            assert!(step.status_text().len() <= 22, "{:?}", step);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// daily_range.rs
+use crate::temperature::format_temp;
+
+/// Lowest and highest temperature observed on one local day
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DailyRange {
+    /// Local day of the samples, see `day_key`
+    pub day: u32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Size of the NVS representation: day, min and max as little-endian 32-bit values
+pub const ENCODED_LEN: usize = 12;
+
+/// Key of a local day that sorts chronologically, e.g. 20241025
+pub fn day_key(year: i32, month: u32, day: u32) -> u32 {
+    year.max(0) as u32 * 10_000 + month * 100 + day
+}
+
+/// Adds a temperature sample taken on local day `day` to the range
+///
+/// A sample of a later day starts a new range (the midnight reset). A sample of an
+/// earlier day is dropped: a fetch that started before midnight may only finish after
+/// the new day's range has begun, and must not widen it.
+pub fn record_sample(range: Option<DailyRange>, day: u32, temp: f32) -> DailyRange {
+    match range {
+        Some(range) if range.day > day => range,
+        Some(range) if range.day == day => DailyRange {
+            min: range.min.min(temp),
+            max: range.max.max(temp),
+            ..range
+        },
+        _ => DailyRange {
+            day,
+            min: temp,
+            max: temp,
+        },
+    }
+}
+
+impl DailyRange {
+    /// Display text, e.g. "8.2/15.6°C", formatted like the current temperature
+    pub fn label(&self, unit: &str, decimals: usize, show_sign: bool) -> String {
+        format!(
+            "{}/{}",
+            format_temp(self.min, "", decimals, show_sign),
+            format_temp(self.max, unit, decimals, show_sign)
+        )
+    }
+
+    /// Encodes the range for storing it in NVS
+    pub fn to_bytes(self) -> [u8; ENCODED_LEN] {
+        let mut bytes = [0u8; ENCODED_LEN];
+        bytes[0..4].copy_from_slice(&self.day.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.min.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.max.to_le_bytes());
+        bytes
+    }
+
+    /// Decodes a range stored with `to_bytes`, `None` for data of another length
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: &[u8; ENCODED_LEN] = bytes.try_into().ok()?;
+        let word = |i: usize| [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+        let range = DailyRange {
+            day: u32::from_le_bytes(word(0)),
+            min: f32::from_le_bytes(word(4)),
+            max: f32::from_le_bytes(word(8)),
+        };
+        (range.min <= range.max).then_some(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_sample() {
+        let today = day_key(2024, 10, 25);
+        let range = record_sample(None, today, 12.5);
+        assert_eq!((range.min, range.max), (12.5, 12.5));
+
+        let range = record_sample(Some(range), today, 15.0);
+        let range = record_sample(Some(range), today, 9.5);
+        assert_eq!((range.min, range.max), (9.5, 15.0));
+
+        // The first sample after midnight starts a new range
+        let tomorrow = day_key(2024, 10, 26);
+        let range = record_sample(Some(range), tomorrow, 8.0);
+        assert_eq!(range.day, tomorrow);
+        assert_eq!((range.min, range.max), (8.0, 8.0));
+    }
+
+    #[test]
+    fn test_record_sample_straddling_midnight() {
+        // Measured at 23:59 but stored after the first sample of the new day
+        let range = record_sample(None, day_key(2025, 1, 1), 3.0);
+        let range = record_sample(Some(range), day_key(2024, 12, 31), -4.0);
+        assert_eq!(range.day, day_key(2025, 1, 1));
+        assert_eq!((range.min, range.max), (3.0, 3.0));
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let range = DailyRange {
+            day: day_key(2024, 10, 25),
+            min: -3.5,
+            max: 12.25,
+        };
+        assert_eq!(DailyRange::from_bytes(&range.to_bytes()), Some(range));
+        assert_eq!(DailyRange::from_bytes(&range.to_bytes()[..8]), None);
+        assert_eq!(DailyRange::from_bytes(&[]), None);
+    }
+
+    #[test]
+    fn test_label() {
+        let range = DailyRange {
+            day: day_key(2024, 10, 25),
+            min: 8.24,
+            max: 15.6,
+        };
+        assert_eq!(range.label("°C", 1, false), "8.2/15.6°C");
+        assert_eq!(range.label("°C", 0, true), "+8/+16°C");
+    }
+}
@@ -0,0 +1,57 @@
+// device_log.rs
+use esp_idf_svc::log::EspLogger;
+use log::{Log, Metadata, Record};
+use std::sync::OnceLock;
+
+/// Device name put in front of every log message, set once after loading the secrets
+static DEVICE_NAME: OnceLock<String> = OnceLock::new();
+
+static LOGGER: DeviceLogger = DeviceLogger {
+    inner: EspLogger::new(),
+};
+
+/// Forwards to the ESP-IDF logger, prefixing each message with the device name
+struct DeviceLogger {
+    inner: EspLogger,
+}
+
+impl Log for DeviceLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        match DEVICE_NAME.get() {
+            Some(name) => self.inner.log(
+                &Record::builder()
+                    .args(format_args!("[{}] {}", name, record.args()))
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .build(),
+            ),
+            None => self.inner.log(record),
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Installs the logger in place of `EspLogger::initialize_default`.
+/// Messages stay unprefixed until `set_device_name` is called.
+pub fn initialize() -> anyhow::Result<()> {
+    log::set_logger(&LOGGER).map_err(|e| anyhow::anyhow!("Failed to install the logger: {}", e))?;
+    LOGGER.inner.initialize();
+    Ok(())
+}
+
+/// Sets the prefix of all following log messages, e.g. "[kitchen] MQTT Connected to broker".
+/// An empty name keeps the messages unprefixed, later calls are ignored.
+pub fn set_device_name(name: &str) {
+    if !name.is_empty() {
+        let _ = DEVICE_NAME.set(name.to_string());
+    }
+}
@@ -0,0 +1,73 @@
+// dimming.rs
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+
+/// First hour (local time) of the night dimming
+pub const NIGHT_START_HOUR: u32 = 23;
+/// First hour (local time) after the night dimming
+pub const NIGHT_END_HOUR: u32 = 6;
+/// Brightness factor used at night
+pub const NIGHT_DIM_FACTOR: f32 = 0.4;
+/// Backlight brightness in percent used at night, with a PWM-controlled backlight
+pub const NIGHT_BACKLIGHT_PERCENT: u8 = 20;
+
+/// Whether the given local hour is within the night dimming
+pub fn is_night_hour(hour: u32) -> bool {
+    !(NIGHT_END_HOUR..NIGHT_START_HOUR).contains(&hour)
+}
+
+/// Returns the brightness factor for the given local hour (1.0 = full brightness)
+pub fn dim_factor_for_hour(hour: u32) -> f32 {
+    if is_night_hour(hour) {
+        NIGHT_DIM_FACTOR
+    } else {
+        1.0
+    }
+}
+
+/// Returns the backlight brightness in percent for the given local hour
+pub fn backlight_percent_for_hour(hour: u32) -> u8 {
+    if is_night_hour(hour) {
+        NIGHT_BACKLIGHT_PERCENT
+    } else {
+        100
+    }
+}
+
+/// Scales a color toward black, `factor` is clamped to 0.0..=1.0
+pub fn scale_color(color: Rgb565, factor: f32) -> Rgb565 {
+    let factor = factor.clamp(0.0, 1.0);
+    let scale = |channel: u8| (channel as f32 * factor).round() as u8;
+    Rgb565::new(scale(color.r()), scale(color.g()), scale(color.b()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dim_factor_for_hour() {
+        assert_eq!(dim_factor_for_hour(22), 1.0);
+        assert_eq!(dim_factor_for_hour(23), NIGHT_DIM_FACTOR);
+        assert_eq!(dim_factor_for_hour(0), NIGHT_DIM_FACTOR);
+        assert_eq!(dim_factor_for_hour(5), NIGHT_DIM_FACTOR);
+        assert_eq!(dim_factor_for_hour(6), 1.0);
+        assert_eq!(dim_factor_for_hour(12), 1.0);
+    }
+
+    #[test]
+    fn test_backlight_percent_for_hour() {
+        assert_eq!(backlight_percent_for_hour(22), 100);
+        assert_eq!(backlight_percent_for_hour(23), NIGHT_BACKLIGHT_PERCENT);
+        assert_eq!(backlight_percent_for_hour(5), NIGHT_BACKLIGHT_PERCENT);
+        assert_eq!(backlight_percent_for_hour(6), 100);
+    }
+
+    #[test]
+    fn test_scale_color() {
+        assert_eq!(scale_color(Rgb565::WHITE, 1.0), Rgb565::WHITE);
+        assert_eq!(scale_color(Rgb565::WHITE, 0.0), Rgb565::BLACK);
+        assert_eq!(scale_color(Rgb565::WHITE, 0.5), Rgb565::new(16, 32, 16));
+        // Out-of-range factors are clamped
+        assert_eq!(scale_color(Rgb565::YELLOW, 2.0), Rgb565::YELLOW);
+    }
+}
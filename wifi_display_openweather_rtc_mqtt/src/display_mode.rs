@@ -0,0 +1,95 @@
+// display_mode.rs
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// What the display shows, switched via the MQTT mode topic
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// The full dashboard: weather, forecast, movement events and status line
+    #[default]
+    Home,
+    /// Only the date and time
+    Away,
+}
+
+impl DisplayMode {
+    /// Parses an MQTT payload, "home" or "away" in any case and with surrounding whitespace
+    pub fn parse(payload: &str) -> Option<Self> {
+        match payload.trim().to_ascii_lowercase().as_str() {
+            "home" => Some(Self::Home),
+            "away" => Some(Self::Away),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Home => "home",
+            Self::Away => "away",
+        }
+    }
+
+    /// The value stored in NVS
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::Home => 0,
+            Self::Away => 1,
+        }
+    }
+
+    /// Decodes a value stored with `to_u8`, `None` for unknown values
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Home),
+            1 => Some(Self::Away),
+            _ => None,
+        }
+    }
+}
+
+/// The current mode, set by the MQTT event thread and read by the main loop
+pub struct SharedDisplayMode(AtomicU8);
+
+impl SharedDisplayMode {
+    pub const fn new() -> Self {
+        Self(AtomicU8::new(0))
+    }
+
+    pub fn get(&self) -> DisplayMode {
+        DisplayMode::from_u8(self.0.load(Ordering::Relaxed)).unwrap_or_default()
+    }
+
+    pub fn set(&self, mode: DisplayMode) {
+        self.0.store(mode.to_u8(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(DisplayMode::parse("home"), Some(DisplayMode::Home));
+        assert_eq!(DisplayMode::parse(" Away\n"), Some(DisplayMode::Away));
+        assert_eq!(DisplayMode::parse("AWAY"), Some(DisplayMode::Away));
+        assert_eq!(DisplayMode::parse(""), None);
+        assert_eq!(DisplayMode::parse("vacation"), None);
+    }
+
+    #[test]
+    fn test_nvs_round_trip() {
+        for mode in [DisplayMode::Home, DisplayMode::Away] {
+            assert_eq!(DisplayMode::from_u8(mode.to_u8()), Some(mode));
+            assert_eq!(DisplayMode::parse(mode.as_str()), Some(mode));
+        }
+        assert_eq!(DisplayMode::from_u8(7), None);
+    }
+
+    #[test]
+    fn test_shared_mode() {
+        let shared = SharedDisplayMode::new();
+        assert_eq!(shared.get(), DisplayMode::Home);
+        shared.set(DisplayMode::Away);
+        assert_eq!(shared.get(), DisplayMode::Away);
+    }
+}
@@ -0,0 +1,124 @@
+// forecast.rs
+use crate::time_utils;
+use serde::{Deserialize, Serialize};
+
+/// Response of the OpenWeatherMap `/data/2.5/forecast` endpoint (3-hour steps over 5 days)
+#[derive(Deserialize, Debug)]
+pub struct ForecastResponse {
+    pub list: Vec<ForecastItem>,
+}
+
+/// One 3-hour step of the forecast
+#[derive(Deserialize, Debug)]
+pub struct ForecastItem {
+    pub dt: i64,
+    pub main: ForecastMain,
+    pub weather: Vec<ForecastWeather>,
+}
+
+/// Temperature of a forecast step
+#[derive(Deserialize, Debug)]
+pub struct ForecastMain {
+    pub temp: f32,
+}
+
+/// Weather condition of a forecast step (only the icon is used)
+#[derive(Deserialize, Debug)]
+pub struct ForecastWeather {
+    pub icon: String,
+}
+
+/// A single day of the forecast as shown on the display
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ForecastEntry {
+    pub dt: i64,
+    pub temp: f32,
+    pub icon: String,
+}
+
+/// Hour of the day (local time) used as the representative sample for a day
+const SAMPLE_HOUR: i64 = 12;
+
+/// Reduce the 3-hour forecast to one entry per day
+/// For every day after `now` the step closest to noon (Berlin time) is picked.
+///
+/// # Arguments
+/// * `items` - Forecast steps in chronological order
+/// * `now` - Current UTC timestamp, its day is skipped
+pub fn sample_daily(items: &[ForecastItem], now: i64) -> Vec<ForecastEntry> {
+    let local_date = |ts: i64| {
+        let (year, month, day, _, _, _) = time_utils::utc_to_berlin(ts);
+        (year, month, day)
+    };
+    let noon_distance = |ts: i64| {
+        let (_, _, _, hour, _, _) = time_utils::utc_to_berlin(ts);
+        (hour as i64 - SAMPLE_HOUR).abs()
+    };
+
+    let today = local_date(now);
+    let mut days: Vec<((i32, u32, u32), &ForecastItem)> = Vec::new();
+
+    for item in items.iter().filter(|item| !item.weather.is_empty()) {
+        let date = local_date(item.dt);
+        if date <= today {
+            continue;
+        }
+        match days.last_mut() {
+            Some((last_date, best)) if *last_date == date => {
+                if noon_distance(item.dt) < noon_distance(best.dt) {
+                    *best = item;
+                }
+            }
+            _ => days.push((date, item)),
+        }
+    }
+
+    days.into_iter()
+        .map(|(_, item)| ForecastEntry {
+            dt: item.dt,
+            temp: item.main.temp,
+            icon: item.weather[0].icon.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(dt: i64, temp: f32) -> ForecastItem {
+        ForecastItem {
+            dt,
+            main: ForecastMain { temp },
+            weather: vec![ForecastWeather {
+                icon: "01d".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_sample_daily() {
+        // 2025-01-15 00:00 UTC (01:00 CET)
+        let day = 86_400;
+        let start = 1_736_899_200;
+        let items: Vec<ForecastItem> = (0..40)
+            .map(|i| item(start + i * 3 * 3600, i as f32))
+            .collect();
+
+        let entries = sample_daily(&items, start);
+
+        // Today is skipped, the 5-day window then covers four more days
+        assert_eq!(entries.len(), 4);
+        // The 3-hour step closest to noon CET is 12:00 UTC (13:00 CET)
+        assert_eq!(entries[0].dt, start + day + 12 * 3600);
+        assert_eq!(entries[1].dt, start + 2 * day + 12 * 3600);
+        assert_eq!(entries[0].temp, 12.0);
+    }
+
+    #[test]
+    fn test_sample_daily_skips_past() {
+        let now = 1_736_942_400; // 2025-01-15 12:00 UTC
+        let items = vec![item(now - 3 * 3600, 1.0), item(now + 3 * 3600, 2.0)];
+        assert!(sample_daily(&items, now).is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+// ha_discovery.rs
+use serde::Serialize;
+
+/// Home Assistant discovery prefix
+const DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// Node id under which all sensors of this device are announced
+const NODE_ID: &str = "esp32weather";
+
+/// Discovery config of a single Home Assistant sensor
+#[derive(Serialize, Debug)]
+struct SensorConfig<'a> {
+    name: &'a str,
+    unique_id: String,
+    state_topic: &'a str,
+    availability_topic: &'a str,
+    value_template: &'a str,
+    unit_of_measurement: &'a str,
+    device_class: &'a str,
+    device: DeviceInfo<'a>,
+}
+
+/// Device the sensors are grouped under in Home Assistant
+#[derive(Serialize, Debug)]
+struct DeviceInfo<'a> {
+    identifiers: [&'a str; 1],
+    name: &'a str,
+    model: &'a str,
+}
+
+/// Build the retained discovery messages for the weather sensors
+/// The sensors read their values from the JSON published on `state_topic` (`weather/<city>`).
+///
+/// # Arguments
+/// * `state_topic` - Topic of the weather publish
+/// * `availability_topic` - Topic carrying "online"/"offline"
+/// * `temperature_unit` - Unit label of the temperature, e.g. "°C"
+/// * `wind_speed_unit` - Unit label of the wind speed, e.g. "m/s"
+///
+/// # Returns
+/// * `Ok(Vec<(topic, payload)>)` - One message per sensor
+/// * `Err` - Serialization error
+pub fn discovery_messages(
+    state_topic: &str,
+    availability_topic: &str,
+    temperature_unit: &str,
+    wind_speed_unit: &str,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let sensors = [
+        (
+            "temperature",
+            "Temperature",
+            "{{ value_json.main.temp }}",
+            temperature_unit,
+        ),
+        (
+            "humidity",
+            "Humidity",
+            "{{ value_json.main.humidity }}",
+            "%",
+        ),
+        (
+            "wind_speed",
+            "Wind Speed",
+            "{{ value_json.wind.speed }}",
+            wind_speed_unit,
+        ),
+    ];
+
+    sensors
+        .iter()
+        .map(|&(object_id, name, value_template, unit)| {
+            let config = SensorConfig {
+                name,
+                unique_id: format!("{}_{}", NODE_ID, object_id),
+                state_topic,
+                availability_topic,
+                value_template,
+                unit_of_measurement: unit,
+                device_class: object_id,
+                device: DeviceInfo {
+                    identifiers: [NODE_ID],
+                    name: "ESP32 Weather Station",
+                    model: "ESP32 + ST7789",
+                },
+            };
+            let topic = format!(
+                "{}/sensor/{}/{}/config",
+                DISCOVERY_PREFIX, NODE_ID, object_id
+            );
+            Ok((topic, serde_json::to_string(&config)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_messages() {
+        let messages = discovery_messages("weather/Berlin", "device/status", "°C", "m/s").unwrap();
+        assert_eq!(messages.len(), 3);
+
+        let (topic, payload) = &messages[0];
+        assert_eq!(
+            topic,
+            "homeassistant/sensor/esp32weather/temperature/config"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(payload).unwrap();
+        assert_eq!(json["state_topic"], "weather/Berlin");
+        assert_eq!(json["availability_topic"], "device/status");
+        assert_eq!(json["unit_of_measurement"], "°C");
+        assert_eq!(json["value_template"], "{{ value_json.main.temp }}");
+        assert_eq!(json["unique_id"], "esp32weather_temperature");
+        assert_eq!(json["device"]["identifiers"][0], "esp32weather");
+
+        assert_eq!(
+            messages[2].0,
+            "homeassistant/sensor/esp32weather/wind_speed/config"
+        );
+    }
+}
@@ -8,262 +8,722 @@
 // - ST7789 TFT display output
 // ===============================================================================
 
-use crate::secrets::Secrets;
-use core::ptr::addr_of_mut;
+use crate::secrets::{OpenWeatherConfig, Secrets, WeatherProviderConfig, WiFiNetwork};
+use core::time::Duration;
+use display_hal::{
+    build_display_for_panel, Backlight, DisplayPinNumbers, DisplayPins, FrameBuffer, GpioPool,
+    PanelConfig,
+};
 use embedded_graphics::{
     mono_font::{iso_8859_1::FONT_10X20, MonoTextStyle, MonoTextStyleBuilder},
     pixelcolor::Rgb565,
     prelude::*,
     text::Text,
 };
-use embedded_hal::digital::OutputPin as OutputPinTrait;
-use embedded_hal::spi::SpiDevice;
-use embedded_svc::http::client::Client;
 
 // === HAL Imports ===
-use esp_idf_hal::{
-    delay::FreeRtos,
-    gpio::{AnyIOPin, OutputPin, PinDriver},
-    peripherals::Peripherals,
-    prelude::*,
-    spi::{config::Config, SpiDeviceDriver, SpiDriver, SpiDriverConfig},
-};
-
-use esp_idf_svc::http::client::{Configuration as HttpConfiguration, EspHttpConnection};
-use esp_idf_svc::mqtt::client::{EspMqttClient, MqttClientConfiguration};
-use esp_idf_svc::sntp::{EspSntp, SyncStatus};
+use esp_idf_hal::gpio::{AnyOutputPin, Output, PinDriver};
+use esp_idf_hal::task::watchdog::{TWDTConfig, TWDTDriver};
+use esp_idf_hal::{delay::FreeRtos, peripherals::Peripherals};
+
+use esp_idf_svc::http::server::{Configuration as HttpServerConfiguration, EspHttpServer};
+use esp_idf_svc::http::Method;
+use esp_idf_svc::mqtt::client::{EspMqttClient, LwtConfiguration, MqttClientConfiguration};
+use esp_idf_svc::sntp::{EspSntp, SntpConf, SyncMode, SyncStatus};
+use esp_idf_svc::tls::X509;
 use esp_idf_svc::wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi};
-use esp_idf_svc::{eventloop::EspSystemEventLoop, nvs::EspDefaultNvsPartition};
+use esp_idf_svc::{
+    eventloop::EspSystemEventLoop,
+    nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault},
+};
 
+use embedded_svc::io::{Read, Write};
 use esp_idf_sys;
 use log::*;
-use mipidsi::{
-    models::ST7789,
-    options::{ColorInversion, ColorOrder},
-    Builder,
-};
+use mipidsi::options::{Orientation, Rotation};
 use profont::PROFONT_24_POINT;
-use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
-
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use weather_common::scheduler::Cadence;
+use weather_common::{backoff, geocoding, http_utils};
+
+mod air_quality;
+mod alerts;
+mod boot;
+mod daily_range;
+mod device_log;
+mod dimming;
+mod display_mode;
+mod forecast;
+mod ha_discovery;
+mod movement;
+mod mqtt_session;
+mod mutex_utils;
+mod outbox;
+mod power_save;
+mod provider;
+mod render;
 mod secrets;
+mod stats;
+mod temperature;
+mod theme;
 mod time_utils;
+mod trend;
+mod weather;
 mod weather_icons;
-
-use weather_icons::get_weather_icon;
+mod web_config;
+mod wifi_retry;
+
+use boot::BootStep;
+use daily_range::DailyRange;
+use display_mode::{DisplayMode, SharedDisplayMode};
+use forecast::ForecastEntry;
+use mqtt_session::MqttSession;
+use mutex_utils::lock_recovering;
+use outbox::{Outbox, OutboxMessage};
+use power_save::{PowerAction, PowerSave};
+use provider::{FieldMapping, WeatherProvider};
+use render::{
+    dim_colors, draw_boot_status, only_seconds_changed, render_display, render_time, rssi_to_bars,
+    stale_marker, DisplayState, ForecastCell, Layout,
+};
+use stats::Stats;
+use trend::TempTrend;
+use weather::{OwmError, WeatherResponse};
+use wifi_retry::WifiRetry;
 
 // ===============================================================================
 // GLOBAL SHARED DATA
 // ===============================================================================
 
 /// Thread-safe queue for storing movement detection timestamps
-/// Maximum 6 events are kept in memory (oldest are removed)
-static MOVEMENT_EVENTS: Mutex<Option<Arc<Mutex<VecDeque<String>>>>> = Mutex::new(None);
+/// At most `movement_history_len` events are kept in memory (oldest are removed)
+static MOVEMENT_EVENTS: Mutex<Option<Arc<Mutex<VecDeque<i64>>>>> = Mutex::new(None);
 
-/// Thread-safe storage for the most recent weather data
+/// Thread-safe storage for the most recent weather data and its fetch time (UTC seconds)
 /// Updated every 15 minutes from OpenWeatherMap API
-static LAST_WEATHER_DATA: Mutex<Option<WeatherResponse>> = Mutex::new(None);
+static LAST_WEATHER_DATA: Mutex<Option<(WeatherResponse, u64)>> = Mutex::new(None);
 
-// ===============================================================================
-// DATA STRUCTURES
-// ===============================================================================
+/// Most recent weather data and fetch time of the other cities of `openweather.cities`,
+/// by the configured name. Only the home city uses `LAST_WEATHER_DATA`.
+static OTHER_CITIES_WEATHER: Mutex<BTreeMap<String, (WeatherResponse, u64)>> =
+    Mutex::new(BTreeMap::new());
 
-/// Complete weather response from OpenWeatherMap API
-#[derive(Deserialize, Serialize, Debug, Clone)]
-struct WeatherResponse {
-    weather: Vec<Weather>,
-    main: Main,
-    wind: Wind,
-    name: String,
-}
+/// Thread-safe storage for the most recent daily forecast (one entry per day)
+/// Updated together with the weather data every 15 minutes
+static LAST_FORECAST_DATA: Mutex<Option<Vec<ForecastEntry>>> = Mutex::new(None);
 
-/// Weather condition details (description and icon code)
-#[derive(Deserialize, Serialize, Debug, Clone)]
-struct Weather {
-    description: String,
-    icon: String,
-}
+/// Thread-safe storage for the most recent air quality index (1-5)
+/// Updated together with the weather data, `None` while no coordinates are configured
+static LAST_AIR_QUALITY: Mutex<Option<u8>> = Mutex::new(None);
 
-/// Main weather parameters (temperature and humidity)
-#[derive(Deserialize, Serialize, Debug, Clone)]
-struct Main {
-    temp: f32,
-    humidity: i32,
-}
+/// Thread-safe storage for the most recent weather alerts (One Call 3.0)
+/// Updated together with the air quality, expired alerts are filtered when displayed
+static LAST_ALERTS: Mutex<Vec<alerts::Alert>> = Mutex::new(Vec::new());
 
-/// Wind information
-#[derive(Deserialize, Serialize, Debug, Clone)]
-struct Wind {
-    speed: f32,
-}
+/// Output pulsed on every movement event (LED or buzzer), `None` if not configured
+/// Set up at boot, used by the MQTT event thread.
+static MOVEMENT_ALERT: Mutex<Option<PinDriver<'static, AnyOutputPin, Output>>> = Mutex::new(None);
+
+/// Temperature trend of the most recent weather data against the reading before it
+/// `None` for the first reading, after a stale period and after a change of the units
+static LAST_TEMP_TREND: Mutex<Option<TempTrend>> = Mutex::new(None);
 
-/// Display state structure for change detection
-/// Used to minimize screen flicker by only redrawing when data changes
-#[derive(Clone, PartialEq, Debug)]
-struct DisplayState {
-    time_str: String,
-    date_str: String,
-    weather_temp: String,
-    weather_desc: String,
-    weather_icon: String,
-    wind_str: String,
-    hum_str: String,
-    city_name: String,
-    movement_events: Vec<String>,
+/// Lowest and highest temperature of the current local day, from the 15-minute samples
+/// Restored from NVS at boot and saved on every change.
+static DAILY_RANGE: Mutex<Option<DailyRange>> = Mutex::new(None);
+
+/// MQTT connection state, updated by the MQTT event thread
+/// The main loop picks up every (re)connect, subscribes and sends the per-connection messages.
+static MQTT_SESSION: MqttSession = MqttSession::new();
+
+/// Counters of successful and failed weather fetches and of Wi-Fi reconnects since boot
+static STATS: Stats = Stats::new();
+
+/// OpenWeatherMap settings in use: `secrets.toml`, with city and units changeable via the web server
+static RUNTIME_OPENWEATHER: Mutex<Option<OpenWeatherConfig>> = Mutex::new(None);
+
+/// Set by the web server after a configuration change
+/// The main loop consumes it and fetches the weather right away instead of at the next interval.
+static CONFIG_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Set by the MQTT event thread on a message on the refresh topic
+/// The main loop consumes it like `CONFIG_CHANGED`, see `REFRESH_MIN_GAP_SECS`.
+static REFRESH_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Display mode, set by the MQTT event thread on a message on the mode topic
+/// Restored from NVS at boot, the main loop saves every change.
+static DISPLAY_MODE: SharedDisplayMode = SharedDisplayMode::new();
+
+/// A refresh command within this many seconds of the last fetch is ignored,
+/// so that repeated commands cannot hammer the API
+const REFRESH_MIN_GAP_SECS: u64 = 60;
+
+/// Runs `f` with the movement event queue (newest first), `None` before it is initialized
+/// A poisoned lock is recovered instead of ending the main loop, see `lock_recovering`.
+fn with_movement_events<R>(f: impl FnOnce(&mut VecDeque<i64>) -> R) -> Option<R> {
+    let events = lock_recovering(&MOVEMENT_EVENTS).clone()?;
+    let mut events = lock_recovering(&events);
+    Some(f(&mut events))
 }
 
-impl DisplayState {
-    /// Create a new empty display state
-    fn new() -> Self {
-        Self {
-            time_str: String::new(),
-            date_str: String::new(),
-            weather_temp: String::new(),
-            weather_desc: String::new(),
-            weather_icon: String::new(),
-            wind_str: String::new(),
-            hum_str: String::new(),
-            city_name: String::new(),
-            movement_events: Vec::new(),
-        }
-    }
+/// Runs `f` with the most recent weather data and its fetch time, `None` before the first data
+/// A poisoned lock is recovered instead of ending the main loop, see `lock_recovering`.
+fn with_weather<R>(f: impl FnOnce(&mut Option<(WeatherResponse, u64)>) -> R) -> R {
+    f(&mut lock_recovering(&LAST_WEATHER_DATA))
 }
 
 // ===============================================================================
 // WEATHER API FUNCTIONS
 // ===============================================================================
 
+/// Build an OpenWeatherMap API URL for the given endpoint (e.g. `weather`, `forecast`)
+/// Location (coordinates or city), units and language are taken from the configuration.
+fn owm_url(endpoint: &str, config: &OpenWeatherConfig) -> String {
+    // Coordinates are more precise than the city name, prefer them when configured
+    let location = match config.coordinates() {
+        Some((lat, lon)) => format!("lat={}&lon={}", lat, lon),
+        None => format!("q={}", http_utils::urlencode(&config.city)),
+    };
+
+    format!(
+        "https://api.openweathermap.org/data/2.5/{}?{}&appid={}&units={}&lang={}",
+        endpoint,
+        location,
+        http_utils::urlencode(&config.api_key),
+        http_utils::urlencode(config.units()),
+        http_utils::urlencode(config.lang())
+    )
+}
+
 /// Fetch current weather data from OpenWeatherMap API
 ///
 /// # Arguments
-/// * `api_key` - Your OpenWeatherMap API key
-/// * `city` - City name to get weather for
+/// * `config` - API key, city, units and language
 ///
 /// # Returns
 /// * `Ok(WeatherResponse)` - Parsed weather data
 /// * `Err` - Network or parsing error
-fn get_weather(api_key: &str, city: &str) -> anyhow::Result<WeatherResponse> {
-    let url = format!(
-        "https://api.openweathermap.org/data/2.5/weather?q={}&appid={}&units=metric&lang=en",
-        city, api_key
-    );
+fn get_weather(config: &OpenWeatherConfig) -> anyhow::Result<WeatherResponse> {
+    feed_watchdog();
+    let result = http_utils::http_get(&owm_url("weather", config), config.http_timeout())
+        .map_err(OwmError::from_http_error)
+        .and_then(|body| weather::parse_weather(&http_utils::decode_body(&body)));
+
+    // An unknown city name is a configuration error, list what the name could have meant
+    if let Err(e) = &result {
+        let not_found = e
+            .downcast_ref::<OwmError>()
+            .is_some_and(|owm_error| owm_error.cod == 404);
+        if not_found && config.coordinates().is_none() {
+            log_city_candidates(config);
+        }
+    }
+    result
+}
 
-    // Create HTTPS connection with certificate bundle
-    let connection = EspHttpConnection::new(&HttpConfiguration {
-        use_global_ca_store: true,
-        crt_bundle_attach: Some(esp_idf_sys::esp_crt_bundle_attach),
-        timeout: Some(core::time::Duration::from_secs(30)),
-        ..Default::default()
-    })?;
+/// Resolve the configured city with the OpenWeatherMap geocoding API and log the matches,
+/// so the right one can be put into `city = "City,CC"` or `lat`/`lon`
+fn log_city_candidates(config: &OpenWeatherConfig) {
+    let url = geocoding::direct_url(&config.city, &config.api_key);
+    match fetch_owm::<Vec<geocoding::GeoCandidate>>(&url, config.http_timeout()) {
+        Ok(candidates) if candidates.is_empty() => {
+            warn!(
+                "City '{}' not found by the geocoding API either",
+                config.city
+            );
+        }
+        Ok(candidates) => {
+            warn!("City '{}' not found, candidates:", config.city);
+            for candidate in &candidates {
+                warn!("  {}", candidate);
+            }
+        }
+        Err(e) => warn!("City lookup for '{}' failed: {}", config.city, e),
+    }
+}
+
+/// Fetch the 5-day forecast and reduce it to one entry per day
+///
+/// # Arguments
+/// * `config` - API key, city, units and language
+///
+/// # Returns
+/// * `Ok(Vec<ForecastEntry>)` - One entry per upcoming day, today excluded
+/// * `Err` - Network or parsing error
+fn get_forecast(config: &OpenWeatherConfig) -> anyhow::Result<Vec<ForecastEntry>> {
+    let forecast: forecast::ForecastResponse =
+        fetch_owm(&owm_url("forecast", config), config.http_timeout())?;
 
-    let mut client = Client::wrap(connection);
-    let request = client.get(&url)?;
-    let mut response = request.submit()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    Ok(forecast::sample_daily(&forecast.list, now))
+}
 
-    let status = response.status();
-    info!("Weather API response status: {}", status);
+/// Fetch the current air quality index at the given coordinates
+///
+/// # Arguments
+/// * `timeout` - Network timeout, `openweather.http_timeout_secs`
+///
+/// # Returns
+/// * `Ok(u8)` - Air quality index from 1 (good) to 5 (very poor)
+/// * `Err` - Network or parsing error, or a response without a valid index
+fn get_air_quality(api_key: &str, lat: f64, lon: f64, timeout: Duration) -> anyhow::Result<u8> {
+    let response: air_quality::AirPollutionResponse =
+        fetch_owm(&air_quality::air_pollution_url(api_key, lat, lon), timeout)?;
+    response
+        .aqi()
+        .ok_or_else(|| anyhow::anyhow!("Air pollution response without a valid AQI"))
+}
 
-    // Read response body
-    let mut body_buf = vec![0u8; 4096];
-    let bytes_read = response.read(&mut body_buf)?;
-    let body_str = std::str::from_utf8(&body_buf[..bytes_read])?;
+/// Fetch the weather alerts at the given coordinates from the One Call 3.0 API
+/// The API needs a separate "One Call by Call" subscription, without it the response is a 401.
+///
+/// # Arguments
+/// * `timeout` - Network timeout, `openweather.http_timeout_secs`
+///
+/// # Returns
+/// * `Ok(Vec<Alert>)` - The active alerts, empty if there are none
+/// * `Err` - Network or parsing error, or `OwmError` (e.g. 401 without subscription)
+fn get_alerts(
+    api_key: &str,
+    lat: f64,
+    lon: f64,
+    timeout: Duration,
+) -> anyhow::Result<Vec<alerts::Alert>> {
+    let response: alerts::OneCallResponse =
+        fetch_owm(&alerts::onecall_alerts_url(api_key, lat, lon), timeout)?;
+    Ok(response.alerts)
+}
 
-    // Parse JSON response
-    let weather: WeatherResponse = serde_json::from_str(body_str)?;
-    Ok(weather)
+/// Current weather of the configured city from OpenWeatherMap
+struct OpenWeatherProvider<'a> {
+    config: &'a OpenWeatherConfig,
 }
 
-/// Map OpenWeatherMap icon codes to emoji symbols
-/// Used as fallback when bitmap icons are not available
-fn get_weather_symbol(icon_code: &str) -> &'static str {
-    match icon_code {
-        "01d" => "☀",         // Clear sky day
-        "01n" => "🌙",        // Clear sky night
-        "02d" => "🌤",         // Few clouds day
-        "02n" => "☁",         // Few clouds night
-        "03d" | "03n" => "☁", // Scattered clouds
-        "04d" | "04n" => "☁", // Broken clouds
-        "09d" | "09n" => "🌧", // Shower rain
-        "10d" => "🌦",         // Rain day
-        "10n" => "🌧",         // Rain night
-        "11d" | "11n" => "⛈", // Thunderstorm
-        "13d" | "13n" => "❄", // Snow
-        "50d" | "50n" => "🌫", // Mist
-        _ => "❓",            // Unknown
+impl WeatherProvider for OpenWeatherProvider<'_> {
+    fn fetch(&self) -> anyhow::Result<WeatherResponse> {
+        get_weather(self.config)
     }
 }
 
-/// Determine icon color based on weather condition
-fn get_weather_icon_color(icon_code: &str) -> Rgb565 {
-    match &icon_code[..2] {
-        "01" | "02" | "11" => Rgb565::YELLOW,   // Sun/Thunder
-        "09" | "10" => Rgb565::BLUE,            // Rain
-        "13" => Rgb565::WHITE,                  // Snow
-        "03" | "04" | "50" => Rgb565::CSS_GRAY, // Clouds/Mist
-        _ => Rgb565::WHITE,
+/// Current weather from the JSON document of another provider, e.g. a self-hosted API
+/// The request uses the timeout of the OpenWeatherMap requests.
+struct JsonProvider<'a> {
+    url: &'a str,
+    /// Location name if the mapping has none
+    name: &'a str,
+    fields: &'a FieldMapping,
+    timeout: Duration,
+}
+
+impl WeatherProvider for JsonProvider<'_> {
+    fn fetch(&self) -> anyhow::Result<WeatherResponse> {
+        feed_watchdog();
+        let body = http_utils::http_get(self.url, self.timeout)?;
+        self.fields
+            .parse(&http_utils::decode_body(&body), self.name)
     }
 }
 
-// ===============================================================================
-// WI-FI SETUP
-// ===============================================================================
+/// Select the source of the home city's current weather from `[weather_provider]`
+fn weather_provider<'a>(
+    provider: &'a WeatherProviderConfig,
+    openweather: &'a OpenWeatherConfig,
+) -> Box<dyn WeatherProvider + 'a> {
+    match provider.json_fields() {
+        Some(fields) => Box::new(JsonProvider {
+            url: &provider.url,
+            name: &provider.name,
+            fields,
+            timeout: openweather.http_timeout(),
+        }),
+        None => Box::new(OpenWeatherProvider {
+            config: openweather,
+        }),
+    }
+}
 
-/// Initialize and connect to Wi-Fi
+/// Perform a GET request against the OpenWeatherMap API and parse the JSON response
+///
+/// # Returns
+/// * `Ok(T)` - The parsed body of a 200 response
+/// * `Err` - Network or parsing error, or `OwmError` for non-200 responses
+fn fetch_owm<T: serde::de::DeserializeOwned>(url: &str, timeout: Duration) -> anyhow::Result<T> {
+    feed_watchdog();
+    // OWM reports errors as a JSON body, e.g. `{"cod":401,"message":"Invalid API key"}`
+    http_utils::http_get_json(url, timeout).map_err(OwmError::from_http_error)
+}
+
+/// Reset the task watchdog before a blocking step of the main loop, e.g. a request
+/// The timeout then only has to cover the longest single step, not a whole iteration with
+/// retries, forecast, air quality, alerts and every city. Only the main loop is watched,
+/// in other tasks this does nothing.
+fn feed_watchdog() {
+    // SAFETY: only resets the watchdog timer of the calling task
+    unsafe {
+        esp_idf_sys::esp_task_wdt_reset();
+    }
+}
+
+/// Maximum number of weather fetch attempts per update
+const WEATHER_MAX_ATTEMPTS: u32 = 3;
+/// Delay between two weather fetch attempts in milliseconds
+const WEATHER_RETRY_DELAY_MS: u32 = 2000;
+
+/// Fetch current weather data, retrying transient failures
 ///
 /// # Arguments
-/// * `peripherals` - ESP32 peripherals
-/// * `secrets` - Configuration containing Wi-Fi credentials
+/// * `provider` - Source of the weather data
+/// * `max_attempts` - Upper bound for the number of requests
 ///
 /// # Returns
-/// * `Ok(BlockingWifi)` - Connected Wi-Fi instance
-fn setup_wifi(
-    modem: impl esp_idf_hal::peripheral::Peripheral<P = esp_idf_hal::modem::Modem> + 'static,
-    secrets: &Secrets,
-) -> anyhow::Result<BlockingWifi<EspWifi<'static>>> {
-    info!("Initializing Wi-Fi...");
-
-    let sys_loop = EspSystemEventLoop::take()?;
-    let nvs = EspDefaultNvsPartition::take()?;
+/// * `Ok(WeatherResponse)` - Parsed weather data
+/// * `Err` - The last error once all attempts failed, or the first non-transient error
+fn get_weather_retrying(
+    provider: &dyn WeatherProvider,
+    max_attempts: u32,
+) -> anyhow::Result<WeatherResponse> {
+    provider::fetch_retrying(provider, max_attempts, || {
+        FreeRtos::delay_ms(WEATHER_RETRY_DELAY_MS)
+    })
+}
 
-    let mut wifi = BlockingWifi::wrap(EspWifi::new(modem, sys_loop.clone(), Some(nvs))?, sys_loop)?;
+// ===============================================================================
+// WI-FI SETUP
+// ===============================================================================
 
-    // Configure Wi-Fi credentials
-    let wifi_config = Configuration::Client(ClientConfiguration {
-        ssid: secrets
-            .wifi
+/// Build the client configuration for a single Wi-Fi network
+fn client_configuration(network: &WiFiNetwork) -> anyhow::Result<Configuration> {
+    Ok(Configuration::Client(ClientConfiguration {
+        ssid: network
             .ssid
             .as_str()
             .try_into()
             .map_err(|_| anyhow::anyhow!("Failed to parse SSID"))?,
-        password: secrets
-            .wifi
+        password: network
             .password
             .as_str()
             .try_into()
             .map_err(|_| anyhow::anyhow!("Failed to parse password"))?,
-        auth_method: if secrets.wifi.password.is_empty() {
+        auth_method: if network.password.is_empty() {
             AuthMethod::None
         } else {
             AuthMethod::WPA2Personal
         },
         ..Default::default()
-    });
+    }))
+}
 
-    wifi.set_configuration(&wifi_config)?;
+/// Start connecting to `network` without waiting for the connection
+/// The driver connects in the background, `wait_for_wifi` or `is_up` tell when it is done.
+fn start_wifi_connect(
+    wifi: &mut BlockingWifi<EspWifi<'static>>,
+    network: &WiFiNetwork,
+) -> anyhow::Result<()> {
+    // Ends a pending attempt, fails harmlessly if there is none
+    let _ = wifi.wifi_mut().disconnect();
+    wifi.wifi_mut()
+        .set_configuration(&client_configuration(network)?)?;
+    wifi.wifi_mut().connect()?;
+    Ok(())
+}
+
+/// Wait until Wi-Fi is connected and has an IP address, giving up after `timeout`
+/// A missing access point would otherwise block the boot forever.
+///
+/// # Returns
+/// * `Ok(())` - Wi-Fi is up
+/// * `Err` - The timeout elapsed first
+fn wait_for_wifi(wifi: &BlockingWifi<EspWifi<'static>>, timeout: Duration) -> anyhow::Result<()> {
+    let start = Instant::now();
+    while !wifi.is_up()? {
+        if start.elapsed() >= timeout {
+            anyhow::bail!("Wi-Fi connection timed out after {}s", timeout.as_secs());
+        }
+        FreeRtos::delay_ms(100);
+    }
+    Ok(())
+}
+
+/// Initialize and connect to Wi-Fi
+///
+/// Scans for access points and tries the configured networks in priority order.
+/// Networks seen in the scan are tried first, the remaining ones (e.g. hidden SSIDs) afterwards.
+/// If none can be joined within the timeout, the device starts offline and the main loop
+/// keeps trying in the background.
+///
+/// # Arguments
+/// * `peripherals` - ESP32 peripherals
+/// * `nvs` - Default NVS partition, used by the Wi-Fi driver for calibration data
+/// * `secrets` - Configuration containing Wi-Fi credentials
+///
+/// # Returns
+/// * `Ok(BlockingWifi)` - Started Wi-Fi instance, connected unless the timeout elapsed
+/// * `Err` - The Wi-Fi driver could not be started
+fn setup_wifi(
+    modem: impl esp_idf_hal::peripheral::Peripheral<P = esp_idf_hal::modem::Modem> + 'static,
+    nvs: EspDefaultNvsPartition,
+    secrets: &Secrets,
+) -> anyhow::Result<BlockingWifi<EspWifi<'static>>> {
+    info!("Initializing Wi-Fi...");
+
+    let sys_loop = EspSystemEventLoop::take()?;
+
+    let mut wifi = BlockingWifi::wrap(EspWifi::new(modem, sys_loop.clone(), Some(nvs))?, sys_loop)?;
+
+    // Start in client mode so that we can scan before choosing a network
+    wifi.set_configuration(&Configuration::Client(ClientConfiguration::default()))?;
     wifi.start()?;
-    wifi.connect()?;
-    wifi.wait_netif_up()?;
 
-    info!("Wi-Fi connected successfully!");
+    let visible_ssids: Vec<String> = match wifi.scan() {
+        Ok(access_points) => access_points
+            .iter()
+            .map(|ap| ap.ssid.as_str().to_string())
+            .collect(),
+        Err(e) => {
+            warn!("Wi-Fi scan failed: {:?}", e);
+            Vec::new()
+        }
+    };
+    info!("Found {} access points", visible_ssids.len());
+
+    // Visible networks first, keeping the configured priority order in both groups
+    let (visible, hidden): (Vec<&WiFiNetwork>, Vec<&WiFiNetwork>) = secrets
+        .wifi
+        .networks
+        .iter()
+        .partition(|network| visible_ssids.contains(&network.ssid));
+
+    let timeout = Duration::from_secs(secrets.wifi.connect_timeout_secs);
+    let started = Instant::now();
+
+    for network in visible.into_iter().chain(hidden) {
+        let remaining = timeout.saturating_sub(started.elapsed());
+        if remaining.is_zero() {
+            break;
+        }
+
+        info!("Connecting to Wi-Fi '{}'...", network.ssid);
+        match start_wifi_connect(&mut wifi, network)
+            .and_then(|_| wait_for_wifi(&wifi, remaining.min(wifi_retry::ATTEMPT_TIME)))
+        {
+            Ok(()) => {
+                info!("Wi-Fi connected successfully to '{}'!", network.ssid);
+                return Ok(wifi);
+            }
+            Err(e) => {
+                warn!("Failed to connect to '{}': {:?}", network.ssid, e);
+                let _ = wifi.wifi_mut().disconnect();
+            }
+        }
+    }
+
+    warn!(
+        "Could not connect to any configured Wi-Fi network within {}s, starting offline",
+        timeout.as_secs()
+    );
     Ok(wifi)
 }
 
+/// Maximum number of Wi-Fi reconnect attempts before giving up
+const WIFI_MAX_RETRIES: u32 = 8;
+
+/// Reconnect to Wi-Fi, retrying with exponential backoff (1s, 2s, 4s, ... capped at 60s)
+///
+/// # Returns
+/// * `Ok(())` - Wi-Fi is connected again
+/// * `Err` - The retry budget is exhausted; the caller decides whether to reboot
+fn reconnect_wifi_with_backoff(
+    wifi: &mut BlockingWifi<EspWifi<'static>>,
+    max_retries: u32,
+) -> anyhow::Result<()> {
+    backoff::retry_with_backoff(
+        max_retries,
+        || {
+            feed_watchdog();
+            wifi.connect()?;
+            wifi.wait_netif_up()
+        },
+        |delay| {
+            feed_watchdog();
+            FreeRtos::delay_ms(delay.as_millis() as u32)
+        },
+    )
+}
+
+/// Read the RSSI (in dBm) of the currently connected access point
+///
+/// # Returns
+/// * `Some(rssi)` - Signal strength of the current AP
+/// * `None` - Not connected or the driver could not provide AP info
+fn get_rssi(wifi: &BlockingWifi<EspWifi<'static>>) -> Option<i8> {
+    if !wifi.is_connected().unwrap_or(false) {
+        return None;
+    }
+
+    let mut ap_info = esp_idf_sys::wifi_ap_record_t::default();
+    esp_idf_sys::esp!(unsafe { esp_idf_sys::esp_wifi_sta_get_ap_info(&mut ap_info) }).ok()?;
+    Some(ap_info.rssi)
+}
+
+/// Read the IPv4 address of the station interface
+///
+/// # Returns
+/// * `Some(ip)` - The address, e.g. "192.168.1.23"
+/// * `None` - No address assigned yet
+fn get_ip(wifi: &BlockingWifi<EspWifi<'static>>) -> Option<String> {
+    let ip_info = wifi.wifi().sta_netif().get_ip_info().ok()?;
+    (!ip_info.ip.is_unspecified()).then(|| ip_info.ip.to_string())
+}
+
+/// Read the IP address after a (re)connect and log it if it differs from `ip`
+fn update_ip(wifi: &BlockingWifi<EspWifi<'static>>, ip: &mut String) {
+    let current = get_ip(wifi).unwrap_or_default();
+    if current != *ip {
+        info!("IP address: {}", current);
+        *ip = current;
+    }
+}
+
+// ===============================================================================
+// TIME SYNCHRONIZATION
+// ===============================================================================
+
+/// Maximum time to wait for the initial SNTP synchronization at boot
+const SNTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Shown instead of the date while the clock is not synchronized
+/// Not longer than a date line, so the date fully overwrites it once synchronized.
+const CLOCK_UNSYNCED_BANNER: &str = "No time sync";
+
+/// Wait until SNTP has synchronized the system clock
+/// Gives up after `timeout` so that a blocked NTP server does not hang the boot.
+/// SNTP keeps running in the background, so the clock may still be synchronized later.
+///
+/// # Returns
+/// * `Ok(())` - The clock is synchronized
+/// * `Err` - The timeout elapsed first
+fn wait_for_sntp(sntp: &EspSntp, timeout: Duration) -> anyhow::Result<()> {
+    let start = Instant::now();
+    while sntp.get_sync_status() != SyncStatus::Completed {
+        if start.elapsed() >= timeout {
+            anyhow::bail!("SNTP sync timed out after {}s", timeout.as_secs());
+        }
+        FreeRtos::delay_ms(100);
+    }
+    info!("Time synchronized after {} ms", start.elapsed().as_millis());
+    Ok(())
+}
+
+/// Start SNTP with smooth corrections, resynchronizing every `resync_interval`
+/// The first sync sets the clock, later ones slew it gradually (adjtime) so that the
+/// displayed seconds never jump. Every resync logs how far the clock was off and its drift.
+fn start_sntp(resync_interval: Duration) -> anyhow::Result<EspSntp<'static>> {
+    // SAFETY: only stores the interval, the SNTP client reads it when scheduling the next sync
+    unsafe {
+        esp_idf_sys::sntp_set_sync_interval(resync_interval.as_millis() as u32);
+    }
+
+    let conf = SntpConf {
+        sync_mode: SyncMode::Smooth,
+        ..Default::default()
+    };
+    // NTP time of the previous sync
+    let mut last_sync: Option<Duration> = None;
+    let sntp = EspSntp::new_with_callback(&conf, move |synced: Duration| {
+        // In smooth mode the clock has not been corrected yet when the callback runs
+        let system = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let offset_ms = system.as_millis() as i64 - synced.as_millis() as i64;
+        match last_sync {
+            Some(previous) => {
+                let elapsed_secs = synced.saturating_sub(previous).as_secs();
+                info!(
+                    "SNTP resync: clock was {} ms {} after {} min ({:.1} ppm), correcting",
+                    offset_ms.abs(),
+                    if offset_ms >= 0 { "ahead" } else { "behind" },
+                    elapsed_secs / 60,
+                    time_utils::clock_drift_ppm(offset_ms, elapsed_secs).unwrap_or_default()
+                );
+            }
+            None => info!("SNTP sync: clock set"),
+        }
+        last_sync = Some(synced);
+    })?;
+    Ok(sntp)
+}
+
 // ===============================================================================
 // MQTT SETUP
 // ===============================================================================
 
+/// CA certificate of the broker, copied by build.rs from the file named in `mqtt.ca_cert_path`
+/// NUL-terminated PEM, empty when not configured.
+static MQTT_CA_CERT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/mqtt_ca_cert.pem"));
+
+/// Client certificate and private key for mutual TLS, copied by build.rs from the files named
+/// in `mqtt.client_cert` and `mqtt.client_key`. NUL-terminated PEM, empty when not configured.
+static MQTT_CLIENT_CERT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/mqtt_client_cert.pem"));
+static MQTT_CLIENT_KEY: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/mqtt_client_key.pem"));
+
+/// An embedded PEM file for the MQTT client, `None` if it was not configured
+fn embedded_pem(pem: &'static [u8]) -> Option<X509<'static>> {
+    (!pem.is_empty()).then(|| X509::pem_until_nul(pem))
+}
+
+/// Time `MqttHandle::shutdown` waits for the event thread to end
+const MQTT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Messages kept while publishing fails, enough for a few weather updates with single values
+const MQTT_OUTBOX_CAPACITY: usize = 32;
+
+/// The MQTT client together with its event thread
+/// Dropping the handle closes the connection, `shutdown` also waits for the thread to end.
+struct MqttHandle {
+    client: EspMqttClient<'static>,
+    /// Asks the event thread to leave its loop before handling the next event
+    stop: Arc<AtomicBool>,
+    thread: std::thread::JoinHandle<anyhow::Result<()>>,
+}
+
+impl MqttHandle {
+    /// The client for publishing and subscribing
+    fn client(&mut self) -> &mut EspMqttClient<'static> {
+        &mut self.client
+    }
+
+    /// Stop the event thread and disconnect from the broker
+    /// Dropping the client closes the connection, which wakes the thread if it is waiting
+    /// for an event; the stop flag ends the loop if it is handling one.
+    ///
+    /// # Returns
+    /// * `Ok(())` - The thread has ended
+    /// * `Err` - The thread returned an error, panicked or did not end within `timeout`
+    fn shutdown(self, timeout: Duration) -> anyhow::Result<()> {
+        info!("Stopping the MQTT client...");
+        self.stop.store(true, Ordering::SeqCst);
+        drop(self.client);
+
+        let started = Instant::now();
+        while !self.thread.is_finished() {
+            if started.elapsed() >= timeout {
+                anyhow::bail!(
+                    "MQTT event thread did not stop within {}s",
+                    timeout.as_secs()
+                );
+            }
+            FreeRtos::delay_ms(10);
+        }
+        self.thread
+            .join()
+            .map_err(|_| anyhow::anyhow!("MQTT event thread panicked"))?
+    }
+}
+
 /// Initialize MQTT client and start listening thread
 ///
 /// # Arguments
@@ -271,55 +731,102 @@ fn setup_wifi(
 /// * `movement_events` - Shared queue for movement timestamps
 ///
 /// # Returns
-/// * `Ok(EspMqttClient)` - MQTT client for publishing
+/// * `Ok(MqttHandle)` - MQTT client for publishing, with the thread to stop it
 fn setup_mqtt(
     secrets: &Secrets,
-    movement_events: Arc<Mutex<VecDeque<String>>>,
-) -> anyhow::Result<EspMqttClient<'static>> {
+    movement_events: Arc<Mutex<VecDeque<i64>>>,
+) -> anyhow::Result<MqttHandle> {
     info!("Initializing MQTT client...");
 
-    // Use the global CA bundle which includes Let's Encrypt certificates
+    // The configured CA, otherwise the global CA bundle which includes Let's Encrypt certificates
+    let server_certificate = embedded_pem(MQTT_CA_CERT);
     let mqtt_config = MqttClientConfiguration {
         username: Some(secrets.mqtt.mqtt_user.as_str()),
         password: Some(secrets.mqtt.mqtt_pw.as_str()),
         client_id: Some("esp32-weather-client-rust"),
 
         // Use the global CA bundle (includes Let's Encrypt, ISRG Root X1, and most CAs)
-        crt_bundle_attach: Some(esp_idf_sys::esp_crt_bundle_attach),
-        server_certificate: None,
+        // unless `mqtt.ca_cert_path` names the CA of a private broker
+        crt_bundle_attach: match server_certificate {
+            Some(_) => None,
+            None => Some(esp_idf_sys::esp_crt_bundle_attach),
+        },
+        server_certificate,
+
+        // Mutual TLS: both are set or neither (checked by `Secrets::validate`)
+        client_certificate: embedded_pem(MQTT_CLIENT_CERT),
+        private_key: embedded_pem(MQTT_CLIENT_KEY),
+
+        // The broker publishes "offline" on our behalf if the connection drops
+        lwt: Some(LwtConfiguration {
+            topic: secrets.mqtt.status_topic.as_str(),
+            payload: b"offline",
+            qos: embedded_svc::mqtt::client::QoS::AtLeastOnce,
+            retain: true,
+        }),
+
+        // Short keepalives let the client notice connections the network dropped silently
+        keep_alive_interval: Some(Duration::from_secs(secrets.mqtt.keep_alive_secs)),
+        reconnect_timeout: Some(Duration::from_secs(secrets.mqtt.reconnect_timeout_secs)),
 
         ..Default::default()
     };
+    info!(
+        "MQTT keepalive: {}s, reconnect timeout: {}s",
+        secrets.mqtt.keep_alive_secs, secrets.mqtt.reconnect_timeout_secs
+    );
+    if let Some(ca_cert) = &secrets.mqtt.ca_cert_path {
+        info!("MQTT CA certificate: {}", ca_cert);
+    }
+    if let Some(client_cert) = &secrets.mqtt.client_cert {
+        info!("MQTT client certificate: {}", client_cert);
+    }
 
-    let (mut client, mut connection) =
+    let (client, mut connection) =
         EspMqttClient::new(secrets.mqtt.broker_url.as_str(), &mqtt_config)?;
 
+    // Owned copies for the event thread
+    let movement_topic = secrets.mqtt.movement_topic.clone();
+    let trigger_payload = secrets.mqtt.movement_trigger_payload.clone();
+    let clear_topic = secrets.mqtt.movement_clear_topic.clone();
+    let refresh_topic = secrets.mqtt.refresh_topic.clone();
+    let mode_topic = secrets.mqtt.mode_topic.clone();
+    let history_len = secrets.mqtt.movement_history_len;
+    let min_event_gap_secs = secrets.mqtt.min_event_gap_secs;
+    let alert_ms = secrets.mqtt.movement_alert_ms;
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+
     // Spawn MQTT event handling thread
-    std::thread::Builder::new()
+    // It ends when the client is dropped (`next` fails) or when `stop` is set
+    let thread = std::thread::Builder::new()
         .stack_size(6000)
         .spawn(move || {
             info!("MQTT event loop started");
-            let mut subscribed = false;
 
             while let Ok(event) = connection.next() {
                 use esp_idf_svc::mqtt::client::EventPayload;
 
+                if thread_stop.load(Ordering::SeqCst) {
+                    break;
+                }
+
                 match event.payload() {
                     EventPayload::Connected(_) => {
                         info!("MQTT Connected to broker");
-                        subscribed = false;
+                        MQTT_SESSION.on_connected();
                     }
                     EventPayload::BeforeConnect => {
                         info!("MQTT connecting to broker...");
                     }
                     EventPayload::Subscribed(msg_id) => {
                         info!("MQTT subscription confirmed (ID: {})", msg_id);
-                        subscribed = true;
+                        MQTT_SESSION.on_subscribed();
                     }
                     EventPayload::Received {
                         id, topic, data, ..
                     } => {
-                        if !subscribed {
+                        if !MQTT_SESSION.is_subscribed() {
                             continue;
                         }
 
@@ -335,19 +842,48 @@ fn setup_mqtt(
 
                                 // Handle movement detection message
                                 if let Some(t) = topic {
-                                    // The topic "Bewegung" is German for "movement".
-                                    if t == "Bewegung" && received_data == "1" {
-                                        if let Err(e) = handle_movement_event(&movement_events) {
+                                    if t == movement_topic && received_data == trigger_payload {
+                                        if let Err(e) = handle_movement_event(
+                                            &movement_events,
+                                            history_len,
+                                            min_event_gap_secs,
+                                            alert_ms,
+                                        ) {
                                             error!("Failed to handle movement event: {}", e);
                                         }
                                     }
                                 }
                             }
                         }
+
+                        // Any message on the clear topic empties the history, even without payload
+                        if topic == Some(clear_topic.as_str()) {
+                            clear_movement_events(&movement_events);
+                        }
+
+                        // Any message on the refresh topic asks the main loop for a fetch
+                        if topic == Some(refresh_topic.as_str()) {
+                            info!("Weather refresh requested via MQTT");
+                            REFRESH_REQUESTED.store(true, Ordering::SeqCst);
+                        }
+
+                        // The mode topic switches between the dashboard and the plain clock
+                        if topic == Some(mode_topic.as_str()) {
+                            match std::str::from_utf8(data).ok().and_then(DisplayMode::parse) {
+                                Some(mode) => {
+                                    info!("Display mode set to {} via MQTT", mode.as_str());
+                                    DISPLAY_MODE.set(mode);
+                                }
+                                None => warn!(
+                                    "Ignoring unknown display mode {:?}",
+                                    String::from_utf8_lossy(data)
+                                ),
+                            }
+                        }
                     }
                     EventPayload::Disconnected => {
                         info!("MQTT disconnected from broker");
-                        subscribed = false;
+                        MQTT_SESSION.on_disconnected();
                     }
                     EventPayload::Error(e) => {
                         error!("MQTT error: {:?}", e);
@@ -359,231 +895,508 @@ fn setup_mqtt(
             Ok::<(), anyhow::Error>(())
         })?;
 
-    // Wait for MQTT connection to establish
-    info!("Waiting for MQTT connection...");
-    FreeRtos::delay_ms(2000);
+    // The topics are subscribed by the main loop on every `Connected` event
+    Ok(MqttHandle {
+        client,
+        stop,
+        thread,
+    })
+}
+
+/// The single weather values published with `publish_split`: topic suffix and plain number
+/// Values missing from the response are left out. The wind speed comes with its unit
+/// on `wind_unit`, since it depends on the unit system (m/s or mph).
+fn weather_values(weather: &WeatherResponse, units: &str) -> Vec<(&'static str, String)> {
+    let mut values = vec![("temp", format!("{:.1}", weather.main.temp))];
+    if let Some(humidity) = weather.main.humidity {
+        values.push(("humidity", humidity.to_string()));
+    }
+    if let Some(wind) = &weather.wind {
+        values.push(("wind", format!("{:.1}", wind.speed)));
+        values.push(("wind_unit", weather::speed_unit(units).to_string()));
+    }
+    values
+}
+
+/// Maps a QoS level from `secrets.toml` (validated to be 0, 1 or 2) to the client type
+fn qos_from_level(level: u8) -> embedded_svc::mqtt::client::QoS {
+    use embedded_svc::mqtt::client::QoS;
+    match level {
+        0 => QoS::AtMostOnce,
+        1 => QoS::AtLeastOnce,
+        _ => QoS::ExactlyOnce,
+    }
+}
 
-    // Subscribe to movement detection topic.
-    // Note: The topic "Bewegung" is German for "movement".
-    let movement_topic = "Bewegung";
-    match client.subscribe(movement_topic, embedded_svc::mqtt::client::QoS::AtLeastOnce) {
-        Ok(_) => info!("Subscribed to topic: {}", movement_topic),
-        Err(e) => error!("Failed to subscribe: {:?}", e),
+/// Subscribe to the movement detection, movement clear, weather refresh and display mode topics
+/// Called for every new connection. Failures are only logged, the next reconnect tries again.
+fn subscribe_topics(client: &mut EspMqttClient<'static>, secrets: &Secrets) {
+    for topic in [
+        secrets.mqtt.movement_topic.as_str(),
+        secrets.mqtt.movement_clear_topic.as_str(),
+        secrets.mqtt.refresh_topic.as_str(),
+        secrets.mqtt.mode_topic.as_str(),
+    ] {
+        match client.subscribe(topic, embedded_svc::mqtt::client::QoS::AtLeastOnce) {
+            Ok(_) => info!("Subscribed to topic: {}", topic),
+            Err(e) => error!("Failed to subscribe to {}: {:?}", topic, e),
+        }
     }
+}
 
-    Ok(client)
+/// Handle a movement clear command by emptying the event queue
+/// The display shows the empty list on the next render.
+fn clear_movement_events(movement_events: &Arc<Mutex<VecDeque<i64>>>) {
+    lock_recovering(movement_events).clear();
+    info!("Movement history cleared");
 }
 
 /// Handle a movement detection event
-/// Converts current time to Berlin timezone and adds to event queue
-fn handle_movement_event(movement_events: &Arc<Mutex<VecDeque<String>>>) -> anyhow::Result<()> {
+/// Adds the current UTC timestamp to the event queue, it is formatted when rendered
+/// so that the date can be shown once the event is no longer from today.
+/// An event within `min_event_gap_secs` of the newest one is ignored, the others pulse
+/// the movement alert pin for `alert_ms`.
+fn handle_movement_event(
+    movement_events: &Arc<Mutex<VecDeque<i64>>>,
+    history_len: usize,
+    min_event_gap_secs: u64,
+    alert_ms: u32,
+) -> anyhow::Result<()> {
     let now = SystemTime::now();
     let since_the_epoch = now.duration_since(UNIX_EPOCH)?;
-    let utc_timestamp = since_the_epoch.as_secs();
-    let (_year, _month, _day, hour, minute, second) =
-        time_utils::utc_to_berlin(utc_timestamp as i64);
-    let formatted_time = time_utils::format_time(hour, minute, second);
-
-    // Add to queue (max 6 events, FIFO)
-    let mut events = movement_events
-        .lock()
-        .map_err(|e| anyhow::anyhow!("Mutex lock failed: {}", e))?;
-    events.push_front(formatted_time.clone());
-    if events.len() > 6 {
-        events.pop_back();
-    }
-    info!("Movement detected at: {}", formatted_time);
+    let utc_timestamp = since_the_epoch.as_secs() as i64;
+
+    // Add to queue (max `history_len` events, FIFO)
+    let mut events = lock_recovering(movement_events);
+    // The queue is newest first
+    if movement::is_bounce(events.front().copied(), utc_timestamp, min_event_gap_secs) {
+        info!("Movement ignored, too close to the previous event");
+        return Ok(());
+    }
+    movement::push_event(&mut events, utc_timestamp, history_len);
+    drop(events);
+    info!(
+        "Movement detected at: {}",
+        time_utils::format_event_time(utc_timestamp, utc_timestamp)
+    );
+    pulse_movement_alert(alert_ms);
     Ok(())
 }
 
-// ===============================================================================
-// DISPLAY SETUP
-// ===============================================================================
-
-/// Custom error type for SPI and GPIO operations.
-/// This is a workaround to adapt the `esp_idf_hal` error types to the `embedded-hal` traits.
-#[derive(Debug)]
-struct CustomError;
+/// Drive the movement alert pin high for `duration_ms`, if one is configured
+/// Runs on the MQTT event thread, `mqtt.movement_alert_ms` is limited to a second
+/// so that the following messages are not held up for long.
+fn pulse_movement_alert(duration_ms: u32) {
+    let mut alert = lock_recovering(&MOVEMENT_ALERT);
+    let Some(pin) = alert.as_mut() else {
+        return;
+    };
+    if let Err(e) = pin.set_high() {
+        error!("Failed to set the movement alert pin: {}", e);
+        return;
+    }
+    FreeRtos::delay_ms(duration_ms);
+    if let Err(e) = pin.set_low() {
+        error!("Failed to reset the movement alert pin: {}", e);
+    }
+}
 
-impl embedded_hal::spi::Error for CustomError {
-    fn kind(&self) -> embedded_hal::spi::ErrorKind {
-        embedded_hal::spi::ErrorKind::Other
+/// Queue the weather data of `city` for `weather/<city>`, as JSON and/or as single values,
+/// and publish it right away unless older messages are still waiting
+fn publish_weather(
+    client: &mut EspMqttClient<'static>,
+    outbox: &mut Outbox,
+    secrets: &Secrets,
+    city: &str,
+    payload: Option<&str>,
+    values: &[(&'static str, String)],
+) {
+    let topic = format!("weather/{}", city);
+    let mut queue = |topic: String, payload: &[u8]| {
+        let message = OutboxMessage {
+            topic,
+            payload: payload.to_vec(),
+            qos: secrets.mqtt.publish_qos,
+            retain: secrets.mqtt.publish_retain,
+        };
+        if let Some(dropped) = outbox.push(message) {
+            warn!(
+                "MQTT outbox full, dropped the message for {}",
+                dropped.topic
+            );
+        }
+    };
+    if secrets.mqtt.publish_json {
+        if let Some(payload) = payload {
+            queue(topic.clone(), payload.as_bytes());
+        }
+    }
+    if secrets.mqtt.publish_split {
+        for (name, value) in values {
+            queue(format!("{}/{}", topic, name), value.as_bytes());
+        }
+    }
+    flush_outbox(client, outbox);
+    if outbox.is_empty() {
+        info!("Weather data published to MQTT: {}", topic);
     }
 }
 
-impl embedded_hal::digital::Error for CustomError {
-    fn kind(&self) -> embedded_hal::digital::ErrorKind {
-        embedded_hal::digital::ErrorKind::Other
+/// Publish the queued messages in order, stopping at the first failure
+/// The rest stays queued for the next weather publish or the next connection.
+fn flush_outbox(client: &mut EspMqttClient<'static>, outbox: &mut Outbox) {
+    let result = outbox.flush(|message| {
+        client
+            .publish(
+                message.topic.as_str(),
+                qos_from_level(message.qos),
+                message.retain,
+                &message.payload,
+            )
+            .map(|_| debug!("Published to MQTT: {}", message.topic))
+    });
+    if let Err(e) = result {
+        warn!(
+            "MQTT publish error, {} messages kept for retry: {:?}",
+            outbox.len(),
+            e
+        );
     }
 }
 
-/// Wrapper for ESP-IDF SPI driver to make it compatible with `embedded-hal` traits.
-struct SpiWrapper<'a> {
-    spi: SpiDeviceDriver<'a, SpiDriver<'a>>,
+/// Publish the Home Assistant discovery configs (retained) for the weather sensors
+fn publish_discovery(client: &mut EspMqttClient<'static>, secrets: &Secrets) -> anyhow::Result<()> {
+    let state_topic = format!("weather/{}", secrets.openweather.home_city());
+    let messages = ha_discovery::discovery_messages(
+        &state_topic,
+        &secrets.mqtt.status_topic,
+        secrets.openweather.temperature_unit(),
+        secrets.openweather.wind_speed_unit(),
+    )?;
+
+    for (topic, payload) in messages {
+        client.publish(
+            &topic,
+            embedded_svc::mqtt::client::QoS::AtLeastOnce,
+            true,
+            payload.as_bytes(),
+        )?;
+    }
+    info!("Home Assistant discovery published");
+    Ok(())
 }
 
-impl embedded_hal::spi::ErrorType for SpiWrapper<'_> {
-    type Error = CustomError;
+/// Topic of the periodic memory report
+const MEM_STATS_TOPIC: &str = "esp32-weather-client-rust/mem";
+
+/// Topic of the periodic fetch and reconnect counters
+const FETCH_STATS_TOPIC: &str = "esp32-weather-client-rust/stats";
+
+/// Interval between two memory reports
+const MEM_STATS_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Free heap in bytes, published to spot slow leaks in the long-running main loop
+#[derive(Serialize, Debug)]
+struct MemStats {
+    free_internal: usize,
+    free_spiram: usize,
 }
 
-impl SpiDevice for SpiWrapper<'_> {
-    fn transaction(
-        &mut self,
-        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
-    ) -> Result<(), Self::Error> {
-        for op in operations {
-            match op {
-                embedded_hal::spi::Operation::Write(data) => {
-                    if !data.is_empty() {
-                        self.spi.write(data).map_err(|_| CustomError)?;
-                    }
-                }
-                embedded_hal::spi::Operation::Transfer(read, write) => {
-                    if !write.is_empty() {
-                        self.spi.transfer(read, write).map_err(|_| CustomError)?;
-                    }
-                }
-                embedded_hal::spi::Operation::TransferInPlace(data) => {
-                    if !data.is_empty() {
-                        let temp = data.to_vec();
-                        self.spi.transfer(data, &temp).map_err(|_| CustomError)?;
-                    }
-                }
-                _ => {}
-            }
+/// Publish the currently free internal heap and SPIRAM as JSON
+fn publish_mem_stats(client: &mut EspMqttClient<'static>, topic: &str) -> anyhow::Result<()> {
+    // SAFETY: heap_caps_get_free_size only reads allocator statistics
+    let stats = unsafe {
+        MemStats {
+            free_internal: esp_idf_sys::heap_caps_get_free_size(esp_idf_sys::MALLOC_CAP_INTERNAL),
+            free_spiram: esp_idf_sys::heap_caps_get_free_size(esp_idf_sys::MALLOC_CAP_SPIRAM),
         }
-        Ok(())
-    }
+    };
+
+    let payload = serde_json::to_string(&stats)?;
+    client.publish(
+        topic,
+        embedded_svc::mqtt::client::QoS::AtMostOnce,
+        false,
+        payload.as_bytes(),
+    )?;
+    info!("Memory stats published: {}", payload);
+    Ok(())
 }
 
-/// Wrapper for the Data/Command (DC) pin to make it compatible with `embedded-hal` traits.
-struct DcPinWrapper<'a> {
-    pin: PinDriver<'a, esp_idf_hal::gpio::AnyOutputPin, esp_idf_hal::gpio::Output>,
+/// Publish the fetch and Wi-Fi reconnect counters since boot as JSON
+fn publish_fetch_stats(client: &mut EspMqttClient<'static>, topic: &str) -> anyhow::Result<()> {
+    let payload = serde_json::to_string(&STATS.snapshot())?;
+    client.publish(
+        topic,
+        embedded_svc::mqtt::client::QoS::AtMostOnce,
+        false,
+        payload.as_bytes(),
+    )?;
+    info!("Fetch stats published: {}", payload);
+    Ok(())
 }
 
-impl embedded_hal::digital::ErrorType for DcPinWrapper<'_> {
-    type Error = CustomError;
+// ===============================================================================
+// RUNTIME CONFIGURATION (WEB SERVER)
+// ===============================================================================
+
+/// NVS namespace of the settings changed via the web server
+const RUNTIME_CONFIG_NAMESPACE: &str = "weather_cfg";
+
+/// Upper bound for a request body of the configuration form
+const WEB_MAX_BODY_LEN: usize = 512;
+
+/// Return a copy of the OpenWeatherMap settings currently in use
+fn runtime_openweather() -> anyhow::Result<OpenWeatherConfig> {
+    lock_recovering(&RUNTIME_OPENWEATHER)
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("RUNTIME_OPENWEATHER not initialized"))
 }
 
-impl OutputPinTrait for DcPinWrapper<'_> {
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.pin.set_low().map_err(|_| CustomError)
+/// Apply the city and units stored in NVS to the configuration from `secrets.toml`
+/// Invalid stored values are ignored, so a bad entry can never prevent booting.
+fn load_runtime_config(nvs: &EspNvs<NvsDefault>, config: &mut OpenWeatherConfig) {
+    let mut city_buf = [0u8; 128];
+    let mut units_buf = [0u8; 16];
+    let update = web_config::ConfigUpdate {
+        city: nvs
+            .get_str("city", &mut city_buf)
+            .ok()
+            .flatten()
+            .map(str::to_string),
+        units: nvs
+            .get_str("units", &mut units_buf)
+            .ok()
+            .flatten()
+            .map(str::to_string),
+    };
+
+    if update == web_config::ConfigUpdate::default() {
+        return;
     }
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.pin.set_high().map_err(|_| CustomError)
+    match web_config::apply_update(config, &update) {
+        Ok(()) => info!(
+            "Using runtime configuration: city '{}', units {}",
+            config.city,
+            config.units()
+        ),
+        Err(e) => warn!("Ignoring stored runtime configuration: {}", e),
     }
 }
 
+/// Persist the runtime-editable settings to NVS
+fn save_runtime_config(
+    nvs: &mut EspNvs<NvsDefault>,
+    config: &OpenWeatherConfig,
+) -> anyhow::Result<()> {
+    nvs.set_str("city", &config.city)?;
+    nvs.set_str("units", config.units())?;
+    Ok(())
+}
+
 // ===============================================================================
-// DISPLAY RENDERING
+// DAILY TEMPERATURE RANGE
 // ===============================================================================
 
-/// Renders the entire display content.
-/// It only redraws the screen if the `DisplayState` has changed to prevent flickering.
-fn render_display(
-    display: &mut impl DrawTarget<Color = Rgb565>,
-    current_state: &DisplayState,
-    text_style: &MonoTextStyle<Rgb565>,
-    symbol_style: &MonoTextStyle<Rgb565>,
-) {
-    // The display is not cleared every frame to reduce flickering.
-    // Instead, specific areas are overwritten.
-
-    // === Render Date and Time ===
-    let _ = Text::new(&current_state.date_str, Point::new(10, 20), *text_style).draw(display);
-    let _ = Text::new(&current_state.time_str, Point::new(10, 40), *text_style).draw(display);
-
-    // === Render Weather Data ===
-    if !current_state.city_name.is_empty() {
-        // City name
-        let _ = Text::new(&current_state.city_name, Point::new(10, 60), *text_style).draw(display);
-        // Temperature
-        let _ =
-            Text::new(&current_state.weather_temp, Point::new(10, 90), *text_style).draw(display);
-        // Description
-        let _ = Text::new(
-            &current_state.weather_desc,
-            Point::new(10, 120),
-            *text_style,
-        )
-        .draw(display);
-        // Wind speed
-        let _ = Text::new(&current_state.wind_str, Point::new(10, 150), *text_style).draw(display);
-        // Humidity
-        let _ = Text::new(&current_state.hum_str, Point::new(10, 180), *text_style).draw(display);
-        // Weather icon
-        render_weather_icon(display, &current_state.weather_icon, symbol_style);
-
-        // Manually clear the event area before drawing.
-        // This draws a black rectangle over the entire event area
-        // to ensure a clean erase before new events are drawn.
-        let event_area = embedded_graphics::primitives::Rectangle::new(
-            Point::new(0, 210), // Start point (slightly above the first text)
-            Size::new(240, 80), // Size (full width, 80 pixels high)
-        )
-        .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
-            Rgb565::BLACK,
-        ));
-        let _ = event_area.draw(display);
-
-        // === Render Movement Events ===
-        render_movement_events(display, &current_state.movement_events, text_style);
-    }
-}
-
-/// Render weather icon (bitmap or emoji fallback)
-fn render_weather_icon(
-    display: &mut impl DrawTarget<Color = Rgb565>,
-    icon_code: &str,
-    symbol_style: &MonoTextStyle<Rgb565>,
-) {
-    let icon_color = get_weather_icon_color(icon_code);
-
-    // Try to render bitmap icon from `weather_icons.rs`
-    if let Some(icon_data) = get_weather_icon(icon_code) {
-        let icon_width: usize = 40;
-        let icon_height: usize = 40;
-        let mut pixels = Vec::with_capacity(icon_width * icon_height);
-
-        // Decode the 1-bit-per-pixel bitmap data
-        for y in 0..icon_height {
-            for x in 0..icon_width {
-                let byte_index = y * (icon_width / 8) + (x / 8);
-                let bit_index = 7 - (x % 8);
-
-                if byte_index < icon_data.len() {
-                    if (icon_data[byte_index] >> bit_index) & 1 == 1 {
-                        pixels.push(Pixel(Point::new(160 + x as i32, 70 + y as i32), icon_color));
-                    }
-                }
-            }
+/// NVS namespace of today's min/max temperature, kept across reboots
+const DAILY_RANGE_NAMESPACE: &str = "weather_day";
+
+/// NVS key of the encoded `DailyRange`
+const DAILY_RANGE_KEY: &str = "range";
+
+/// Load the range saved before the last reboot, `None` if there is none or it is unreadable
+fn load_daily_range(nvs: &EspNvs<NvsDefault>) -> Option<DailyRange> {
+    let mut buf = [0u8; daily_range::ENCODED_LEN];
+    nvs.get_raw(DAILY_RANGE_KEY, &mut buf)
+        .ok()
+        .flatten()
+        .and_then(DailyRange::from_bytes)
+}
+
+/// Persist the range, so that a reboot during the day does not lose it
+fn save_daily_range(nvs: &mut EspNvs<NvsDefault>, range: &DailyRange) -> anyhow::Result<()> {
+    nvs.set_raw(DAILY_RANGE_KEY, &range.to_bytes())?;
+    Ok(())
+}
+
+// ===============================================================================
+// DISPLAY MODE
+// ===============================================================================
+
+/// NVS namespace of the display mode, kept across reboots
+const DISPLAY_MODE_NAMESPACE: &str = "display_mode";
+
+/// NVS key of the mode, see `DisplayMode::to_u8`
+const DISPLAY_MODE_KEY: &str = "mode";
+
+/// Load the mode saved before the last reboot, "home" if there is none or it is unknown
+fn load_display_mode(nvs: &EspNvs<NvsDefault>) -> DisplayMode {
+    nvs.get_u8(DISPLAY_MODE_KEY)
+        .ok()
+        .flatten()
+        .and_then(DisplayMode::from_u8)
+        .unwrap_or_default()
+}
+
+/// Persist the mode, so that a reboot keeps the chosen layout
+fn save_display_mode(nvs: &mut EspNvs<NvsDefault>, mode: DisplayMode) -> anyhow::Result<()> {
+    nvs.set_u8(DISPLAY_MODE_KEY, mode.to_u8())?;
+    Ok(())
+}
+
+// ===============================================================================
+// WEATHER CACHE
+// ===============================================================================
+
+/// NVS namespace of the last weather data, shown right after boot
+const WEATHER_CACHE_NAMESPACE: &str = "weather_cache";
+
+/// NVS key of the weather data as JSON
+const WEATHER_CACHE_JSON_KEY: &str = "json";
+
+/// NVS key of the fetch time (UTC seconds)
+const WEATHER_CACHE_TIME_KEY: &str = "fetched_at";
+
+/// Upper bound for the cached JSON, a response for one city is well below 1 KB
+const WEATHER_CACHE_MAX_LEN: usize = 2048;
+
+/// Load the weather data of the last successful fetch before the reboot
+/// Returns `None` on the first boot or if the entry cannot be read, the display then
+/// waits for the first live fetch as before.
+fn load_weather_cache(nvs: &EspNvs<NvsDefault>) -> Option<(WeatherResponse, u64)> {
+    let fetched_at = nvs.get_u64(WEATHER_CACHE_TIME_KEY).ok().flatten()?;
+    let mut buf = vec![0u8; WEATHER_CACHE_MAX_LEN];
+    let json = nvs
+        .get_raw(WEATHER_CACHE_JSON_KEY, &mut buf)
+        .ok()
+        .flatten()?;
+    match serde_json::from_slice(json) {
+        Ok(weather) => Some((weather, fetched_at)),
+        Err(e) => {
+            warn!("Ignoring the cached weather data: {}", e);
+            None
         }
-        let _ = display.draw_iter(pixels.iter().cloned());
-    } else {
-        // Fallback to emoji symbol if bitmap is not found
-        let symbol = get_weather_symbol(icon_code);
-        let _ = Text::new(symbol, Point::new(160, 70), *symbol_style).draw(display);
     }
 }
 
-/// Render movement detection events in two columns
-fn render_movement_events(
-    display: &mut impl DrawTarget<Color = Rgb565>,
-    events: &[String],
-    text_style: &MonoTextStyle<Rgb565>,
-) {
-    let mut y_offset = 220;
+/// Persist the weather data of a successful fetch for the next boot
+fn save_weather_cache(
+    nvs: &mut EspNvs<NvsDefault>,
+    json: &str,
+    fetched_at: u64,
+) -> anyhow::Result<()> {
+    if json.len() > WEATHER_CACHE_MAX_LEN {
+        anyhow::bail!(
+            "Weather data of {} bytes exceeds the cache size of {} bytes",
+            json.len(),
+            WEATHER_CACHE_MAX_LEN
+        );
+    }
+    nvs.set_raw(WEATHER_CACHE_JSON_KEY, json.as_bytes())?;
+    nvs.set_u64(WEATHER_CACHE_TIME_KEY, fetched_at)?;
+    Ok(())
+}
+
+/// Start the configuration web server
+///
+/// * `GET /` - Form asking for the token
+/// * `POST /config` - Form submission (`token`, `city`, `units`), applied to the next weather fetch;
+///   with only the token it shows the current city and units
+///
+/// The token is only accepted in the form body. Requests without it are answered with 403.
+fn start_web_server(
+    token: String,
+    nvs: Arc<Mutex<EspNvs<NvsDefault>>>,
+) -> anyhow::Result<EspHttpServer<'static>> {
+    let mut server = EspHttpServer::new(&HttpServerConfiguration::default())?;
+
+    server.fn_handler::<anyhow::Error, _>("/", Method::Get, move |req| {
+        let html = web_config::render_login();
+        req.into_ok_response()?.write_all(html.as_bytes())?;
+        Ok(())
+    })?;
 
-    for (i, event) in events.iter().enumerate() {
-        // Alternate between left and right columns
-        let x_pos = if i % 2 == 0 { 10 } else { 120 };
-        let _ = Text::new(event, Point::new(x_pos, y_offset), *text_style).draw(display);
+    server.fn_handler::<anyhow::Error, _>("/config", Method::Post, move |mut req| {
+        // Read the form body, anything beyond the limit is cut off
+        let mut body = [0u8; WEB_MAX_BODY_LEN];
+        let mut len = 0;
+        while len < body.len() {
+            let read = req.read(&mut body[len..])?;
+            if read == 0 {
+                break;
+            }
+            len += read;
+        }
 
-        // Move to the next row after every two events
-        if i % 2 != 0 {
-            y_offset += 20;
+        let pairs = web_config::parse_form(&String::from_utf8_lossy(&body[..len]));
+        let given = web_config::form_value(&pairs, "token").unwrap_or("");
+        if !web_config::token_matches(&token, given) {
+            req.into_status_response(403)?.write_all(b"Forbidden")?;
+            return Ok(());
         }
-    }
+
+        // The login form only sends the token
+        let update = web_config::parse_update(&pairs);
+        if update == web_config::ConfigUpdate::default() {
+            let html = web_config::render_form(&runtime_openweather()?, &token, None);
+            req.into_ok_response()?.write_all(html.as_bytes())?;
+            return Ok(());
+        }
+
+        let (config, message) = {
+            let mut guard = lock_recovering(&RUNTIME_OPENWEATHER);
+            let config = guard
+                .as_mut()
+                .ok_or_else(|| anyhow::anyhow!("RUNTIME_OPENWEATHER not initialized"))?;
+            match web_config::apply_update(config, &update) {
+                Ok(()) => (config.clone(), None),
+                Err(e) => (config.clone(), Some(format!("Not saved: {}", e))),
+            }
+        };
+
+        let message = match message {
+            Some(message) => message,
+            None => {
+                info!(
+                    "Runtime configuration changed: city '{}', units {}",
+                    config.city,
+                    config.units()
+                );
+                CONFIG_CHANGED.store(true, Ordering::SeqCst);
+
+                let saved = save_runtime_config(&mut lock_recovering(&nvs), &config);
+                match saved {
+                    Ok(()) => "Saved".to_string(),
+                    Err(e) => {
+                        error!("Failed to persist runtime configuration: {}", e);
+                        "Applied, but not stored (lost on reboot)".to_string()
+                    }
+                }
+            }
+        };
+
+        let html = web_config::render_form(&config, &token, Some(&message));
+        req.into_ok_response()?.write_all(html.as_bytes())?;
+        Ok(())
+    })?;
+
+    info!("Configuration web server started");
+    Ok(server)
+}
+
+// ===============================================================================
+// DISPLAY RENDERING
+// ===============================================================================
+
+/// Map the configured rotation in degrees to the mipidsi orientation
+fn display_orientation(degrees: u16) -> Orientation {
+    let rotation = match degrees {
+        90 => Rotation::Deg90,
+        180 => Rotation::Deg180,
+        270 => Rotation::Deg270,
+        _ => Rotation::Deg0,
+    };
+    Orientation::new().rotate(rotation)
 }
 
 // ===============================================================================
@@ -593,112 +1406,273 @@ fn render_movement_events(
 fn main() -> anyhow::Result<()> {
     // Initialize ESP-IDF services
     esp_idf_svc::sys::link_patches();
-    esp_idf_svc::log::EspLogger::initialize_default();
+    device_log::initialize()?;
 
     info!("=== ESP32 Weather Station Starting ===");
+    info!("\n{}", check_spiram::memory_report());
 
     // Load configuration from secrets.toml
     let secrets = Secrets::load()?;
+    device_log::set_device_name(&secrets.device_name);
     let peripherals = Peripherals::take()?;
+    let nvs_partition = EspDefaultNvsPartition::take()?;
+
+    // === Runtime Configuration ===
+    // City and units may have been changed via the web server, those values win over secrets.toml
+    let runtime_nvs = EspNvs::new(nvs_partition.clone(), RUNTIME_CONFIG_NAMESPACE, true)?;
+    let mut openweather = secrets.openweather.clone();
+    load_runtime_config(&runtime_nvs, &mut openweather);
+    *lock_recovering(&RUNTIME_OPENWEATHER) = Some(openweather);
+
+    // === Daily Temperature Range ===
+    let mut daily_range_nvs = EspNvs::new(nvs_partition.clone(), DAILY_RANGE_NAMESPACE, true)?;
+    *lock_recovering(&DAILY_RANGE) = load_daily_range(&daily_range_nvs);
+
+    // === Display Mode ===
+    let mut display_mode_nvs = EspNvs::new(nvs_partition.clone(), DISPLAY_MODE_NAMESPACE, true)?;
+    let mut saved_display_mode = load_display_mode(&display_mode_nvs);
+    DISPLAY_MODE.set(saved_display_mode);
+    info!("Display mode: {}", saved_display_mode.as_str());
+
+    // === Weather Cache ===
+    // The last known weather is shown (marked stale) until the first live fetch completes
+    let mut weather_cache_nvs = EspNvs::new(nvs_partition.clone(), WEATHER_CACHE_NAMESPACE, true)?;
+    let cached_weather = load_weather_cache(&weather_cache_nvs);
+    let weather_cache_loaded = cached_weather.is_some();
+    if let Some((weather, fetched_at)) = &cached_weather {
+        info!(
+            "Loaded cached weather for {} (fetched at {})",
+            weather.name, fetched_at
+        );
+    }
+    with_weather(|weather| *weather = cached_weather);
 
-    // === Initialize Wi-Fi ===
-    let mut wifi = setup_wifi(peripherals.modem, &secrets)?;
+    // === Initialize Display ===
+    info!("Initializing display...");
 
-    // === Initialize SNTP (Network Time Protocol) ===
-    let sntp = EspSntp::new_default()?;
-    info!("Waiting for time synchronization...");
-    while sntp.get_sync_status() != SyncStatus::Completed {
-        FreeRtos::delay_ms(100);
+    // Pin assignments for the display, from `[display.pins]`
+    let mut gpios = GpioPool::new(peripherals.pins);
+    let pin_config = &secrets.display.pins;
+    let pins = DisplayPins::take(
+        &mut gpios,
+        DisplayPinNumbers {
+            sclk: pin_config.sclk,
+            mosi: pin_config.mosi,
+            cs: pin_config.cs,
+            dc: pin_config.dc,
+            rst: pin_config.rst,
+        },
+    )?;
+
+    let panel = PanelConfig {
+        width: secrets.display.width,
+        height: secrets.display.height,
+        offset_x: secrets.display.offset_x,
+        offset_y: secrets.display.offset_y,
+        spi_mhz: secrets.display.spi_mhz,
+    };
+    if panel.clamped_spi_mhz() != panel.spi_mhz {
+        warn!(
+            "display.spi_mhz {} is out of range, using {} MHz",
+            panel.spi_mhz,
+            panel.clamped_spi_mhz()
+        );
     }
-    info!("Time synchronized!");
+    let mut display = build_display_for_panel(
+        peripherals.spi2,
+        pins,
+        display_orientation(secrets.display.rotation),
+        panel,
+    )?;
 
-    // === Initialize Movement Events Queue ===
-    *MOVEMENT_EVENTS
-        .lock()
-        .map_err(|e| anyhow::anyhow!("Failed to lock MOVEMENT_EVENTS: {}", e))? =
-        Some(Arc::new(Mutex::new(VecDeque::new())));
-    info!("Movement events queue initialized");
+    // Show panic messages on the display, not only on the serial console
+    // SAFETY: `display` stays in place until the end of `main`, the guard is dropped before it
+    let _panic_screen = unsafe { display_hal::register_panic_display(&mut display) };
+
+    // PWM backlight, without it the text colors are dimmed at night
+    let mut backlight = match secrets.display.backlight_pin {
+        Some(pin) => Some(Backlight::new(
+            peripherals.ledc.timer0,
+            peripherals.ledc.channel0,
+            gpios.take_output(pin)?,
+        )?),
+        None => None,
+    };
+    let mut backlight_percent = 100;
+
+    // LED or buzzer pulsed on movement events, they only arrive via MQTT
+    if let Some(pin) = secrets
+        .mqtt
+        .movement_alert_pin
+        .filter(|_| secrets.mqtt.enabled)
+    {
+        let mut alert = PinDriver::output(gpios.take_output(pin)?)?;
+        alert.set_low()?;
+        *lock_recovering(&MOVEMENT_ALERT) = Some(alert);
+        info!("Movement alert on GPIO{}", pin);
+    }
 
-    // === Initialize MQTT ===
-    let movement_events_arc = MOVEMENT_EVENTS
-        .lock()
-        .map_err(|e| anyhow::anyhow!("Failed to lock MOVEMENT_EVENTS: {}", e))?
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("MOVEMENT_EVENTS not initialized"))?
-        .clone();
+    display.clear(secrets.theme.bg).ok();
 
-    let mut mqtt_client = setup_mqtt(&secrets, movement_events_arc)?;
+    // The size reported by the driver already reflects the rotation
+    let display_size = display.size();
+    let layout = Layout::for_size(display_size.width, display_size.height);
+    info!(
+        "Display initialized successfully ({}x{}, rotation {}°)",
+        display_size.width, display_size.height, secrets.display.rotation
+    );
 
-    // === Initialize Display ===
-    info!("Initializing display...");
+    // Fail loudly on boards without the required PSRAM instead of running out of memory later
+    if let Err(e) = check_spiram::assert_spiram_present(secrets.memory.min_spiram_bytes) {
+        error!("{}", e);
+        let error_style = MonoTextStyle::new(&FONT_10X20, Rgb565::RED);
+        let _ = Text::new("SPIRAM missing!", Point::new(10, 20), error_style).draw(&mut display);
+        return Err(e);
+    }
 
-    // Pin assignments for the display
-    let sclk = peripherals.pins.gpio18;
-    let mosi = peripherals.pins.gpio23;
-    let cs = peripherals.pins.gpio15;
-    let dc = peripherals.pins.gpio21;
-    let mut rst = PinDriver::output(peripherals.pins.gpio22)?;
-
-    // Perform a hardware reset on the display
-    rst.set_low()?;
-    FreeRtos::delay_ms(50);
-    rst.set_high()?;
-    FreeRtos::delay_ms(200);
-
-    // Configure the SPI bus
-    let spi_config = Config::new().baudrate(26.MHz().into());
-    let spi_driver = SpiDriver::new(
-        peripherals.spi2,
-        sclk,
-        mosi,
-        None::<AnyIOPin>,
-        &SpiDriverConfig::new(),
-    )?;
-    let spi_device = SpiDeviceDriver::new(spi_driver, Some(cs), &spi_config)?;
-    let spi_wrapper = SpiWrapper { spi: spi_device };
-    let dc_wrapper = DcPinWrapper {
-        pin: PinDriver::output(dc.downgrade_output())?,
+    // Optional frame buffer in PSRAM, without enough PSRAM the content is drawn directly
+    let mut frame_buffer = if secrets.display.frame_buffer {
+        match check_spiram::assert_spiram_present(FrameBuffer::byte_len(display_size))
+            .and_then(|()| FrameBuffer::new(display_size, secrets.theme.bg))
+        {
+            Ok(frame_buffer) => {
+                info!(
+                    "Frame buffer enabled ({} bytes)",
+                    FrameBuffer::byte_len(display_size)
+                );
+                Some(frame_buffer)
+            }
+            Err(e) => {
+                warn!("Frame buffer disabled, drawing directly: {}", e);
+                None
+            }
+        }
+    } else {
+        None
     };
 
-    // Initialize the display driver using the `mipidsi` crate
-    static mut DISPLAY_BUFFER: [u8; 240 * 10 * 2] = [0u8; 240 * 10 * 2];
-    let di = unsafe {
-        mipidsi::interface::SpiInterface::new(
-            spi_wrapper,
-            dc_wrapper,
-            &mut *addr_of_mut!(DISPLAY_BUFFER),
-        )
+    // Boot splash until the first weather data arrives
+    draw_boot_status(&mut display, &layout, &secrets.theme, BootStep::Wifi);
+
+    // === Initialize Wi-Fi ===
+    let mut wifi = setup_wifi(peripherals.modem, nvs_partition, &secrets)?;
+
+    // === Initialize Configuration Web Server ===
+    // Kept alive for the whole runtime, dropping the server stops it
+    let _web_server = if secrets.web.enabled {
+        Some(start_web_server(
+            secrets.web.token.clone(),
+            Arc::new(Mutex::new(runtime_nvs)),
+        )?)
+    } else {
+        None
     };
 
-    let mut display = Builder::new(ST7789, di)
-        .display_size(240, 320)
-        .display_offset(0, 0)
-        .color_order(ColorOrder::Rgb)
-        .invert_colors(ColorInversion::Inverted)
-        .init(&mut FreeRtos)
-        .map_err(|e| anyhow::anyhow!("Display initialization failed: {:?}", e))?;
+    // === Initialize SNTP (Network Time Protocol) ===
+    draw_boot_status(&mut display, &layout, &secrets.theme, BootStep::TimeSync);
+    let sntp = start_sntp(Duration::from_secs(secrets.time.resync_hours * 3600))?;
+    // Offline the clock runs unsynchronized until Wi-Fi connects in the background
+    let mut offline = !wifi.is_up().unwrap_or(false);
+    // Read again after every reconnect, the DHCP server may hand out another address
+    let mut ip_address = String::new();
+    update_ip(&wifi, &mut ip_address);
+    let mut clock_synced = if offline {
+        warn!("Wi-Fi is offline, continuing with an unsynchronized clock");
+        false
+    } else {
+        info!("Waiting for time synchronization...");
+        match wait_for_sntp(&sntp, SNTP_TIMEOUT) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{}, continuing with an unsynchronized clock", e);
+                false
+            }
+        }
+    };
 
-    display.clear(Rgb565::BLACK).ok();
-    info!("Display initialized successfully");
+    // === Initialize Movement Events Queue ===
+    *lock_recovering(&MOVEMENT_EVENTS) = Some(Arc::new(Mutex::new(VecDeque::new())));
+    info!("Movement events queue initialized");
+
+    // === Initialize MQTT ===
+    // Without a broker the movement list stays empty and nothing is published
+    let mut mqtt = if secrets.mqtt.enabled {
+        draw_boot_status(&mut display, &layout, &secrets.theme, BootStep::Mqtt);
+        let movement_events_arc = lock_recovering(&MOVEMENT_EVENTS)
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("MOVEMENT_EVENTS not initialized"))?;
+
+        Some(setup_mqtt(&secrets, movement_events_arc)?)
+    } else {
+        info!("MQTT disabled");
+        None
+    };
 
     // === Define Text Styles ===
     let text_style = MonoTextStyleBuilder::new()
         .font(&FONT_10X20)
-        .text_color(Rgb565::WHITE)
-        .background_color(Rgb565::BLACK)
+        .text_color(secrets.theme.fg)
+        .background_color(secrets.theme.bg)
         .build();
 
     let symbol_style = MonoTextStyle::new(&PROFONT_24_POINT, Rgb565::YELLOW);
 
+    // === Initialize Task Watchdog ===
+    // The main loop feeds it every iteration and before every request and Wi-Fi reconnect
+    // attempt. A step that hangs (e.g. a request or a lock) panics the task watchdog, the
+    // panic handler logs the backtrace and restarts the chip.
+    let watchdog_config = TWDTConfig {
+        duration: Duration::from_secs(secrets.watchdog.timeout_secs),
+        panic_on_trigger: true,
+        ..Default::default()
+    };
+    let mut watchdog = TWDTDriver::new(peripherals.twdt, &watchdog_config)?;
+    let mut watchdog_subscription = watchdog.watch_current_task()?;
+    info!(
+        "Task watchdog watching the main loop ({}s)",
+        secrets.watchdog.timeout_secs
+    );
+
     // === Main Loop ===
     info!("Entering main loop");
-
+    draw_boot_status(&mut display, &layout, &secrets.theme, BootStep::Weather);
+    let mut splash_visible = true;
+
+    // The clock follows the wall clock, so that it redraws right after a second change.
+    // The weather follows the uptime, which does not jump when SNTP corrects the time.
+    let schedule = &secrets.schedule;
+    let loop_started = Instant::now();
+    let mut clock_cadence = Cadence::new(Duration::from_millis(schedule.clock_redraw_ms.into()));
+    let mut weather_cadence =
+        Cadence::new(Duration::from_secs(schedule.weather_interval_mins * 60));
     let mut last_weather_fetch = 0u64;
-    let weather_interval = 15 * 60; // 15 minutes in seconds
-    let mut previous_state = DisplayState::new();
-    let mut last_second = 0u32;
+    // Cleared when the API key has no One Call 3.0 subscription
+    let mut alerts_available = true;
+    let mut previous_state = DisplayState::new(&secrets.theme);
+    let mut last_mem_stats: Option<Instant> = None;
+    // Weather messages not published yet, e.g. during a reconnect of the broker
+    let mut outbox = Outbox::new(MQTT_OUTBOX_CAPACITY);
+
+    // Connection attempts while offline, cycling through the configured networks
+    let mut wifi_retry = WifiRetry::new(Duration::ZERO);
+
+    // The display sleeps after `power_save_minutes` without a movement event or weather change
+    let boot_secs = unsafe { esp_idf_sys::esp_timer_get_time() } as u64 / 1_000_000;
+    let mut power_save = PowerSave::new(secrets.display.power_save_minutes, boot_secs);
+    // Latest movement event and the shown weather, a change counts as activity
+    let mut last_activity_key = None;
+
+    // Index of the displayed city, 0 is the home city
+    let mut city_cycle = Cadence::new(secrets.openweather.cycle_interval());
+    city_cycle.mark_run(Duration::ZERO);
+    let mut shown_city = 0usize;
 
     loop {
+        // Every iteration, including the short sleeps below, counts as progress
+        if let Err(e) = watchdog_subscription.feed() {
+            error!("Failed to feed the task watchdog: {:?}", e);
+        }
+
         // Get current timestamp
         let now = SystemTime::now();
         let since_the_epoch = now.duration_since(UNIX_EPOCH)?;
@@ -708,108 +1682,619 @@ fn main() -> anyhow::Result<()> {
         let (year, month, day, hour, minute, second) =
             time_utils::utc_to_berlin(utc_timestamp as i64);
 
-        // Only update the display if the second has changed, to reduce CPU usage.
-        if second == last_second {
-            FreeRtos::delay_ms(100); // Short sleep
+        // Only update the display when the clock redraw is due, to reduce CPU usage.
+        if !clock_cadence.poll(since_the_epoch) {
+            FreeRtos::delay_ms(schedule.tick_ms);
             continue;
         }
-        last_second = second;
+        let uptime = loop_started.elapsed();
+
+        // === Offline Mode ===
+        // Without a connection since boot the clock keeps running while the driver connects
+        // in the background. A connection lost later is handled by the weather update.
+        if offline {
+            if wifi.is_up().unwrap_or(false) {
+                info!("Wi-Fi connected after {} attempts", wifi_retry.attempts());
+                offline = false;
+                wifi_retry.reset(uptime);
+                update_ip(&wifi, &mut ip_address);
+            } else if wifi_retry.poll(uptime) {
+                let networks = &secrets.wifi.networks;
+                let network = &networks[(wifi_retry.attempts() as usize - 1) % networks.len()];
+                info!("Offline, trying Wi-Fi '{}' in the background", network.ssid);
+                if let Err(e) = start_wifi_connect(&mut wifi, network) {
+                    warn!("Failed to start the Wi-Fi connection: {}", e);
+                }
+            }
+        }
+
+        // City and units may change at runtime via the web server.
+        // The home city comes first, the other cities only need their current weather.
+        let mut city_configs = runtime_openweather()?.city_configs();
+        let openweather = city_configs.remove(0);
+
+        if let Some(mqtt_client) = mqtt.as_mut().map(MqttHandle::client) {
+            // === MQTT Connection Handling ===
+            // Runs once per (re)connect of the MQTT client
+            if MQTT_SESSION.take_new_connection() {
+                // The broker forgets the subscriptions of a clean session on disconnect
+                subscribe_topics(mqtt_client, &secrets);
+                if let Err(e) = mqtt_client.publish(
+                    &secrets.mqtt.status_topic,
+                    embedded_svc::mqtt::client::QoS::AtLeastOnce,
+                    true,
+                    b"online",
+                ) {
+                    error!("Failed to publish online status: {:?}", e);
+                }
+                if let Err(e) = publish_discovery(mqtt_client, &secrets) {
+                    error!("Failed to publish Home Assistant discovery: {}", e);
+                }
+
+                // Weather messages whose publish failed are retried in order. Only after a
+                // (re)connect, while the broker is down every attempt would fail.
+                if !outbox.is_empty() {
+                    flush_outbox(mqtt_client, &mut outbox);
+                }
+            }
+
+            // === Memory and Fetch Reports ===
+            if last_mem_stats.is_none_or(|last| last.elapsed() >= MEM_STATS_INTERVAL) {
+                if let Err(e) = publish_mem_stats(mqtt_client, MEM_STATS_TOPIC) {
+                    error!("Failed to publish memory stats: {}", e);
+                }
+                if let Err(e) = publish_fetch_stats(mqtt_client, FETCH_STATS_TOPIC) {
+                    error!("Failed to publish fetch stats: {}", e);
+                }
+                last_mem_stats = Some(Instant::now());
+            }
+        }
 
         // === Weather Update Logic ===
-        // Fetch new weather data every `weather_interval` seconds
-        if utc_timestamp >= last_weather_fetch + weather_interval || last_weather_fetch == 0 {
+        // Fetch new weather data every `weather_interval_mins`, after a config change
+        // and on a refresh command. Both flags are consumed here, so each triggers one fetch.
+        // With cached data the first frame is drawn before the first fetch blocks the loop
+        let config_changed = CONFIG_CHANGED.swap(false, Ordering::SeqCst);
+        let mut refresh_requested = REFRESH_REQUESTED.swap(false, Ordering::SeqCst);
+        if refresh_requested && utc_timestamp < last_weather_fetch + REFRESH_MIN_GAP_SECS {
+            info!(
+                "Weather refresh ignored, the last fetch was less than {}s ago",
+                REFRESH_MIN_GAP_SECS
+            );
+            refresh_requested = false;
+        }
+        let show_cache_first = splash_visible && weather_cache_loaded;
+        if !offline
+            && !show_cache_first
+            && (weather_cadence.is_due(uptime) || config_changed || refresh_requested)
+        {
             info!("Fetching weather update...");
 
             // Ensure Wi-Fi is still connected before making the request
             if !wifi.is_connected()? {
                 info!("Wi-Fi disconnected, reconnecting...");
-                wifi.connect()?;
-                wifi.wait_netif_up()?;
+                if let Err(e) = reconnect_wifi_with_backoff(&mut wifi, WIFI_MAX_RETRIES) {
+                    error!("Wi-Fi reconnect failed, rebooting: {}", e);
+                    if let Some(mqtt) = mqtt.take() {
+                        if let Err(e) = mqtt.shutdown(MQTT_SHUTDOWN_TIMEOUT) {
+                            warn!("Failed to stop the MQTT client: {}", e);
+                        }
+                    }
+                    esp_idf_hal::reset::restart();
+                }
+                STATS.record_wifi_reconnect();
+                update_ip(&wifi, &mut ip_address);
             }
 
             // Fetch weather data from OpenWeatherMap
-            match get_weather(&secrets.openweather.api_key, &secrets.openweather.city) {
+            let provider = weather_provider(&secrets.weather_provider, &openweather);
+            match get_weather_retrying(provider.as_ref(), WEATHER_MAX_ATTEMPTS) {
+                // A partial response without conditions is skipped instead of panicking later,
+                // the previous data stays on screen (and is marked stale eventually)
+                Ok(weather) if weather.weather.is_empty() => {
+                    warn!(
+                        "Weather for {} contains no conditions, skipping this update",
+                        weather.name
+                    );
+                    STATS.record_fetch_err();
+                    weather_cadence.mark_run(uptime);
+                    last_weather_fetch = utc_timestamp;
+                }
                 Ok(weather) => {
                     info!(
-                        "Weather data received: {} - {}°C",
-                        weather.name, weather.main.temp
+                        "Weather data received: {}, {} - {}{}",
+                        weather.name,
+                        weather.sys.country,
+                        weather.main.temp,
+                        openweather.temperature_unit()
                     );
 
-                    // Store weather data in the global static variable
-                    *LAST_WEATHER_DATA.lock().map_err(|e| {
-                        anyhow::anyhow!("Failed to lock LAST_WEATHER_DATA: {}", e)
-                    })? = Some(weather);
-
-                    // Publish the new weather data to an MQTT topic
-                    if let Ok(payload) = serde_json::to_string(
-                        LAST_WEATHER_DATA
-                            .lock()
-                            .map_err(|e| {
-                                anyhow::anyhow!("Failed to lock LAST_WEATHER_DATA: {}", e)
-                            })?
+                    // Today's min/max, by the local day of the measurement. The fetch may finish
+                    // after midnight while the sample still belongs to the previous day.
+                    // Without a synchronized clock the local day is unknown.
+                    if clock_synced {
+                        let measured_at = if weather.dt > 0 {
+                            weather.dt
+                        } else {
+                            utc_timestamp as i64
+                        };
+                        let (year, month, day, _, _, _) = time_utils::utc_to_berlin(measured_at);
+                        let mut range = lock_recovering(&DAILY_RANGE);
+                        // A new city or unit system starts a new range
+                        let previous = if config_changed { None } else { *range };
+                        let updated = daily_range::record_sample(
+                            previous,
+                            daily_range::day_key(year, month, day),
+                            weather.main.temp,
+                        );
+                        if *range != Some(updated) {
+                            if let Err(e) = save_daily_range(&mut daily_range_nvs, &updated) {
+                                warn!("Failed to save the daily temperature range: {}", e);
+                            }
+                            *range = Some(updated);
+                        }
+                    }
+
+                    // Serialize before the data moves into the global static variable
+                    let payload = serde_json::to_string(&weather);
+                    let values = weather_values(&weather, openweather.units());
+
+                    // Store weather data in the global static variable, after comparing
+                    // the temperature with the previous reading. A stale reading or one in
+                    // other units (changed via the web server) gives no trend.
+                    let temp_trend = with_weather(|last_weather| {
+                        let temp_trend = last_weather
                             .as_ref()
-                            .ok_or_else(|| anyhow::anyhow!("Weather data not available"))?,
-                    ) {
-                        let topic = format!("weather/{}", secrets.openweather.city);
-                        match mqtt_client.publish(
-                            topic.as_str(),
-                            embedded_svc::mqtt::client::QoS::AtLeastOnce,
-                            false,
-                            payload.as_bytes(),
-                        ) {
-                            Ok(_) => info!("Weather data published to MQTT: {}", topic),
-                            Err(e) => error!("MQTT publish error: {:?}", e),
+                            .filter(|(_, fetched_at)| {
+                                !config_changed
+                                    && utc_timestamp.checked_sub(*fetched_at).is_some_and(|age| {
+                                        age <= secrets.display.stale_after_mins * 60
+                                    })
+                            })
+                            .map(|(previous, _)| {
+                                trend::temp_trend(
+                                    previous.main.temp,
+                                    weather.main.temp,
+                                    trend::deadband_for_units(openweather.units()),
+                                )
+                            });
+                        *last_weather = Some((weather, utc_timestamp));
+                        temp_trend
+                    });
+                    *lock_recovering(&LAST_TEMP_TREND) = temp_trend;
+
+                    // Keep the data for the next boot
+                    if let Ok(json) = &payload {
+                        if let Err(e) =
+                            save_weather_cache(&mut weather_cache_nvs, json, utc_timestamp)
+                        {
+                            warn!("Failed to cache the weather data: {}", e);
                         }
                     }
 
+                    // Publish the new weather data, the topic matches the discovery configs
+                    if let Some(mqtt_client) = mqtt.as_mut().map(MqttHandle::client) {
+                        publish_weather(
+                            mqtt_client,
+                            &mut outbox,
+                            &secrets,
+                            secrets.openweather.home_city(),
+                            payload.ok().as_deref(),
+                            &values,
+                        );
+                    }
+
+                    STATS.record_fetch_ok();
+                    weather_cadence.mark_run(uptime);
                     last_weather_fetch = utc_timestamp;
                 }
                 Err(e) => {
                     error!("Weather fetch error: {}", e);
+                    STATS.record_fetch_err();
+                }
+            }
+
+            // Air quality needs coordinates, it is skipped when only a city is configured
+            if let Some((lat, lon)) = openweather.coordinates() {
+                match get_air_quality(&openweather.api_key, lat, lon, openweather.http_timeout()) {
+                    Ok(aqi) => {
+                        info!("Air quality index: {}", aqi);
+                        *lock_recovering(&LAST_AIR_QUALITY) = Some(aqi);
+                    }
+                    Err(e) => {
+                        error!("Air quality fetch error: {}", e);
+                    }
+                }
+
+                // Without a One Call subscription the alerts stay off until the next boot
+                if alerts_available {
+                    match get_alerts(&openweather.api_key, lat, lon, openweather.http_timeout()) {
+                        Ok(alerts) => {
+                            for alert in &alerts {
+                                info!("Weather alert: {}: {}", alert.event, alert.description);
+                            }
+                            *lock_recovering(&LAST_ALERTS) = alerts;
+                        }
+                        Err(e)
+                            if e.downcast_ref::<OwmError>()
+                                .is_some_and(|owm_error| owm_error.cod == 401) =>
+                        {
+                            warn!("Weather alerts disabled, One Call 3.0 not available: {}", e);
+                            alerts_available = false;
+                        }
+                        Err(e) => {
+                            error!("Weather alerts fetch error: {}", e);
+                        }
+                    }
+                }
+            } else {
+                // The coordinates were removed at runtime, the old index is for another place
+                *lock_recovering(&LAST_AIR_QUALITY) = None;
+            }
+
+            // Fetch the daily forecast on the same cadence, keeping the old one on failure
+            match get_forecast(&openweather) {
+                Ok(forecast) => {
+                    info!("Forecast received: {} days", forecast.len());
+                    *lock_recovering(&LAST_FORECAST_DATA) = Some(forecast);
+                }
+                Err(e) => {
+                    error!("Forecast fetch error: {}", e);
+                }
+            }
+
+            // The other cities, one attempt each, their previous data stays on failure.
+            // A changed unit system makes their data outdated.
+            if config_changed {
+                lock_recovering(&OTHER_CITIES_WEATHER).clear();
+            }
+            for config in &city_configs {
+                match get_weather(config) {
+                    Ok(weather) if weather.weather.is_empty() => {
+                        warn!(
+                            "Weather for {} contains no conditions, skipping",
+                            config.city
+                        );
+                        STATS.record_fetch_err();
+                    }
+                    Ok(weather) => {
+                        info!(
+                            "Weather data received: {}, {} - {}{}",
+                            weather.name,
+                            weather.sys.country,
+                            weather.main.temp,
+                            config.temperature_unit()
+                        );
+                        if let Some(mqtt_client) = mqtt.as_mut().map(MqttHandle::client) {
+                            publish_weather(
+                                mqtt_client,
+                                &mut outbox,
+                                &secrets,
+                                &config.city,
+                                serde_json::to_string(&weather).ok().as_deref(),
+                                &weather_values(&weather, config.units()),
+                            );
+                        }
+                        lock_recovering(&OTHER_CITIES_WEATHER)
+                            .insert(config.city.clone(), (weather, utc_timestamp));
+                        STATS.record_fetch_ok();
+                    }
+                    Err(e) => {
+                        error!("Weather fetch error for {}: {}", config.city, e);
+                        STATS.record_fetch_err();
+                    }
                 }
             }
         }
 
+        // === City Cycle ===
+        // The displayed city changes every `cycle_secs`, a city without data shows the home city
+        if city_cycle.poll(uptime) {
+            shown_city = (shown_city + 1) % (city_configs.len() + 1);
+        }
+        let other_weather = shown_city.checked_sub(1).and_then(|index| {
+            let city = &city_configs.get(index)?.city;
+            lock_recovering(&OTHER_CITIES_WEATHER).get(city).cloned()
+        });
+        // Trend, daily range, alerts, air quality and forecast belong to the home city
+        let showing_home = other_weather.is_none();
+
         // === Build Current Display State ===
-        let mut current_state = DisplayState::new();
+        let mut current_state = DisplayState::new(&secrets.theme);
 
         // Time and date
-        current_state.time_str = time_utils::format_time(hour, minute, second);
-        current_state.date_str = format!(
-            "{} {}",
-            time_utils::format_date(day, month, year),
-            time_utils::get_timezone_str(year, month, day, hour)
-        );
+        // Without SNTP the clock starts in 1970, show a banner instead of a bogus date
+        // and the time of the RTC, it counts up from boot
+        if !clock_synced && sntp.get_sync_status() == SyncStatus::Completed {
+            info!("Time synchronized!");
+            clock_synced = true;
+        }
+        if clock_synced {
+            current_state.time_str = time_utils::format_time(hour, minute, second);
+            // The weekday takes the place of the time zone, both need the same room
+            let date_format = secrets.display.date_format();
+            match secrets.display.weekday_lang() {
+                Some(lang) if layout.show_time_zone => {
+                    current_state.date_str =
+                        time_utils::format_date_long(day, month, year, lang, date_format);
+                }
+                _ => {
+                    current_state.date_str =
+                        time_utils::format_date_with(day, month, year, date_format);
+                    if layout.show_time_zone {
+                        current_state.date_str.push(' ');
+                        current_state
+                            .date_str
+                            .push_str(time_utils::get_timezone_str(year, month, day, hour));
+                    }
+                }
+            }
+        } else {
+            current_state.time_str = time_utils::format_unsynced_time(utc_timestamp);
+            current_state.date_str = CLOCK_UNSYNCED_BANNER.to_string();
+        }
 
-        // Weather data from the global static variable
-        if let Some(weather) = LAST_WEATHER_DATA
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock LAST_WEATHER_DATA: {}", e))?
-            .as_ref()
-        {
-            current_state.city_name = weather.name.clone();
-            current_state.weather_temp = format!("{:.1}°C", weather.main.temp);
-            current_state.weather_desc = weather.weather[0].description.clone();
-            current_state.weather_icon = weather.weather[0].icon.clone();
-            current_state.wind_str = format!("W: {:.1}m/s", weather.wind.speed);
-            current_state.hum_str = format!("H: {}%", weather.main.humidity);
+        // Status line: uptime and age of the weather data
+        // esp_timer counts microseconds since boot and is not affected by the SNTP sync
+        let uptime_secs = unsafe { esp_idf_sys::esp_timer_get_time() } as u64 / 1_000_000;
+        current_state.uptime_str = time_utils::format_uptime(uptime_secs);
+        current_state.last_fetch_age_str = time_utils::format_fetch_age(None);
+
+        // Weather fetches since boot
+        current_state.stats_str = STATS.snapshot().label();
+
+        // Weather data from the global static variable, copied so that the lock is not
+        // held while the other statics are read below
+        let shown_weather = match other_weather {
+            Some(weather) => Some(weather),
+            None => with_weather(|weather| weather.clone()),
+        };
+        if let Some((weather, fetched_at)) = shown_weather.as_ref() {
+            // The age is the clock minus `fetched_at`. Data cached in NVS carries a synced fetch
+            // time, so before the SNTP sync the clock is behind it: the age is unknown and the
+            // data counts as stale. Only until the sync, from then on it ages like live data.
+            let age_secs = utc_timestamp.checked_sub(*fetched_at);
+            let stale = age_secs.is_none_or(|age| age > secrets.display.stale_after_mins * 60);
+            current_state.last_fetch_age_str = time_utils::format_fetch_age(age_secs);
+            current_state.city_name =
+                geocoding::location_label(&weather.name, &weather.sys.country);
+            current_state.weather_temp = format!(
+                "{}{}",
+                temperature::format_temp(
+                    weather.main.temp,
+                    openweather.temperature_unit(),
+                    secrets.display.temp_decimals,
+                    secrets.display.temp_show_sign
+                ),
+                stale_marker(stale)
+            );
+            if let Some(temp_colors) = &secrets.theme.temp_colors {
+                current_state.temp_color = temperature::temp_color(
+                    temperature::to_celsius(weather.main.temp, openweather.units()),
+                    temp_colors,
+                );
+            }
+            // Time of the measurement, which can be well before the fetch
+            current_state.observed_str = time_utils::format_observation_time(weather.dt);
+            // The arrow disappears together with the data it compares
+            if !stale && showing_home {
+                current_state.temp_trend = *lock_recovering(&LAST_TEMP_TREND);
+            }
+            if let Some(condition) = weather.weather.first() {
+                current_state.weather_desc = condition.description.clone();
+                current_state.weather_icon = condition.icon.clone();
+            }
+            // Some station responses lack wind, humidity or clouds
+            current_state.wind_str = match &weather.wind {
+                Some(wind) => format!("W: {:.1}{}", wind.speed, openweather.wind_speed_unit()),
+                None => "W: --".to_string(),
+            };
+            current_state.hum_str = match weather.main.humidity {
+                Some(humidity) => format!("H: {}%", humidity),
+                None => "H: --".to_string(),
+            };
+            // Padded to a fixed width, so that a shorter value overwrites a longer one
+            current_state.clouds_str = match &weather.clouds {
+                Some(clouds) => format!("Cl: {:>3}%", clouds.all),
+                None => "Cl:   --".to_string(),
+            };
+            // Day/night from the real sunrise/sunset, the icon's d/n suffix lags behind
+            if clock_synced {
+                current_state.daytime = time_utils::is_daytime(
+                    utc_timestamp as i64,
+                    weather.sys.sunrise,
+                    weather.sys.sunset,
+                );
+            }
+        }
+
+        // Today's min/max, hidden after midnight until the first sample of the new day
+        let today = daily_range::day_key(year, month, day);
+        if let Some(range) = *lock_recovering(&DAILY_RANGE) {
+            if clock_synced && showing_home && range.day == today {
+                current_state.temp_range_str = range.label(
+                    openweather.temperature_unit(),
+                    secrets.display.temp_decimals,
+                    secrets.display.temp_show_sign,
+                );
+            }
+        }
+
+        if showing_home {
+            // Weather alerts that have not ended yet
+            current_state.alerts = lock_recovering(&LAST_ALERTS)
+                .iter()
+                .filter(|alert| alert.is_active(utc_timestamp as i64))
+                .map(|alert| alert.event.clone())
+                .collect();
+
+            // Air quality index
+            current_state.aqi = *lock_recovering(&LAST_AIR_QUALITY);
+
+            // Forecast for the next three days
+            if let Some(forecast) = lock_recovering(&LAST_FORECAST_DATA).as_ref() {
+                current_state.forecast = forecast
+                    .iter()
+                    .take(3)
+                    .map(|entry| {
+                        let (year, month, day, _, _, _) = time_utils::utc_to_berlin(entry.dt);
+                        ForecastCell {
+                            day: time_utils::weekday_abbr(
+                                day,
+                                month,
+                                year,
+                                time_utils::WeekdayLang::English,
+                            )
+                            .unwrap_or_default()
+                            .to_string(),
+                            // Whole degrees, the cells are narrow
+                            temp: temperature::format_temp(
+                                entry.temp,
+                                openweather.temperature_unit(),
+                                0,
+                                secrets.display.temp_show_sign,
+                            ),
+                            icon: entry.icon.clone(),
+                        }
+                    })
+                    .collect();
+            }
         }
 
         // Movement events from the global queue
-        let movement_events_guard = MOVEMENT_EVENTS
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Failed to lock MOVEMENT_EVENTS: {}", e))?;
-        if let Some(events_arc) = movement_events_guard.as_ref() {
-            let events = events_arc
-                .lock()
-                .map_err(|e| anyhow::anyhow!("Failed to lock movement events: {}", e))?;
-            current_state.movement_events = events.iter().cloned().collect();
-        }
-
-        // === Render Display (only if the state has changed) ===
-        if current_state != previous_state {
-            render_display(&mut display, &current_state, &text_style, &symbol_style);
+        let mut latest_movement = None;
+        if let Some(movement_events) = with_movement_events(|events| {
+            // Newest first
+            latest_movement = events.front().copied();
+            events
+                .iter()
+                .map(|&event| time_utils::format_event_time(event, utc_timestamp as i64))
+                .collect()
+        }) {
+            current_state.movement_events = movement_events;
+        }
+
+        // Wi-Fi signal strength
+        current_state.wifi_bars = get_rssi(&wifi).map(rssi_to_bars);
+        if secrets.display.show_ip {
+            current_state.ip_str = ip_address.clone();
+        }
+
+        // Display mode, saved whenever MQTT changed it
+        current_state.display_mode = DISPLAY_MODE.get();
+        if current_state.display_mode != saved_display_mode {
+            match save_display_mode(&mut display_mode_nvs, current_state.display_mode) {
+                Ok(()) => saved_display_mode = current_state.display_mode,
+                Err(e) => error!("Failed to save the display mode: {}", e),
+            }
+        }
+
+        // Dim the backlight at night, or the text if there is no PWM backlight.
+        // Both only change on the hour transition.
+        let dim_factor = match backlight.as_mut() {
+            Some(backlight) => {
+                let percent = dimming::backlight_percent_for_hour(hour);
+                if percent != backlight_percent {
+                    match backlight.set_backlight(percent) {
+                        Ok(()) => backlight_percent = percent,
+                        Err(e) => error!("Failed to set the backlight: {}", e),
+                    }
+                }
+                1.0
+            }
+            None => dimming::dim_factor_for_hour(hour),
+        };
+        let current_state = dim_colors(&current_state, dim_factor);
+
+        // === Power Save ===
+        // The clock keeps ticking in the state, only the SPI writes pause while asleep.
+        // The panel keeps its frame memory, so rendering continues from `previous_state`.
+        // The other cities of the cycle are no activity, the home city's values are kept
+        let activity_key = match (&last_activity_key, showing_home) {
+            (Some((_, temp, desc, icon)), false) => {
+                (latest_movement, temp.clone(), desc.clone(), icon.clone())
+            }
+            _ => (
+                latest_movement,
+                current_state.weather_temp.clone(),
+                current_state.weather_desc.clone(),
+                current_state.weather_icon.clone(),
+            ),
+        };
+        let activity = last_activity_key.as_ref() != Some(&activity_key);
+        last_activity_key = Some(activity_key);
+        match power_save.update(activity, uptime_secs) {
+            PowerAction::Sleep => {
+                info!("No activity, display going to sleep");
+                if let Err(e) = display.sleep(&mut FreeRtos) {
+                    error!("Failed to send the display to sleep: {:?}", e);
+                }
+            }
+            PowerAction::Wake => {
+                info!("Activity, waking the display");
+                if let Err(e) = display.wake(&mut FreeRtos) {
+                    error!("Failed to wake the display: {:?}", e);
+                }
+            }
+            PowerAction::Keep => {}
+        }
+
+        // === Render Display (only if the state has changed and the display is awake) ===
+        // Most seconds only the clock ticks, then just the time field is written over SPI
+        // With a frame buffer everything is drawn into it and the changed area sent below
+        if !power_save.is_asleep() && only_seconds_changed(&current_state, &previous_state) {
+            match frame_buffer.as_mut() {
+                Some(frame_buffer) => {
+                    render_time(frame_buffer, &layout, &current_state, &text_style)
+                }
+                None => render_time(&mut display, &layout, &current_state, &text_style),
+            }
             previous_state = current_state;
+        } else if !power_save.is_asleep() && current_state != previous_state {
+            // The first frame replaces the boot splash. A mode change leaves parts of the
+            // other layout behind, so the screen is cleared and everything drawn again.
+            if splash_visible || current_state.display_mode != previous_state.display_mode {
+                match frame_buffer.as_mut() {
+                    // The splash was drawn on the panel directly, the whole buffer is sent
+                    Some(frame_buffer) => {
+                        frame_buffer.clear(secrets.theme.bg).ok();
+                        frame_buffer.invalidate();
+                    }
+                    None => {
+                        display.clear(secrets.theme.bg).ok();
+                    }
+                }
+                previous_state = DisplayState::new(&secrets.theme);
+                splash_visible = false;
+            }
+            let history_len = secrets.mqtt.movement_history_len;
+            match frame_buffer.as_mut() {
+                Some(frame_buffer) => render_display(
+                    frame_buffer,
+                    &layout,
+                    &current_state,
+                    &previous_state,
+                    &text_style,
+                    &symbol_style,
+                    history_len,
+                ),
+                None => render_display(
+                    &mut display,
+                    &layout,
+                    &current_state,
+                    &previous_state,
+                    &text_style,
+                    &symbol_style,
+                    history_len,
+                ),
+            }
+            previous_state = current_state;
+        }
+        // The buffer starts out different from the panel, the splash stays until the first frame
+        if let Some(frame_buffer) = frame_buffer.as_mut().filter(|_| !splash_visible) {
+            if let Err(e) = frame_buffer.blit(&mut display) {
+                error!("Failed to send the frame buffer: {:?}", e);
+            }
         }
 
         // Short delay to yield to other tasks
@@ -0,0 +1,136 @@
+// movement.rs
+use embedded_graphics::prelude::Point;
+use embedded_graphics::primitives::Rectangle;
+use std::collections::VecDeque;
+
+/// Add a movement event to the front of the history (newest first)
+/// Events beyond `capacity` are dropped, oldest first.
+pub fn push_event<T>(events: &mut VecDeque<T>, event: T, capacity: usize) {
+    events.push_front(event);
+    events.truncate(capacity);
+}
+
+/// Whether an event at `timestamp` comes less than `min_gap_secs` after the `newest` one
+/// Both are epoch seconds. A jittery sensor may send several triggers within a second, those
+/// would fill the history with the same time. A clock step backwards counts as a gap as well.
+pub fn is_bounce(newest: Option<i64>, timestamp: i64, min_gap_secs: u64) -> bool {
+    newest.is_some_and(|newest| timestamp.abs_diff(newest) < min_gap_secs)
+}
+
+/// Grid cell `(row, column)` of the event at `index`, the columns are filled left to right
+pub fn grid_cell(index: usize, columns: usize) -> (usize, usize) {
+    let columns = columns.max(1);
+    (index / columns, index % columns)
+}
+
+/// Text baseline of the event at `index` in a grid of `columns` inside `area`
+/// Each column is inset by 10 px on the left, the whole grid by 20 px in width.
+/// Only depends on the index, so a partially filled last row lines up with the full ones.
+pub fn event_position(index: usize, columns: usize, area: Rectangle, row_height: i32) -> Point {
+    let (row, column) = grid_cell(index, columns);
+    let column_width = (area.size.width as i32 - 20) / columns.max(1) as i32;
+
+    Point::new(
+        area.top_left.x + 10 + column as i32 * column_width,
+        // Baseline of the row, the glyphs extend upwards from there
+        area.top_left.y + (row as i32 + 1) * row_height - 2,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::prelude::Size;
+
+    fn fill(capacity: usize, count: usize) -> VecDeque<String> {
+        let mut events = VecDeque::new();
+        for i in 0..count {
+            push_event(&mut events, format!("{:02}", i), capacity);
+        }
+        events
+    }
+
+    #[test]
+    fn test_push_event_trims_to_capacity() {
+        // Default capacity: the six newest events are kept
+        let events = fill(6, 10);
+        assert_eq!(events.len(), 6);
+        assert_eq!(events.front().unwrap(), "09");
+        assert_eq!(events.back().unwrap(), "04");
+
+        let events = fill(1, 3);
+        assert_eq!(events, VecDeque::from(vec!["02".to_string()]));
+
+        let events = fill(3, 3);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events.back().unwrap(), "00");
+
+        // Zero capacity keeps nothing
+        assert!(fill(0, 5).is_empty());
+    }
+
+    #[test]
+    fn test_is_bounce() {
+        // Rapid triggers of a jittery sensor: only the first and the one 2s later are kept
+        let mut events = VecDeque::new();
+        for timestamp in [1000, 1000, 1001, 1002, 1002, 1003] {
+            if !is_bounce(events.front().copied(), timestamp, 2) {
+                push_event(&mut events, timestamp, 6);
+            }
+        }
+        assert_eq!(events, VecDeque::from(vec![1002, 1000]));
+
+        assert!(!is_bounce(None, 1000, 2));
+        // A gap of 0 keeps every event
+        assert!(!is_bounce(Some(1000), 1000, 0));
+        assert!(is_bounce(Some(1000), 999, 2));
+    }
+
+    /// Event area of the portrait layout: two columns below the forecast strip
+    fn portrait_area() -> Rectangle {
+        Rectangle::new(Point::new(0, 232), Size::new(240, 88))
+    }
+
+    fn positions(count: usize) -> Vec<Point> {
+        (0..count)
+            .map(|i| event_position(i, 2, portrait_area(), 20))
+            .collect()
+    }
+
+    #[test]
+    fn test_grid_cell() {
+        assert_eq!(grid_cell(0, 2), (0, 0));
+        assert_eq!(grid_cell(1, 2), (0, 1));
+        assert_eq!(grid_cell(4, 2), (2, 0));
+        // A single column stacks all events
+        assert_eq!(grid_cell(3, 1), (3, 0));
+        // Zero columns are treated as one instead of dividing by zero
+        assert_eq!(grid_cell(3, 0), (3, 0));
+    }
+
+    #[test]
+    fn test_event_positions() {
+        assert_eq!(positions(1), vec![Point::new(10, 250)]);
+
+        // Odd count: the last event starts a new row in the left column
+        assert_eq!(
+            positions(5),
+            vec![
+                Point::new(10, 250),
+                Point::new(120, 250),
+                Point::new(10, 270),
+                Point::new(120, 270),
+                Point::new(10, 290),
+            ]
+        );
+
+        // The sixth event fills the right column of the last row
+        let six = positions(6);
+        assert_eq!(six[..5], positions(5)[..]);
+        assert_eq!(six[5], Point::new(120, 290));
+
+        // Landscape: a single column right of the weather text
+        let area = Rectangle::new(Point::new(170, 115), Size::new(150, 70));
+        assert_eq!(event_position(2, 1, area, 20), Point::new(180, 173));
+    }
+}
@@ -0,0 +1,91 @@
+// mqtt_session.rs
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// MQTT connection state shared between the event thread and the main loop
+///
+/// The event thread reports the connection events, the main loop picks up every new
+/// connection and (re)subscribes. The client is not used from the event thread itself,
+/// publishing or subscribing there could deadlock while an event is still borrowed.
+pub struct MqttSession {
+    /// Set on every `Connected` event until the main loop has taken it
+    connect_pending: AtomicBool,
+    /// Set once the broker confirmed a subscription of the current connection
+    subscribed: AtomicBool,
+}
+
+impl MqttSession {
+    pub const fn new() -> Self {
+        Self {
+            connect_pending: AtomicBool::new(false),
+            subscribed: AtomicBool::new(false),
+        }
+    }
+
+    /// A (re)connect: with a clean session the broker has dropped all subscriptions
+    pub fn on_connected(&self) {
+        self.subscribed.store(false, Ordering::SeqCst);
+        self.connect_pending.store(true, Ordering::SeqCst);
+    }
+
+    /// The connection was lost, the client reconnects on its own
+    pub fn on_disconnected(&self) {
+        self.subscribed.store(false, Ordering::SeqCst);
+    }
+
+    /// The broker confirmed a subscription
+    pub fn on_subscribed(&self) {
+        self.subscribed.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true once per connection; the caller then subscribes and sends the
+    /// per-connection messages (availability, discovery)
+    pub fn take_new_connection(&self) -> bool {
+        self.connect_pending.swap(false, Ordering::SeqCst)
+    }
+
+    /// Whether received messages belong to a confirmed subscription
+    pub fn is_subscribed(&self) -> bool {
+        self.subscribed.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resubscribe_after_reconnect() {
+        let session = MqttSession::new();
+        assert!(!session.take_new_connection());
+
+        // First connection: the main loop subscribes exactly once
+        session.on_connected();
+        assert!(!session.is_subscribed());
+        assert!(session.take_new_connection());
+        assert!(!session.take_new_connection());
+        session.on_subscribed();
+        assert!(session.is_subscribed());
+
+        // Simulated broker restart: the subscription is gone until renewed
+        session.on_disconnected();
+        assert!(!session.is_subscribed());
+        assert!(!session.take_new_connection());
+
+        session.on_connected();
+        assert!(session.take_new_connection());
+        assert!(!session.is_subscribed());
+        session.on_subscribed();
+        assert!(session.is_subscribed());
+    }
+
+    #[test]
+    fn test_connect_while_main_loop_is_busy() {
+        // Two reconnects before the main loop gets to it still mean one (re)subscribe
+        let session = MqttSession::new();
+        session.on_connected();
+        session.on_disconnected();
+        session.on_connected();
+        assert!(session.take_new_connection());
+        assert!(!session.take_new_connection());
+    }
+}
@@ -0,0 +1,45 @@
+// mutex_utils.rs
+use log::*;
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks `mutex`, recovering the data if another thread panicked while holding it
+///
+/// The shared values are replaced as a whole or changed in single steps, so the data
+/// of a poisoned lock is still consistent. Keeping the main loop running is better
+/// than returning an error that ends it. The poison is cleared, so it is only reported once.
+pub fn lock_recovering<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        warn!("Recovering the data of a poisoned mutex");
+        mutex.clear_poison();
+        poisoned.into_inner()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_lock_recovering() {
+        let mutex = Mutex::new(1);
+        *lock_recovering(&mutex) += 1;
+        assert_eq!(*lock_recovering(&mutex), 2);
+    }
+
+    #[test]
+    fn test_lock_recovering_poisoned() {
+        let mutex = Arc::new(Mutex::new(vec![1, 2]));
+        let poisoner = Arc::clone(&mutex);
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the mutex");
+        })
+        .join();
+
+        assert!(mutex.is_poisoned());
+        lock_recovering(&mutex).push(3);
+        assert!(!mutex.is_poisoned());
+        assert_eq!(*lock_recovering(&mutex), vec![1, 2, 3]);
+    }
+}
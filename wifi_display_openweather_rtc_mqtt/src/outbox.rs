@@ -0,0 +1,129 @@
+// outbox.rs
+use std::collections::VecDeque;
+
+/// A message waiting to be published
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboxMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    /// QoS level 0, 1 or 2
+    pub qos: u8,
+    pub retain: bool,
+}
+
+/// Bounded queue of outgoing MQTT messages, kept in RAM
+///
+/// Messages are published in the order they were queued. A failed publish (e.g. during a
+/// reconnect) keeps the message and the ones after it for the next attempt, so a retained
+/// value is never overwritten by an older one. When the queue is full, the oldest message
+/// is dropped.
+pub struct Outbox {
+    messages: VecDeque<OutboxMessage>,
+    capacity: usize,
+}
+
+impl Outbox {
+    /// A capacity of 0 is treated as 1
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Queues `message`, returns the oldest message if it had to make room
+    pub fn push(&mut self, message: OutboxMessage) -> Option<OutboxMessage> {
+        let dropped = if self.messages.len() >= self.capacity {
+            self.messages.pop_front()
+        } else {
+            None
+        };
+        self.messages.push_back(message);
+        dropped
+    }
+
+    /// Number of queued messages
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Publishes the queued messages in order until `publish` fails
+    /// The failed message stays at the front of the queue.
+    pub fn flush<E>(
+        &mut self,
+        mut publish: impl FnMut(&OutboxMessage) -> Result<(), E>,
+    ) -> Result<(), E> {
+        while let Some(message) = self.messages.front() {
+            publish(message)?;
+            self.messages.pop_front();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(topic: &str) -> OutboxMessage {
+        OutboxMessage {
+            topic: topic.to_string(),
+            payload: b"1".to_vec(),
+            qos: 1,
+            retain: true,
+        }
+    }
+
+    #[test]
+    fn test_drops_oldest_when_full() {
+        let mut outbox = Outbox::new(2);
+        assert_eq!(outbox.push(message("a")), None);
+        assert_eq!(outbox.push(message("b")), None);
+        assert_eq!(outbox.push(message("c")), Some(message("a")));
+        assert_eq!(outbox.len(), 2);
+
+        let mut sent = Vec::new();
+        outbox
+            .flush(|message| {
+                sent.push(message.topic.clone());
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+        assert_eq!(sent, ["b", "c"]);
+        assert!(outbox.is_empty());
+    }
+
+    #[test]
+    fn test_failed_publish_is_retried_in_order() {
+        let mut outbox = Outbox::new(8);
+        for topic in ["a", "b", "c"] {
+            outbox.push(message(topic));
+        }
+
+        // The broker goes away after the first message
+        let mut sent = Vec::new();
+        let result = outbox.flush(|message| {
+            if !sent.is_empty() {
+                return Err("disconnected");
+            }
+            sent.push(message.topic.clone());
+            Ok(())
+        });
+        assert_eq!(result, Err("disconnected"));
+        assert_eq!(outbox.len(), 2);
+
+        // After the reconnect the rest follows
+        outbox
+            .flush(|message| {
+                sent.push(message.topic.clone());
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+        assert_eq!(sent, ["a", "b", "c"]);
+        assert!(outbox.is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+// power_save.rs
+
+/// What the main loop has to do with the display after an `update`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerAction {
+    /// Keep the current state
+    Keep,
+    /// Send the panel to sleep and stop rendering
+    Sleep,
+    /// Wake the panel up and render again
+    Wake,
+}
+
+/// Puts the display to sleep after a quiet period and wakes it on the next activity
+///
+/// Activity is anything worth looking at: a new movement event or changed weather data.
+/// The times are seconds of a monotonic clock, e.g. the uptime.
+pub struct PowerSave {
+    /// Quiet time before sleeping, 0 disables power saving
+    timeout_secs: u64,
+    last_activity_secs: u64,
+    asleep: bool,
+}
+
+impl PowerSave {
+    /// `timeout_mins` of 0 keeps the display on
+    pub fn new(timeout_mins: u64, now_secs: u64) -> Self {
+        Self {
+            timeout_secs: timeout_mins * 60,
+            last_activity_secs: now_secs,
+            asleep: false,
+        }
+    }
+
+    /// Called on every tick with whether there was activity since the previous tick
+    pub fn update(&mut self, activity: bool, now_secs: u64) -> PowerAction {
+        if activity {
+            self.last_activity_secs = now_secs;
+            if self.asleep {
+                self.asleep = false;
+                return PowerAction::Wake;
+            }
+        } else if !self.asleep
+            && self.timeout_secs > 0
+            && now_secs.saturating_sub(self.last_activity_secs) >= self.timeout_secs
+        {
+            self.asleep = true;
+            return PowerAction::Sleep;
+        }
+        PowerAction::Keep
+    }
+
+    /// Whether rendering is paused
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_and_wake() {
+        let mut power = PowerSave::new(5, 1000);
+        assert_eq!(power.update(false, 1299), PowerAction::Keep);
+        assert_eq!(power.update(false, 1300), PowerAction::Sleep);
+        assert!(power.is_asleep());
+        assert_eq!(power.update(false, 5000), PowerAction::Keep);
+
+        // The next activity wakes the display and restarts the quiet period
+        assert_eq!(power.update(true, 5001), PowerAction::Wake);
+        assert!(!power.is_asleep());
+        assert_eq!(power.update(true, 5002), PowerAction::Keep);
+        assert_eq!(power.update(false, 5301), PowerAction::Keep);
+        assert_eq!(power.update(false, 5302), PowerAction::Sleep);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut power = PowerSave::new(0, 0);
+        assert_eq!(power.update(false, u64::MAX), PowerAction::Keep);
+        assert!(!power.is_asleep());
+    }
+}
@@ -0,0 +1,271 @@
+// provider.rs
+use crate::weather::{is_transient_error, Clouds, Main, Sys, Weather, WeatherResponse, Wind};
+use log::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// A source of the current weather
+///
+/// The display and MQTT code only use the neutral `WeatherResponse`, so a new source
+/// only needs an implementation of this trait.
+pub trait WeatherProvider {
+    /// Fetches the current weather, blocking until the response arrived
+    fn fetch(&self) -> anyhow::Result<WeatherResponse>;
+}
+
+/// Fetches the weather from `provider`, retrying transient failures
+///
+/// `delay` is called between two attempts, never after the last one.
+///
+/// # Returns
+/// * `Ok(WeatherResponse)` - The weather data
+/// * `Err` - The last error once `max_attempts` failed, or the first non-transient error
+pub fn fetch_retrying(
+    provider: &dyn WeatherProvider,
+    max_attempts: u32,
+    mut delay: impl FnMut(),
+) -> anyhow::Result<WeatherResponse> {
+    let mut attempt = 1;
+    loop {
+        match provider.fetch() {
+            Ok(weather) => return Ok(weather),
+            Err(e) if attempt < max_attempts && is_transient_error(&e) => {
+                warn!(
+                    "Weather fetch attempt {}/{} failed, retrying: {}",
+                    attempt, max_attempts, e
+                );
+                delay();
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Where the values of a `WeatherResponse` are found in the JSON of another provider
+/// Every field is a JSON pointer (RFC 6901), e.g. "/current/temperature". Only the
+/// temperature is required; the values are expected in the configured unit system.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldMapping {
+    pub temp: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// An OpenWeatherMap icon code such as "10d", other values show the unknown symbol
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Relative humidity in percent
+    #[serde(default)]
+    pub humidity: Option<String>,
+    #[serde(default)]
+    pub wind_speed: Option<String>,
+    /// Cloud cover in percent
+    #[serde(default)]
+    pub clouds: Option<String>,
+    /// Location name, the configured name if not mapped
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Time of the measurement (UTC seconds)
+    #[serde(default)]
+    pub dt: Option<String>,
+}
+
+impl FieldMapping {
+    /// The configured pointers, for validation
+    pub fn pointers(&self) -> impl Iterator<Item = &str> {
+        [
+            Some(&self.temp),
+            self.description.as_ref(),
+            self.icon.as_ref(),
+            self.humidity.as_ref(),
+            self.wind_speed.as_ref(),
+            self.clouds.as_ref(),
+            self.name.as_ref(),
+            self.dt.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+    }
+
+    /// Builds a `WeatherResponse` from the JSON `body`
+    ///
+    /// A missing optional value is left out, like in a station response without wind.
+    /// Numbers may also be sent as strings.
+    ///
+    /// # Returns
+    /// * `Ok(WeatherResponse)` - The weather data, named `default_name` unless `name` is mapped
+    /// * `Err` - Invalid JSON, no temperature, or a value of the wrong type
+    pub fn parse(&self, body: &str, default_name: &str) -> anyhow::Result<WeatherResponse> {
+        let json: Value = serde_json::from_str(body)?;
+        let number = |pointer: &String| -> anyhow::Result<Option<f64>> {
+            let Some(value) = json.pointer(pointer) else {
+                return Ok(None);
+            };
+            value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .map(Some)
+                .ok_or_else(|| anyhow::anyhow!("Value at {} is not a number: {}", pointer, value))
+        };
+        let text = |pointer: &Option<String>| -> anyhow::Result<Option<String>> {
+            let Some(value) = pointer.as_ref().and_then(|pointer| json.pointer(pointer)) else {
+                return Ok(None);
+            };
+            match value {
+                Value::String(text) => Ok(Some(text.clone())),
+                Value::Number(number) => Ok(Some(number.to_string())),
+                _ => anyhow::bail!("Value at {:?} is not a text: {}", pointer, value),
+            }
+        };
+        let optional = |pointer: &Option<String>| match pointer {
+            Some(pointer) => number(pointer),
+            None => Ok(None),
+        };
+
+        let temp = number(&self.temp)?
+            .ok_or_else(|| anyhow::anyhow!("No temperature at {}", self.temp))?;
+        Ok(WeatherResponse {
+            weather: vec![Weather {
+                description: text(&self.description)?.unwrap_or_default(),
+                icon: text(&self.icon)?.unwrap_or_default(),
+            }],
+            main: Main {
+                temp: temp as f32,
+                humidity: optional(&self.humidity)?.map(|humidity| humidity.round() as i32),
+            },
+            wind: optional(&self.wind_speed)?.map(|speed| Wind {
+                speed: speed as f32,
+            }),
+            clouds: optional(&self.clouds)?.map(|all| Clouds {
+                all: all.round() as i32,
+            }),
+            name: text(&self.name)?.unwrap_or_else(|| default_name.to_string()),
+            dt: optional(&self.dt)?.map_or(0, |dt| dt as i64),
+            sys: Sys::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weather::OwmError;
+    use std::cell::Cell;
+
+    /// Answers with the given OWM error codes first (0 for a timeout), then with weather
+    struct FlakyProvider {
+        failures: Vec<u16>,
+        calls: Cell<usize>,
+    }
+
+    impl FlakyProvider {
+        fn new(failures: &[u16]) -> Self {
+            Self {
+                failures: failures.to_vec(),
+                calls: Cell::new(0),
+            }
+        }
+    }
+
+    impl WeatherProvider for FlakyProvider {
+        fn fetch(&self) -> anyhow::Result<WeatherResponse> {
+            let call = self.calls.get();
+            self.calls.set(call + 1);
+            match self.failures.get(call) {
+                Some(0) => anyhow::bail!("connection timed out"),
+                Some(&cod) => Err(OwmError {
+                    cod,
+                    message: String::new(),
+                }
+                .into()),
+                None => mapping().parse(r#"{"current": {"temperature": 5}}"#, "Garden"),
+            }
+        }
+    }
+
+    fn mapping() -> FieldMapping {
+        FieldMapping {
+            temp: "/current/temperature".to_string(),
+            description: Some("/current/summary".to_string()),
+            icon: None,
+            humidity: Some("/current/humidity".to_string()),
+            wind_speed: Some("/current/wind/speed".to_string()),
+            clouds: None,
+            name: None,
+            dt: Some("/time".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_fetch_retrying_transient_errors() {
+        let provider = FlakyProvider::new(&[0, 503]);
+        let mut delays = 0;
+        let weather = fetch_retrying(&provider, 3, || delays += 1).unwrap();
+        assert_eq!(weather.main.temp, 5.0);
+        assert_eq!(provider.calls.get(), 3);
+        assert_eq!(delays, 2);
+    }
+
+    #[test]
+    fn test_fetch_retrying_budget() {
+        // The budget runs out before the provider recovers, the last error is returned
+        let provider = FlakyProvider::new(&[0, 0, 502]);
+        let mut delays = 0;
+        let error = fetch_retrying(&provider, 3, || delays += 1).unwrap_err();
+        assert_eq!(error.downcast_ref::<OwmError>().unwrap().cod, 502);
+        assert_eq!(provider.calls.get(), 3);
+        assert_eq!(delays, 2);
+
+        // A single attempt never waits
+        let provider = FlakyProvider::new(&[0]);
+        assert!(fetch_retrying(&provider, 1, || panic!("no delay expected")).is_err());
+        assert_eq!(provider.calls.get(), 1);
+    }
+
+    #[test]
+    fn test_fetch_retrying_stops_on_client_errors() {
+        for cod in [401, 404] {
+            let provider = FlakyProvider::new(&[cod]);
+            let error = fetch_retrying(&provider, 3, || {}).unwrap_err();
+            assert_eq!(error.downcast_ref::<OwmError>().unwrap().cod, cod);
+            assert_eq!(provider.calls.get(), 1);
+        }
+    }
+
+    #[test]
+    fn test_parse_mapped_fields() {
+        let body = r#"{
+            "time": 1728900000,
+            "current": {"temperature": 12.4, "summary": "cloudy", "humidity": "81.6",
+                        "wind": {"speed": 3.5}}
+        }"#;
+        let weather = mapping().parse(body, "Garden").unwrap();
+        assert_eq!(weather.main.temp, 12.4);
+        assert_eq!(weather.main.humidity, Some(82));
+        assert_eq!(weather.wind.map(|wind| wind.speed), Some(3.5));
+        assert!(weather.clouds.is_none());
+        assert_eq!(weather.weather[0].description, "cloudy");
+        assert_eq!(weather.weather[0].icon, "");
+        assert_eq!(weather.name, "Garden");
+        assert_eq!(weather.dt, 1728900000);
+    }
+
+    #[test]
+    fn test_parse_missing_and_invalid_values() {
+        // Optional values may be missing, the temperature may not
+        let weather = mapping()
+            .parse(r#"{"current": {"temperature": -1}}"#, "Garden")
+            .unwrap();
+        assert_eq!(weather.main.temp, -1.0);
+        assert!(weather.wind.is_none());
+        assert_eq!(weather.dt, 0);
+
+        let error = mapping().parse(r#"{"current": {}}"#, "Garden").unwrap_err();
+        assert!(error.to_string().contains("/current/temperature"));
+        let error = mapping()
+            .parse(r#"{"current": {"temperature": "warm"}}"#, "Garden")
+            .unwrap_err();
+        assert!(error.to_string().contains("not a number"));
+        assert!(mapping().parse("<html>", "Garden").is_err());
+    }
+}
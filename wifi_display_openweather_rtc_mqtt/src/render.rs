@@ -0,0 +1,1450 @@
+// render.rs
+use crate::air_quality;
+use crate::alerts;
+use crate::boot::{self, BootStep};
+use crate::dimming;
+use crate::display_mode::DisplayMode;
+use crate::movement;
+use crate::secrets::ThemeConfig;
+use crate::trend::TempTrend;
+use crate::weather_icons::{get_weather_icon, get_weather_icon_rgb};
+use embedded_graphics::{
+    mono_font::{iso_8859_1::FONT_10X20, MonoTextStyle, MonoTextStyleBuilder},
+    pixelcolor::Rgb565,
+    prelude::*,
+    text::Text,
+};
+use log::*;
+use weather_common::icon_bitmap;
+
+/// Display state structure for change detection
+/// Used to minimize screen flicker by only redrawing when data changes
+#[derive(Clone, PartialEq, Debug)]
+pub struct DisplayState {
+    pub time_str: String,
+    pub date_str: String,
+    pub weather_temp: String,
+    pub weather_desc: String,
+    pub weather_icon: String,
+    pub wind_str: String,
+    pub hum_str: String,
+    /// Home shows the full dashboard, away only the date and time
+    pub display_mode: DisplayMode,
+    /// Cloud cover, e.g. "Cl:  75%", padded to a fixed width
+    pub clouds_str: String,
+    pub city_name: String,
+    pub movement_events: Vec<String>,
+    /// Wi-Fi signal strength in bars (0-4), `None` while not connected
+    /// Bucketed, so an RSSI fluctuation within a bar does not count as a change.
+    pub wifi_bars: Option<u8>,
+    /// Whether the sun is up, `None` while unknown
+    pub daytime: Option<bool>,
+    /// Air quality index (1-5), `None` without coordinates or data
+    pub aqi: Option<u8>,
+    /// Temperature trend since the previous fetch, `None` while unknown or stale
+    pub temp_trend: Option<TempTrend>,
+    /// Today's observed min/max temperature, e.g. "8.2/15.6°C"
+    pub temp_range_str: String,
+    /// Time of the measurement at the station, e.g. "obs 14:32"
+    pub observed_str: String,
+    /// Weather fetches since boot, e.g. "ok:123 err:4"
+    pub stats_str: String,
+    pub forecast: Vec<ForecastCell>,
+    /// Status line: time since boot, e.g. "up 3h12m"
+    pub uptime_str: String,
+    /// Status line: minutes since the last successful weather fetch
+    pub last_fetch_age_str: String,
+    /// Events of the active weather alerts, shown in a banner instead of the date
+    pub alerts: Vec<String>,
+    /// IP address of the station interface, e.g. "192.168.1.23", empty if not shown
+    pub ip_str: String,
+    pub text_color: Rgb565,
+    /// Color of the temperature, `text_color` unless `theme.temp_colors` is set
+    pub temp_color: Rgb565,
+    /// Theme background, used to clear the areas that are redrawn
+    pub background_color: Rgb565,
+}
+
+/// One day of the forecast strip, already formatted for display
+#[derive(Clone, PartialEq, Debug)]
+pub struct ForecastCell {
+    pub day: String,
+    pub temp: String,
+    pub icon: String,
+}
+
+impl DisplayState {
+    /// Create a new empty display state in the colors of the theme
+    pub fn new(theme: &ThemeConfig) -> Self {
+        Self {
+            time_str: String::new(),
+            date_str: String::new(),
+            weather_temp: String::new(),
+            weather_desc: String::new(),
+            weather_icon: String::new(),
+            wind_str: String::new(),
+            hum_str: String::new(),
+            clouds_str: String::new(),
+            display_mode: DisplayMode::Home,
+            city_name: String::new(),
+            movement_events: Vec::new(),
+            wifi_bars: None,
+            daytime: None,
+            aqi: None,
+            temp_trend: None,
+            temp_range_str: String::new(),
+            observed_str: String::new(),
+            stats_str: String::new(),
+            forecast: Vec::new(),
+            uptime_str: String::new(),
+            last_fetch_age_str: String::new(),
+            alerts: Vec::new(),
+            ip_str: String::new(),
+            text_color: theme.fg,
+            temp_color: theme.fg,
+            background_color: theme.bg,
+        }
+    }
+}
+
+/// Suffix of the temperature line, marks weather data older than `display.stale_after_mins`
+/// Both variants have the same width, so the marker is erased when fresh data arrives.
+pub fn stale_marker(stale: bool) -> &'static str {
+    if stale {
+        " *"
+    } else {
+        "  "
+    }
+}
+
+/// Return a copy of the state with its text colors scaled toward black
+/// A `factor` of 1.0 keeps the colors, 0.0 turns them black.
+pub fn dim_colors(state: &DisplayState, factor: f32) -> DisplayState {
+    DisplayState {
+        text_color: dimming::scale_color(state.text_color, factor),
+        temp_color: dimming::scale_color(state.temp_color, factor),
+        ..state.clone()
+    }
+}
+
+/// Map OpenWeatherMap icon codes to emoji symbols
+/// Used as fallback when bitmap icons are not available
+fn get_weather_symbol(icon_code: &str) -> &'static str {
+    match icon_code {
+        "01d" => "☀",         // Clear sky day
+        "01n" => "🌙",        // Clear sky night
+        "02d" => "🌤",         // Few clouds day
+        "02n" => "☁",         // Few clouds night
+        "03d" | "03n" => "☁", // Scattered clouds
+        "04d" | "04n" => "☁", // Broken clouds
+        "09d" | "09n" => "🌧", // Shower rain
+        "10d" => "🌦",         // Rain day
+        "10n" => "🌧",         // Rain night
+        "11d" | "11n" => "⛈", // Thunderstorm
+        "13d" | "13n" => "❄", // Snow
+        "50d" | "50n" => "🌫", // Mist
+        _ => "❓",            // Unknown
+    }
+}
+
+/// Determine icon color based on weather condition
+fn get_weather_icon_color(icon_code: &str) -> Rgb565 {
+    match icon_code.get(..2).unwrap_or_default() {
+        "01" | "02" | "11" => Rgb565::YELLOW,   // Sun/Thunder
+        "09" | "10" => Rgb565::BLUE,            // Rain
+        "13" => Rgb565::WHITE,                  // Snow
+        "03" | "04" | "50" => Rgb565::CSS_GRAY, // Clouds/Mist
+        _ => Rgb565::WHITE,
+    }
+}
+
+/// Map an RSSI value to a number of signal bars (0-4)
+pub fn rssi_to_bars(rssi: i8) -> u8 {
+    match rssi {
+        r if r >= -55 => 4,
+        r if r >= -66 => 3,
+        r if r >= -77 => 2,
+        r if r >= -88 => 1,
+        _ => 0,
+    }
+}
+
+/// Height of one row of movement events (FONT_10X20)
+const EVENT_ROW_HEIGHT: i32 = 20;
+
+/// Height of the status line at the bottom edge (FONT_6X10)
+const STATUS_LINE_HEIGHT: i32 = 10;
+
+/// Screen regions of the display content
+/// Text positions are baselines (FONT_10X20), the other points are top-left corners.
+/// `default_240x320` is the reference, `new` adapts it to the active display size.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    width: u32,
+    /// Date and time zone, e.g. "25.10.2024 CEST"
+    date_pos: Point,
+    /// Weather alert banner, in place of the date
+    alert_area: embedded_graphics::primitives::Rectangle,
+    /// Whether the time zone fits behind the date, or the weekday in front of it
+    pub show_time_zone: bool,
+    /// Clock
+    time_pos: Point,
+    /// City name and country
+    city_pos: Point,
+    /// Temperature
+    temp_pos: Point,
+    /// Top-left corner of the trend arrow right of the temperature, `None` if there is no room
+    trend_origin: Option<Point>,
+    /// Top-left corner of today's min/max temperature (FONT_6X10), `None` if there is no room
+    range_pos: Option<Point>,
+    /// Top-left corner of the observation time (FONT_6X10), `None` if there is no room
+    observed_pos: Option<Point>,
+    /// Top-left corner of the fetch counters (FONT_6X10), `None` if there is no room
+    stats_pos: Option<Point>,
+    /// Weather description
+    desc_pos: Point,
+    /// Wind speed
+    wind_pos: Point,
+    /// Humidity
+    hum_pos: Point,
+    /// Cloud cover, `None` if there is no room
+    clouds_pos: Option<Point>,
+    /// Top-left corner of the current weather icon
+    icon_origin: Point,
+    /// Left edge of the Wi-Fi signal bars
+    signal_bars_x: i32,
+    /// Top-left corner of the sun/moon glyph, left of the signal bars
+    day_night_origin: Point,
+    /// Top-left corner of the air quality badge, right of the time
+    aqi_origin: Point,
+    /// Top edge of the forecast strip, `None` if there is no room for it
+    forecast_top: Option<i32>,
+    /// Area of the movement event list
+    event_area: embedded_graphics::primitives::Rectangle,
+    /// Number of columns in the movement event list
+    event_columns: i32,
+    /// Top edge of the status line (uptime, age of the weather data)
+    status_top: i32,
+    /// Top edge of the IP address line above the status line, `None` if there is no room
+    ip_top: Option<i32>,
+}
+
+impl Layout {
+    /// The reference layout: a 240x320 panel in portrait orientation
+    fn default_240x320() -> Self {
+        use embedded_graphics::primitives::Rectangle;
+
+        let status_top = 320 - STATUS_LINE_HEIGHT;
+        let ip_top = status_top - STATUS_LINE_HEIGHT;
+        let event_top = 232;
+
+        Self {
+            width: 240,
+            date_pos: Point::new(10, 20),
+            // The date row, up to the sun/moon glyph
+            alert_area: Rectangle::new(Point::new(0, 5), Size::new(190, 20)),
+            show_time_zone: true,
+            time_pos: Point::new(10, 40),
+            city_pos: Point::new(10, 60),
+            temp_pos: Point::new(10, 90),
+            // Right of the longest temperature, e.g. "-10.25°C *"
+            trend_origin: Some(Point::new(112, 76)),
+            // Between the character cells of the temperature (y 75..=94) and the description
+            range_pos: Some(Point::new(10, 95)),
+            // Right of the min/max, left of the icon
+            observed_pos: Some(Point::new(100, 95)),
+            // Right of the wind speed, below the icon
+            stats_pos: Some(Point::new(150, 142)),
+            desc_pos: Point::new(10, 120),
+            wind_pos: Point::new(10, 150),
+            hum_pos: Point::new(10, 180),
+            // Right of the humidity, above the forecast strip
+            clouds_pos: Some(Point::new(90, 180)),
+            icon_origin: Point::new(160, 70),
+            signal_bars_x: 212,
+            day_night_origin: Point::new(192, 4),
+            aqi_origin: Point::new(160, 26),
+            forecast_top: Some(188),
+            // Two columns below the forecast strip, down to the IP address line
+            event_area: Rectangle::new(
+                Point::new(0, event_top),
+                Size::new(240, (ip_top - event_top) as u32),
+            ),
+            event_columns: 2,
+            status_top,
+            ip_top: Some(ip_top),
+        }
+    }
+
+    /// Select the layout for a display of `width` x `height` pixels (after rotation)
+    /// The small 135x240 panels get their own compact layout, larger ones the adapted reference.
+    pub fn for_size(width: u32, height: u32) -> Self {
+        if width.min(height) < 240 {
+            Self::compact(width, height)
+        } else {
+            Self::new(width, height)
+        }
+    }
+
+    /// Layout for the small 135x240 panels (e.g. TTGO T-Display) in either orientation
+    /// There is no room for the forecast strip. Portrait shows the latest movement event
+    /// above the status line, landscape none.
+    fn compact(width: u32, height: u32) -> Self {
+        use embedded_graphics::primitives::Rectangle;
+
+        let w = width as i32;
+        let h = height as i32;
+        let status_top = h - STATUS_LINE_HEIGHT;
+
+        if h > w {
+            // One text column, the icon right of the temperature and the AQI below the humidity
+            let event_top = 204;
+            Self {
+                width,
+                time_pos: Point::new(4, 20),
+                date_pos: Point::new(4, 40),
+                alert_area: Rectangle::new(Point::new(0, 25), Size::new(width, 20)),
+                show_time_zone: false,
+                city_pos: Point::new(4, 62),
+                temp_pos: Point::new(4, 90),
+                trend_origin: None,
+                range_pos: Some(Point::new(4, 96)),
+                observed_pos: None,
+                stats_pos: None,
+                desc_pos: Point::new(4, 134),
+                wind_pos: Point::new(4, 156),
+                hum_pos: Point::new(4, 178),
+                clouds_pos: None,
+                icon_origin: Point::new(w - 45, 70),
+                signal_bars_x: w - 28,
+                day_night_origin: Point::new(w - 48, 4),
+                aqi_origin: Point::new(4, 186),
+                forecast_top: None,
+                event_area: Rectangle::new(
+                    Point::new(0, event_top),
+                    Size::new(width, (status_top - event_top) as u32),
+                ),
+                event_columns: 1,
+                status_top,
+                ip_top: None,
+            }
+        } else {
+            // The reference rows down to the wind, with the humidity next to the wind
+            Self {
+                width,
+                date_pos: Point::new(4, 20),
+                alert_area: Rectangle::new(Point::new(0, 5), Size::new(width - 50, 20)),
+                show_time_zone: true,
+                time_pos: Point::new(4, 40),
+                city_pos: Point::new(4, 60),
+                temp_pos: Point::new(4, 80),
+                trend_origin: Some(Point::new(94, 66)),
+                range_pos: Some(Point::new(110, 70)),
+                observed_pos: None,
+                stats_pos: None,
+                desc_pos: Point::new(4, 100),
+                wind_pos: Point::new(4, 120),
+                hum_pos: Point::new(124, 120),
+                clouds_pos: None,
+                icon_origin: Point::new(w - 44, 44),
+                signal_bars_x: w - 28,
+                day_night_origin: Point::new(w - 48, 4),
+                aqi_origin: Point::new(w - 80, 26),
+                forecast_top: None,
+                event_area: Rectangle::new(Point::new(0, status_top), Size::zero()),
+                event_columns: 1,
+                status_top,
+                ip_top: None,
+            }
+        }
+    }
+
+    /// Adapt the reference layout to a display of `width` x `height` pixels
+    /// Text stays left-aligned in the same rows; the elements at the right edge follow the width
+    /// and the status line the height, so that the content fits in both orientations.
+    fn new(width: u32, height: u32) -> Self {
+        use embedded_graphics::primitives::Rectangle;
+
+        let reference = Self::default_240x320();
+        let w = width as i32;
+        let h = height as i32;
+        let status_top = h - STATUS_LINE_HEIGHT;
+
+        // Portrait: two columns below the forecast strip, down to the IP address line.
+        // Landscape: a single column right of the weather text, below the icon.
+        let ip_top = Some(status_top - STATUS_LINE_HEIGHT).filter(|_| h > w);
+        let (event_area, event_columns) = if let Some(ip_top) = ip_top {
+            let top = reference.event_area.top_left.y;
+            (
+                Rectangle::new(Point::new(0, top), Size::new(width, (ip_top - top) as u32)),
+                reference.event_columns,
+            )
+        } else {
+            let left = w - 150;
+            (Rectangle::new(Point::new(left, 115), Size::new(150, 70)), 1)
+        };
+
+        // Offset of the right-aligned elements relative to the reference width
+        let dx = w - reference.width as i32;
+        Self {
+            width,
+            // In landscape the movement events take the space right of the wind speed
+            stats_pos: reference.stats_pos.filter(|_| h > w),
+            icon_origin: reference.icon_origin + Point::new(dx, 0),
+            signal_bars_x: reference.signal_bars_x + dx,
+            day_night_origin: reference.day_night_origin + Point::new(dx, 0),
+            aqi_origin: reference.aqi_origin + Point::new(dx, 0),
+            alert_area: Rectangle::new(
+                reference.alert_area.top_left,
+                reference.alert_area.size + Size::new(dx as u32, 0),
+            ),
+            event_area,
+            event_columns,
+            status_top,
+            ip_top,
+            ..reference
+        }
+    }
+
+    /// Number of event rows needed for `history_len` events,
+    /// limited to the rows that fit into the event area
+    fn event_rows(&self, history_len: usize) -> i32 {
+        let max_rows = self.event_area.size.height as i32 / EVENT_ROW_HEIGHT;
+        let needed = history_len.div_ceil(self.event_columns as usize) as i32;
+        needed.min(max_rows)
+    }
+}
+
+/// Renders the entire display content.
+/// It only redraws the screen if the `DisplayState` has changed to prevent flickering.
+pub fn render_display(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    layout: &Layout,
+    current_state: &DisplayState,
+    previous_state: &DisplayState,
+    text_style: &MonoTextStyle<Rgb565>,
+    symbol_style: &MonoTextStyle<Rgb565>,
+    movement_history_len: usize,
+) {
+    // The display is not cleared every frame to reduce flickering.
+    // Instead, specific areas are overwritten.
+
+    // Apply the (possibly dimmed) text color of this state
+    let mut text_style = *text_style;
+    text_style.text_color = Some(current_state.text_color);
+    let text_style = &text_style;
+
+    // === Render Date and Time ===
+    // An active weather alert takes the place of the date
+    if current_state.alerts.is_empty() {
+        if !previous_state.alerts.is_empty() {
+            let _ = layout
+                .alert_area
+                .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
+                    current_state.background_color,
+                ))
+                .draw(display);
+        }
+        let _ = Text::new(&current_state.date_str, layout.date_pos, *text_style).draw(display);
+    } else if current_state.alerts != previous_state.alerts {
+        render_alert_banner(display, layout.alert_area, &current_state.alerts);
+    }
+    render_time(display, layout, current_state, text_style);
+
+    // The away mode is a plain clock
+    if current_state.display_mode == DisplayMode::Away {
+        return;
+    }
+
+    // === Render Wi-Fi Signal Strength ===
+    if current_state.wifi_bars != previous_state.wifi_bars {
+        render_signal_bars(
+            display,
+            layout.signal_bars_x,
+            current_state.wifi_bars,
+            current_state,
+        );
+    }
+
+    // === Render Day/Night Glyph ===
+    if current_state.daytime != previous_state.daytime {
+        render_day_night_glyph(
+            display,
+            layout.day_night_origin,
+            current_state.daytime,
+            current_state.background_color,
+        );
+    }
+
+    // === Render Air Quality Badge ===
+    if current_state.aqi != previous_state.aqi {
+        render_aqi_badge(
+            display,
+            layout.aqi_origin,
+            current_state.aqi,
+            current_state.background_color,
+        );
+    }
+
+    // === Render Daily Min/Max ===
+    if let Some(position) = layout.range_pos {
+        if current_state.temp_range_str != previous_state.temp_range_str
+            || current_state.text_color != previous_state.text_color
+        {
+            render_temp_range(display, position, current_state);
+        }
+    }
+
+    // === Render Observation Time ===
+    if let Some(position) = layout.observed_pos {
+        if current_state.observed_str != previous_state.observed_str
+            || current_state.text_color != previous_state.text_color
+        {
+            render_observation_time(display, position, current_state);
+        }
+    }
+
+    // === Render Fetch Counters ===
+    if let Some(position) = layout.stats_pos {
+        if current_state.stats_str != previous_state.stats_str
+            || current_state.text_color != previous_state.text_color
+        {
+            render_stats(display, position, current_state);
+        }
+    }
+
+    // === Render Weather Data ===
+    if !current_state.city_name.is_empty() {
+        // City name
+        let _ = Text::new(&current_state.city_name, layout.city_pos, *text_style).draw(display);
+        // Temperature, in its own color
+        let mut temp_style = *text_style;
+        temp_style.text_color = Some(current_state.temp_color);
+        let _ = Text::new(&current_state.weather_temp, layout.temp_pos, temp_style).draw(display);
+        // The trend arrow right of it, after the text, whose background may reach into it
+        if let Some(origin) = layout.trend_origin {
+            render_trend_arrow(display, origin, current_state);
+        }
+        // Description
+        let _ = Text::new(&current_state.weather_desc, layout.desc_pos, *text_style).draw(display);
+        // Wind speed
+        let _ = Text::new(&current_state.wind_str, layout.wind_pos, *text_style).draw(display);
+        // Humidity
+        let _ = Text::new(&current_state.hum_str, layout.hum_pos, *text_style).draw(display);
+        // Cloud cover
+        if let Some(position) = layout.clouds_pos {
+            let _ = Text::new(&current_state.clouds_str, position, *text_style).draw(display);
+        }
+        // Weather icon
+        render_weather_icon(
+            display,
+            layout.icon_origin,
+            &current_state.weather_icon,
+            symbol_style,
+            current_state.background_color,
+        );
+
+        // Manually clear the event area before drawing.
+        // This draws a background colored rectangle over the entire event area
+        // to ensure a clean erase before new events are drawn.
+        let event_rows = layout.event_rows(movement_history_len);
+        let event_area = embedded_graphics::primitives::Rectangle::new(
+            layout.event_area.top_left, // Start point (slightly above the first text)
+            Size::new(
+                layout.event_area.size.width,
+                (event_rows * EVENT_ROW_HEIGHT) as u32,
+            ), // Area width, all rows
+        )
+        .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
+            current_state.background_color,
+        ));
+        let _ = event_area.draw(display);
+
+        // === Render Movement Events ===
+        render_movement_events(
+            display,
+            layout,
+            &current_state.movement_events,
+            event_rows,
+            text_style,
+        );
+    }
+
+    // === Render Forecast Strip ===
+    // Only redraw when the forecast changes, the icons are drawn pixel by pixel
+    if current_state.forecast != previous_state.forecast {
+        render_forecast_strip(display, layout, current_state);
+    }
+
+    // === Render Status Line ===
+    if current_state.uptime_str != previous_state.uptime_str
+        || current_state.last_fetch_age_str != previous_state.last_fetch_age_str
+        || current_state.text_color != previous_state.text_color
+    {
+        render_status_line(display, layout, current_state);
+    }
+
+    // === Render IP Address ===
+    if let Some(top) = layout.ip_top {
+        if current_state.ip_str != previous_state.ip_str
+            || current_state.text_color != previous_state.text_color
+        {
+            render_ip_line(display, layout, top, current_state);
+        }
+    }
+}
+
+/// Returns true if the states differ only in the seconds of the clock
+/// Then `render_time` is enough, a new minute or any other change needs `render_display`.
+pub fn only_seconds_changed(current_state: &DisplayState, previous_state: &DisplayState) -> bool {
+    fn hours_minutes(time_str: &str) -> Option<&str> {
+        time_str
+            .rsplit_once(':')
+            .map(|(hours_minutes, _)| hours_minutes)
+    }
+    // Without `..`, a new field of the state cannot be left out of the comparison
+    let DisplayState {
+        time_str,
+        date_str,
+        weather_temp,
+        weather_desc,
+        weather_icon,
+        wind_str,
+        hum_str,
+        display_mode,
+        clouds_str,
+        city_name,
+        movement_events,
+        wifi_bars,
+        daytime,
+        aqi,
+        temp_trend,
+        temp_range_str,
+        observed_str,
+        stats_str,
+        forecast,
+        uptime_str,
+        last_fetch_age_str,
+        alerts,
+        ip_str,
+        text_color,
+        temp_color,
+        background_color,
+    } = previous_state;
+    current_state.time_str != *time_str
+        && current_state.time_str.len() == time_str.len()
+        && hours_minutes(&current_state.time_str).is_some()
+        && hours_minutes(&current_state.time_str) == hours_minutes(time_str)
+        && current_state.date_str == *date_str
+        && current_state.weather_temp == *weather_temp
+        && current_state.weather_desc == *weather_desc
+        && current_state.weather_icon == *weather_icon
+        && current_state.wind_str == *wind_str
+        && current_state.hum_str == *hum_str
+        && current_state.display_mode == *display_mode
+        && current_state.clouds_str == *clouds_str
+        && current_state.city_name == *city_name
+        && current_state.movement_events == *movement_events
+        && current_state.wifi_bars == *wifi_bars
+        && current_state.daytime == *daytime
+        && current_state.aqi == *aqi
+        && current_state.temp_trend == *temp_trend
+        && current_state.temp_range_str == *temp_range_str
+        && current_state.observed_str == *observed_str
+        && current_state.stats_str == *stats_str
+        && current_state.forecast == *forecast
+        && current_state.uptime_str == *uptime_str
+        && current_state.last_fetch_age_str == *last_fetch_age_str
+        && current_state.alerts == *alerts
+        && current_state.ip_str == *ip_str
+        && current_state.text_color == *text_color
+        && current_state.temp_color == *temp_color
+        && current_state.background_color == *background_color
+}
+
+/// Draws the time field only
+/// The text style has a background color, so the text overwrites its bounding rectangle.
+/// The seconds keep the width of the time, nothing of the previous time remains.
+pub fn render_time(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    layout: &Layout,
+    state: &DisplayState,
+    text_style: &MonoTextStyle<Rgb565>,
+) {
+    let mut text_style = *text_style;
+    text_style.text_color = Some(state.text_color);
+    let _ = Text::new(&state.time_str, layout.time_pos, text_style).draw(display);
+}
+
+/// Draw the boot splash: firmware name, version and the current initialization step
+/// Only the status line changes between the steps, it is cleared before drawing.
+pub fn draw_boot_status(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    layout: &Layout,
+    theme: &ThemeConfig,
+    step: BootStep,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+    info!("Boot: {}", step.status_text());
+
+    let x = layout.date_pos.x;
+    let title_style = MonoTextStyle::new(&FONT_10X20, theme.fg);
+    let small_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(theme.fg)
+        .background_color(theme.bg)
+        .build();
+
+    let _ = Text::new(boot::FIRMWARE_NAME, Point::new(x, 40), title_style).draw(display);
+    let _ = Text::new(&boot::version_text(), Point::new(x, 60), small_style).draw(display);
+
+    let _ = Rectangle::new(Point::new(0, 80), Size::new(layout.width, 12))
+        .into_styled(PrimitiveStyle::with_fill(theme.bg))
+        .draw(display);
+    let _ = Text::new(&step.status_text(), Point::new(x, 90), small_style).draw(display);
+}
+
+/// Render the weather alert banner: white text on red, the event cut off to fit the area
+fn render_alert_banner(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    area: embedded_graphics::primitives::Rectangle,
+    events: &[String],
+) {
+    use embedded_graphics::primitives::PrimitiveStyle;
+    use embedded_graphics::text::Baseline;
+
+    let _ = area
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+        .draw(display);
+
+    // 4 pixels padding on both sides, FONT_10X20 is 10 pixels wide
+    let max_chars = area.size.width.saturating_sub(8) as usize / 10;
+    let _ = Text::with_baseline(
+        &alerts::banner_text(events, max_chars),
+        area.top_left + Point::new(4, 0),
+        MonoTextStyle::new(&FONT_10X20, Rgb565::WHITE),
+        Baseline::Top,
+    )
+    .draw(display);
+}
+
+/// Size of the air quality badge in pixels
+const AQI_BADGE_SIZE: Size = Size::new(40, 14);
+
+/// Render the air quality index as a colored badge, from green (good) to red (very poor)
+/// `None` clears the badge.
+fn render_aqi_badge(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    origin: Point,
+    aqi: Option<u8>,
+    background: Rgb565,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle, RoundedRectangle};
+    use embedded_graphics::text::Baseline;
+
+    let area = Rectangle::new(origin, AQI_BADGE_SIZE);
+    let _ = area
+        .into_styled(PrimitiveStyle::with_fill(background))
+        .draw(display);
+
+    let Some(aqi) = aqi else {
+        return;
+    };
+
+    let _ = RoundedRectangle::with_equal_corners(area, Size::new(4, 4))
+        .into_styled(PrimitiveStyle::with_fill(air_quality::aqi_color(aqi)))
+        .draw(display);
+
+    // "AQI n" is 30 pixels wide, centered in the badge and cut out in the background color
+    let label_style = MonoTextStyle::new(&FONT_6X10, background);
+    let _ = Text::with_baseline(
+        &format!("AQI {}", aqi),
+        origin + Point::new(5, 2),
+        label_style,
+        Baseline::Top,
+    )
+    .draw(display);
+}
+
+/// Render the IP address in a small line at `top`, cleared first
+/// An empty `ip_str` only clears the line.
+fn render_ip_line(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    layout: &Layout,
+    top: i32,
+    state: &DisplayState,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+    use embedded_graphics::text::Baseline;
+
+    let _ = Rectangle::new(
+        Point::new(0, top),
+        Size::new(layout.width, STATUS_LINE_HEIGHT as u32),
+    )
+    .into_styled(PrimitiveStyle::with_fill(state.background_color))
+    .draw(display);
+
+    if state.ip_str.is_empty() {
+        return;
+    }
+    let small_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(state.text_color)
+        .background_color(state.background_color)
+        .build();
+    let _ = Text::with_baseline(
+        &format!("IP {}", state.ip_str),
+        Point::new(10, top),
+        small_style,
+        Baseline::Top,
+    )
+    .draw(display);
+}
+
+/// Render the small status line at the bottom edge: uptime on the left, age of the weather data
+/// in the middle. The line is cleared first, the texts change their length.
+fn render_status_line(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    layout: &Layout,
+    state: &DisplayState,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+    use embedded_graphics::text::Baseline;
+
+    let _ = Rectangle::new(
+        Point::new(0, layout.status_top),
+        Size::new(layout.width, STATUS_LINE_HEIGHT as u32),
+    )
+    .into_styled(PrimitiveStyle::with_fill(state.background_color))
+    .draw(display);
+
+    let small_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(state.text_color)
+        .background_color(state.background_color)
+        .build();
+
+    let _ = Text::with_baseline(
+        &state.uptime_str,
+        Point::new(10, layout.status_top),
+        small_style,
+        Baseline::Top,
+    )
+    .draw(display);
+    let _ = Text::with_baseline(
+        &state.last_fetch_age_str,
+        Point::new(layout.width as i32 / 2, layout.status_top),
+        small_style,
+        Baseline::Top,
+    )
+    .draw(display);
+}
+
+/// Render the Wi-Fi signal strength as four bars in the top-right corner
+/// `None` (not connected) draws all bars as outlines.
+fn render_signal_bars(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    origin_x: i32,
+    bars: Option<u8>,
+    state: &DisplayState,
+) {
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+    let bar_width = 4;
+    let bar_gap = 2;
+    let bottom_y = 20;
+
+    // Clear the glyph area first
+    let _ = Rectangle::new(Point::new(origin_x, 0), Size::new(24, bottom_y as u32 + 1))
+        .into_styled(PrimitiveStyle::with_fill(state.background_color))
+        .draw(display);
+
+    for i in 0..4u8 {
+        let height = 4 * (i as u32 + 1);
+        let x = origin_x + i as i32 * (bar_width + bar_gap);
+        let bar = Rectangle::new(
+            Point::new(x, bottom_y - height as i32),
+            Size::new(bar_width as u32, height),
+        );
+
+        let style = if bars.is_some_and(|b| i < b) {
+            PrimitiveStyle::with_fill(state.text_color)
+        } else {
+            PrimitiveStyle::with_stroke(Rgb565::CSS_DIM_GRAY, 1)
+        };
+        let _ = bar.into_styled(style).draw(display);
+    }
+}
+
+/// Characters reserved for today's min/max, e.g. "-10.25/-3.25°C"
+const TEMP_RANGE_CHARS: usize = 14;
+
+/// Render today's min/max temperature below the current temperature
+/// The text is padded to a fixed width, so a shorter text overwrites a longer one.
+fn render_temp_range(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    position: Point,
+    state: &DisplayState,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::text::Baseline;
+
+    let small_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(state.text_color)
+        .background_color(state.background_color)
+        .build();
+    let text = format!("{:<width$}", state.temp_range_str, width = TEMP_RANGE_CHARS);
+    let _ = Text::with_baseline(&text, position, small_style, Baseline::Top).draw(display);
+}
+
+/// Characters reserved for the observation time, "obs HH:MM"
+const OBSERVED_CHARS: usize = 9;
+
+/// Render the time the station measured the current weather
+/// The text is padded to a fixed width, so that it is erased when the time is missing.
+fn render_observation_time(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    position: Point,
+    state: &DisplayState,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::text::Baseline;
+
+    let small_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(state.text_color)
+        .background_color(state.background_color)
+        .build();
+    let text = format!("{:<width$}", state.observed_str, width = OBSERVED_CHARS);
+    let _ = Text::with_baseline(&text, position, small_style, Baseline::Top).draw(display);
+}
+
+/// Characters reserved for the fetch counters, e.g. "ok:1234 err:56"
+const STATS_CHARS: usize = 15;
+
+/// Render the counters of successful and failed weather fetches
+/// The text is padded to a fixed width, so a shorter text overwrites a longer one.
+fn render_stats(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    position: Point,
+    state: &DisplayState,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::text::Baseline;
+
+    let small_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(state.text_color)
+        .background_color(state.background_color)
+        .build();
+    let text = format!("{:<width$}", state.stats_str, width = STATS_CHARS);
+    let _ = Text::with_baseline(&text, position, small_style, Baseline::Top).draw(display);
+}
+
+/// Size of the temperature trend arrow in pixels
+const TREND_ARROW_SIZE: u32 = 12;
+
+/// Render the temperature trend as a small arrow with its top-left corner at `origin`:
+/// up (rising), down (falling) or right (steady). `None` only clears the arrow area.
+fn render_trend_arrow(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    origin: Point,
+    state: &DisplayState,
+) {
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle, Triangle};
+
+    let size = TREND_ARROW_SIZE as i32;
+    let _ = Rectangle::new(origin, Size::new(TREND_ARROW_SIZE, TREND_ARROW_SIZE))
+        .into_styled(PrimitiveStyle::with_fill(state.background_color))
+        .draw(display);
+
+    let (a, b, c) = match state.temp_trend {
+        Some(TempTrend::Rising) => ((size / 2, 0), (0, size - 1), (size - 1, size - 1)),
+        Some(TempTrend::Falling) => ((0, 0), (size - 1, 0), (size / 2, size - 1)),
+        Some(TempTrend::Steady) => ((0, 0), (0, size - 1), (size - 1, size / 2)),
+        None => return,
+    };
+    let point = |(x, y)| origin + Point::new(x, y);
+    let _ = Triangle::new(point(a), point(b), point(c))
+        .into_styled(PrimitiveStyle::with_fill(state.text_color))
+        .draw(display);
+}
+
+/// Render a small sun (day) or crescent moon (night) with its top-left corner at `origin`
+/// `None` (sunrise/sunset unknown) only clears the glyph area.
+fn render_day_night_glyph(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    origin: Point,
+    daytime: Option<bool>,
+    background: Rgb565,
+) {
+    use embedded_graphics::primitives::{Circle, PrimitiveStyle, Rectangle};
+
+    let size = 14;
+    let area = Rectangle::new(origin, Size::new(size, size));
+    let _ = area
+        .into_styled(PrimitiveStyle::with_fill(background))
+        .draw(display);
+
+    // Clip to the glyph area, the moon's shadow disc reaches beyond it
+    let mut display = display.clipped(&area);
+    match daytime {
+        Some(true) => {
+            let _ = Circle::new(origin, size)
+                .into_styled(PrimitiveStyle::with_fill(Rgb565::YELLOW))
+                .draw(&mut display);
+        }
+        Some(false) => {
+            let _ = Circle::new(origin, size)
+                .into_styled(PrimitiveStyle::with_fill(Rgb565::CSS_LIGHT_GRAY))
+                .draw(&mut display);
+            let _ = Circle::new(origin + Point::new(5, -2), size)
+                .into_styled(PrimitiveStyle::with_fill(background))
+                .draw(&mut display);
+        }
+        None => {}
+    }
+}
+
+/// Render weather icon (bitmap or emoji fallback)
+fn render_weather_icon(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    origin: Point,
+    icon_code: &str,
+    symbol_style: &MonoTextStyle<Rgb565>,
+    background: Rgb565,
+) {
+    let icon_color = get_weather_icon_color(icon_code);
+
+    // Prefer the full-color icon, then the 1-bit bitmap from `weather_icons.rs`
+    if let Some(icon_pixels) = get_weather_icon_rgb(icon_code) {
+        draw_icon_rgb(display, icon_pixels, origin, background);
+    } else if let Some(icon_data) = get_weather_icon(icon_code) {
+        draw_icon_bitmap(display, icon_data, origin, icon_color);
+    } else {
+        // Fallback to emoji symbol if bitmap is not found
+        let symbol = get_weather_symbol(icon_code);
+        let _ = Text::new(symbol, origin, *symbol_style).draw(display);
+    }
+}
+
+/// Draw a 40x40 1-bit-per-pixel icon bitmap with its top-left corner at `origin`
+fn draw_icon_bitmap(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    icon_data: &[u8],
+    origin: Point,
+    color: Rgb565,
+) {
+    // Decode the 1-bit-per-pixel bitmap data on the fly, without a pixel buffer
+    let pixels = icon_bitmap::set_pixels(icon_data).map(|point| Pixel(origin + point, color));
+    let _ = display.draw_iter(pixels);
+}
+
+/// Draw a 40x40 full-color icon (one Rgb565 value per pixel, row by row) at `origin`
+/// The black background of the icon is drawn in `background`.
+fn draw_icon_rgb(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    icon_pixels: &[Rgb565],
+    origin: Point,
+    background: Rgb565,
+) {
+    use embedded_graphics::primitives::Rectangle;
+
+    // The whole area is blitted at once, which also clears the previous icon
+    let size = icon_bitmap::ICON_SIZE as u32;
+    let area = Rectangle::new(origin, Size::new(size, size));
+    let pixels = icon_pixels.iter().map(|&pixel| {
+        if pixel == Rgb565::BLACK {
+            background
+        } else {
+            pixel
+        }
+    });
+    let _ = display.fill_contiguous(&area, pixels);
+}
+
+/// Render the forecast for the next days as a horizontal strip below the current conditions
+/// Each cell shows the icon with the weekday and temperature next to it.
+fn render_forecast_strip(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    layout: &Layout,
+    state: &DisplayState,
+) {
+    use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+    let Some(top_y) = layout.forecast_top else {
+        return;
+    };
+    let cell_width = 80;
+
+    // Clear the strip area first
+    let _ = Rectangle::new(Point::new(0, top_y), Size::new(layout.width, 42))
+        .into_styled(PrimitiveStyle::with_fill(state.background_color))
+        .draw(display);
+
+    let small_style = MonoTextStyleBuilder::new()
+        .font(&FONT_6X10)
+        .text_color(state.text_color)
+        .background_color(state.background_color)
+        .build();
+
+    for (i, cell) in state.forecast.iter().enumerate() {
+        let x = 4 + i as i32 * cell_width;
+
+        if let Some(icon_pixels) = get_weather_icon_rgb(&cell.icon) {
+            draw_icon_rgb(
+                display,
+                icon_pixels,
+                Point::new(x, top_y),
+                state.background_color,
+            );
+        } else if let Some(icon_data) = get_weather_icon(&cell.icon) {
+            let color = get_weather_icon_color(&cell.icon);
+            draw_icon_bitmap(display, icon_data, Point::new(x, top_y), color);
+        }
+
+        let _ = Text::new(&cell.day, Point::new(x + 42, top_y + 15), small_style).draw(display);
+        let _ = Text::new(&cell.temp, Point::new(x + 42, top_y + 30), small_style).draw(display);
+    }
+}
+
+/// Render movement detection events row by row in the layout's event columns
+fn render_movement_events(
+    display: &mut impl DrawTarget<Color = Rgb565>,
+    layout: &Layout,
+    events: &[String],
+    rows: i32,
+    text_style: &MonoTextStyle<Rgb565>,
+) {
+    let columns = layout.event_columns as usize;
+
+    for (i, event) in events.iter().take(rows as usize * columns).enumerate() {
+        // The position only depends on the index, filling the columns from left to right
+        let position = movement::event_position(i, columns, layout.event_area, EVENT_ROW_HEIGHT);
+        let _ = Text::new(event, position, *text_style).draw(display);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::primitives::Rectangle;
+
+    const FG: Rgb565 = Rgb565::WHITE;
+
+    /// A dashboard with weather data, in the default theme (white on black)
+    fn home_state() -> DisplayState {
+        DisplayState {
+            date_str: "25.10.2024".to_string(),
+            time_str: "12:34:56".to_string(),
+            city_name: "Berlin, DE".to_string(),
+            weather_temp: format!("12.3°C{}", stale_marker(false)),
+            ..DisplayState::new(&ThemeConfig::default())
+        }
+    }
+
+    /// Renders `state` on the reference 240x320 layout and returns the 64x64 pixel window
+    /// whose top-left corner is `window` on the screen. `MockDisplay` has a fixed size of
+    /// 64x64, everything outside the window is dropped.
+    fn render_window(state: &DisplayState, window: Point) -> MockDisplay<Rgb565> {
+        render_window_with(state, window, MonoTextStyle::new(&FONT_10X20, FG))
+    }
+
+    /// `render_window` with the given text style, e.g. one with a background like in main
+    fn render_window_with(
+        state: &DisplayState,
+        window: Point,
+        text_style: MonoTextStyle<'static, Rgb565>,
+    ) -> MockDisplay<Rgb565> {
+        let mut display = MockDisplay::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        display.set_allow_overdraw(true);
+
+        let previous_state = DisplayState::new(&ThemeConfig::default());
+        render_display(
+            &mut display.translated(Point::zero() - window),
+            &Layout::default_240x320(),
+            state,
+            &previous_state,
+            &text_style,
+            &text_style,
+            6,
+        );
+        display
+    }
+
+    /// Whether any pixel of `area` (screen coordinates) has the text color
+    /// The area must lie inside the 64x64 window.
+    fn has_text(display: &MockDisplay<Rgb565>, window: Point, area: Rectangle) -> bool {
+        let window_area = Rectangle::new(window, Size::new(64, 64));
+        assert!(window_area.contains(area.top_left));
+        assert!(window_area.contains(area.bottom_right().unwrap()));
+        area.points()
+            .any(|point| display.get_pixel(point - window) == Some(FG))
+    }
+
+    /// Character cell (FONT_10X20) of the character at `index` of a text at `baseline`
+    fn char_cell(baseline: Point, index: i32) -> Rectangle {
+        Rectangle::new(baseline + Point::new(index * 10, -15), Size::new(10, 20))
+    }
+
+    #[test]
+    fn test_movement_grid() {
+        let state = DisplayState {
+            movement_events: vec!["00:03".into(), "00:02".into(), "00:01".into()],
+            ..home_state()
+        };
+        let layout = Layout::default_240x320();
+        assert_eq!(layout.event_rows(6), 3);
+
+        // Newest first, two columns filled from left to right, rows of 20 px below y=232
+        let left = Point::new(0, 232);
+        let right = Point::new(110, 232);
+        let display_left = render_window(&state, left);
+        let display_right = render_window(&state, right);
+
+        let text =
+            |baseline: Point| Rectangle::new(baseline - Point::new(0, 15), Size::new(50, 20));
+        assert!(has_text(&display_left, left, text(Point::new(10, 250))));
+        assert!(has_text(&display_right, right, text(Point::new(120, 250))));
+        assert!(has_text(&display_left, left, text(Point::new(10, 270))));
+        // The second row has a single event, the third row none
+        assert!(!has_text(&display_right, right, text(Point::new(120, 270))));
+        assert!(!has_text(&display_left, left, text(Point::new(10, 290))));
+
+        // The cleared event area covers the three rows
+        assert_eq!(
+            display_left.get_pixel(Point::new(0, 0)),
+            Some(Rgb565::BLACK)
+        );
+        assert_eq!(
+            display_left.get_pixel(Point::new(0, 59)),
+            Some(Rgb565::BLACK)
+        );
+        assert_eq!(display_left.get_pixel(Point::new(0, 60)), None);
+    }
+
+    #[test]
+    fn test_stale_marker() {
+        // "12.3°C *": the marker is the 8th character of the temperature at (10, 90)
+        let window = Point::new(40, 64);
+        let marker = char_cell(Point::new(10, 90), 7);
+
+        let fresh = render_window(&home_state(), window);
+        assert!(!has_text(&fresh, window, marker));
+        // The unit in front of it is drawn either way
+        assert!(has_text(&fresh, window, char_cell(Point::new(10, 90), 5)));
+
+        let stale_state = DisplayState {
+            weather_temp: format!("12.3°C{}", stale_marker(true)),
+            ..home_state()
+        };
+        let stale = render_window(&stale_state, window);
+        assert!(has_text(&stale, window, marker));
+        assert!(has_text(&stale, window, char_cell(Point::new(10, 90), 5)));
+    }
+
+    #[test]
+    fn test_small_text_below_the_temperature() {
+        // The temperature and the description are redrawn with their background,
+        // the min/max and the observation time must stay out of their character cells
+        let layout = Layout::default_240x320();
+        let style = MonoTextStyle::new(&FONT_10X20, FG);
+        let temp = Text::new("-10.25°C *", layout.temp_pos, style).bounding_box();
+        let desc = Text::new("light rain", layout.desc_pos, style).bounding_box();
+        let range = Rectangle::new(
+            layout.range_pos.unwrap(),
+            Size::new(TEMP_RANGE_CHARS as u32 * 6, 10),
+        );
+        let observed = Rectangle::new(
+            layout.observed_pos.unwrap(),
+            Size::new(OBSERVED_CHARS as u32 * 6, 10),
+        );
+        for area in [range, observed] {
+            assert!(temp.intersection(&area).is_zero_sized());
+            assert!(desc.intersection(&area).is_zero_sized());
+        }
+    }
+
+    #[test]
+    fn test_trend_arrow_next_to_a_long_temperature() {
+        let state = DisplayState {
+            weather_temp: format!("-10.25°C{}", stale_marker(true)),
+            temp_trend: Some(TempTrend::Rising),
+            ..home_state()
+        };
+        let text_style = MonoTextStyleBuilder::new()
+            .font(&FONT_10X20)
+            .text_color(FG)
+            .background_color(state.background_color)
+            .build();
+        let window = Point::new(64, 64);
+        let display = render_window_with(&state, window, text_style);
+
+        // The arrow is visible and does not cover the stale marker, the 10th character
+        let layout = Layout::default_240x320();
+        let arrow = Rectangle::new(
+            layout.trend_origin.unwrap(),
+            Size::new(TREND_ARROW_SIZE, TREND_ARROW_SIZE),
+        );
+        assert!(has_text(&display, window, arrow));
+        assert!(char_cell(layout.temp_pos, 9)
+            .intersection(&arrow)
+            .is_zero_sized());
+    }
+
+    #[test]
+    fn test_icon_background_follows_the_theme() {
+        let background = Rgb565::WHITE;
+        let mut icon = vec![Rgb565::BLACK; icon_bitmap::ICON_SIZE * icon_bitmap::ICON_SIZE];
+        icon[0] = Rgb565::YELLOW;
+
+        let mut display = MockDisplay::new();
+        draw_icon_rgb(&mut display, &icon, Point::zero(), background);
+        assert_eq!(display.get_pixel(Point::zero()), Some(Rgb565::YELLOW));
+        assert_eq!(display.get_pixel(Point::new(1, 0)), Some(background));
+        assert_eq!(display.get_pixel(Point::new(39, 39)), Some(background));
+    }
+
+    #[test]
+    fn test_rssi_to_bars() {
+        assert_eq!(rssi_to_bars(-40), 4);
+        assert_eq!(rssi_to_bars(-55), 4);
+        assert_eq!(rssi_to_bars(-56), 3);
+        assert_eq!(rssi_to_bars(-77), 2);
+        assert_eq!(rssi_to_bars(-88), 1);
+        assert_eq!(rssi_to_bars(-89), 0);
+        assert_eq!(rssi_to_bars(i8::MIN), 0);
+    }
+
+    #[test]
+    fn test_only_seconds_changed() {
+        let previous = home_state();
+        let tick = DisplayState {
+            time_str: "12:34:57".to_string(),
+            ..home_state()
+        };
+        assert!(only_seconds_changed(&tick, &previous));
+
+        // Nothing changed
+        assert!(!only_seconds_changed(&previous, &previous));
+        // A new minute or hour redraws everything
+        let minute = DisplayState {
+            time_str: "12:35:00".to_string(),
+            ..home_state()
+        };
+        assert!(!only_seconds_changed(&minute, &previous));
+        let hour = DisplayState {
+            time_str: "13:34:56".to_string(),
+            ..home_state()
+        };
+        assert!(!only_seconds_changed(&hour, &previous));
+        // Another field changed in the same second
+        let weather = DisplayState {
+            weather_temp: "13.0°C".to_string(),
+            ..tick.clone()
+        };
+        assert!(!only_seconds_changed(&weather, &previous));
+        // The first frame starts from an empty state
+        let empty = DisplayState::new(&ThemeConfig::default());
+        assert!(!only_seconds_changed(&tick, &empty));
+    }
+
+    #[test]
+    fn test_render_time() {
+        let mut display = MockDisplay::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        let window = Point::new(0, 8);
+        render_time(
+            &mut display.translated(Point::zero() - window),
+            &Layout::default_240x320(),
+            &home_state(),
+            &MonoTextStyleBuilder::new()
+                .font(&FONT_10X20)
+                .text_color(FG)
+                .background_color(Rgb565::BLACK)
+                .build(),
+        );
+
+        // The time at (10, 40) fills its bounding rectangle, up to the window's edge,
+        // and nothing else
+        assert!(has_text(&display, window, char_cell(Point::new(10, 40), 0)));
+        assert_eq!(
+            display.affected_area(),
+            Rectangle::new(Point::new(10, 25) - window, Size::new(54, 20))
+        );
+    }
+
+    #[test]
+    fn test_alert_banner_replaces_the_date() {
+        let window = Point::new(0, 0);
+        let alert_state = DisplayState {
+            alerts: vec!["Storm warning".to_string()],
+            ..home_state()
+        };
+        let display = render_window(&alert_state, window);
+
+        // White text on red in the date row, the date "25.10.2024" is not drawn
+        assert_eq!(display.get_pixel(Point::new(1, 5)), Some(Rgb565::RED));
+        assert!(has_text(&display, window, char_cell(Point::new(4, 20), 0)));
+        let date = render_window(&home_state(), window);
+        assert_eq!(date.get_pixel(Point::new(1, 5)), None);
+        assert_ne!(display, date);
+        // The time below is drawn either way
+        assert!(has_text(&display, window, char_cell(Point::new(10, 40), 0)));
+    }
+
+    #[test]
+    fn test_ip_line_above_the_status_line() {
+        let window = Point::new(0, 256);
+        let ip_line = Rectangle::new(Point::new(10, 300), Size::new(30, 10));
+        let state = DisplayState {
+            ip_str: "192.168.1.23".to_string(),
+            ..home_state()
+        };
+        assert!(has_text(&render_window(&state, window), window, ip_line));
+        assert!(!has_text(
+            &render_window(&home_state(), window),
+            window,
+            ip_line
+        ));
+        // The movement events end above the IP address line
+        assert!(
+            Layout::default_240x320()
+                .event_area
+                .bottom_right()
+                .unwrap()
+                .y
+                < 300
+        );
+    }
+
+    #[test]
+    fn test_away_mode_draws_only_the_clock() {
+        let away_state = DisplayState {
+            display_mode: DisplayMode::Away,
+            ..home_state()
+        };
+
+        // The time at (10, 40) is drawn, the city name at (10, 60) below it is not
+        let window = Point::new(0, 8);
+        let display = render_window(&away_state, window);
+        assert!(has_text(&display, window, char_cell(Point::new(10, 40), 0)));
+        assert!(!has_text(
+            &display,
+            window,
+            char_cell(Point::new(10, 60), 0)
+        ));
+
+        let below_clock = render_window(&away_state, Point::new(0, 46));
+        assert_eq!(below_clock, MockDisplay::new());
+    }
+}
@@ -1,46 +1,1008 @@
-use serde::Deserialize;
+use crate::provider::FieldMapping;
+use crate::time_utils::{DateFormat, WeekdayLang};
+use embedded_graphics::pixelcolor::{Rgb565, RgbColor};
+use serde::{Deserialize, Deserializer};
 
 // Read secrets directly from file at compile time
-const SECRETS_TOML: &str = include_str!("../../secrets.toml");
+// build.rs resolves the path: $SECRETS_FILE or the workspace secrets.toml
+const SECRETS_TOML: &str = include_str!(env!("SECRETS_FILE"));
 
 /// Defines the structure for the secrets.
 #[derive(Deserialize, Debug, Clone)]
 pub struct Secrets {
+    /// Name put in front of every log message (optional, must precede the first section).
+    #[serde(default)]
+    pub device_name: String,
     /// Wi-Fi configuration.
     pub wifi: WiFiConfig,
     /// OpenWeather API configuration.
     pub openweather: OpenWeatherConfig,
-    /// MQTT configuration.
+    /// Source of the current weather (optional section, default: OpenWeather).
+    #[serde(default)]
+    pub weather_provider: WeatherProviderConfig,
+    /// MQTT configuration (optional section, without it MQTT is disabled).
+    #[serde(default)]
     pub mqtt: MqttConfig,
+    /// Display configuration (optional section).
+    #[serde(default)]
+    pub display: DisplayConfig,
+    /// Memory requirements (optional section).
+    #[serde(default)]
+    pub memory: MemoryConfig,
+    /// Web server for changing the city and units at runtime (optional section).
+    #[serde(default)]
+    pub web: WebConfig,
+    /// Task watchdog of the main loop (optional section).
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    /// Text and background colors (optional section).
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Time synchronization (optional section).
+    #[serde(default)]
+    pub time: TimeConfig,
+    /// Timing of the main loop (optional section).
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
 }
 
 /// Defines the structure for the Wi-Fi configuration.
+/// Accepts either a single `ssid`/`password` pair or a list of `[[wifi.networks]]`.
 #[derive(Deserialize, Debug, Clone)]
+#[serde(from = "RawWiFiConfig")]
 pub struct WiFiConfig {
+    /// The configured networks in priority order.
+    pub networks: Vec<WiFiNetwork>,
+    /// The total time in seconds to try all networks at boot before starting offline.
+    pub connect_timeout_secs: u64,
+}
+
+/// Defines the structure for a single Wi-Fi network.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WiFiNetwork {
     /// The SSID of the Wi-Fi network.
     pub ssid: String,
     /// The password of the Wi-Fi network.
     pub password: String,
 }
 
+/// The two accepted TOML forms of the Wi-Fi configuration.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawWiFiConfig {
+    /// A list of `[[wifi.networks]]` entries.
+    Multiple {
+        networks: Vec<WiFiNetwork>,
+        connect_timeout_secs: Option<u64>,
+    },
+    /// The single-network form with `ssid` and `password`.
+    Single {
+        ssid: String,
+        password: String,
+        connect_timeout_secs: Option<u64>,
+    },
+}
+
+/// Default total Wi-Fi connect timeout in seconds.
+const DEFAULT_WIFI_CONNECT_TIMEOUT_SECS: u64 = 60;
+
+impl From<RawWiFiConfig> for WiFiConfig {
+    fn from(raw: RawWiFiConfig) -> Self {
+        match raw {
+            RawWiFiConfig::Multiple {
+                networks,
+                connect_timeout_secs,
+            } => Self {
+                networks,
+                connect_timeout_secs: connect_timeout_secs
+                    .unwrap_or(DEFAULT_WIFI_CONNECT_TIMEOUT_SECS),
+            },
+            RawWiFiConfig::Single {
+                ssid,
+                password,
+                connect_timeout_secs,
+            } => Self {
+                networks: vec![WiFiNetwork { ssid, password }],
+                connect_timeout_secs: connect_timeout_secs
+                    .unwrap_or(DEFAULT_WIFI_CONNECT_TIMEOUT_SECS),
+            },
+        }
+    }
+}
+
 /// Defines the structure for the OpenWeather API configuration.
 #[derive(Deserialize, Debug, Clone)]
 pub struct OpenWeatherConfig {
     /// The API key for the OpenWeather API.
     pub api_key: String,
-    /// The city for which the weather should be displayed.
+    /// The city for which the weather should be displayed, optionally as "City,CC".
+    #[serde(default)]
     pub city: String,
+    /// Several cities shown in turn, used instead of `city` if not empty.
+    /// The first one is the home city, `lat`/`lon` only apply to it.
+    #[serde(default)]
+    pub cities: Vec<String>,
+    /// Seconds each city stays on the display when `cities` has more than one entry (default: 10).
+    #[serde(default = "default_cycle_secs")]
+    pub cycle_secs: u64,
+    /// The unit system: "metric", "imperial" or "standard" (default: metric).
+    pub units: Option<String>,
+    /// The language of the weather description, e.g. "en" or "de" (default: en).
+    pub lang: Option<String>,
+    /// The latitude of the location; together with `lon` it is used instead of `city`.
+    pub lat: Option<f64>,
+    /// The longitude of the location; together with `lat` it is used instead of `city`.
+    pub lon: Option<f64>,
+    /// The network timeout of a request in seconds (default: 30).
+    #[serde(default = "default_http_timeout_secs")]
+    pub http_timeout_secs: u64,
+}
+
+/// Unit systems supported by the OpenWeather API.
+const OPENWEATHER_UNITS: [&str; 3] = ["metric", "imperial", "standard"];
+
+/// Accepted range of the HTTP timeout in seconds.
+const HTTP_TIMEOUT_SECS_RANGE: core::ops::RangeInclusive<u64> = 5..=120;
+
+/// Default HTTP timeout in seconds.
+fn default_http_timeout_secs() -> u64 {
+    30
+}
+
+/// Accepted range of the city cycle interval in seconds.
+const CYCLE_SECS_RANGE: core::ops::RangeInclusive<u64> = 3..=3600;
+
+/// Default city cycle interval in seconds.
+fn default_cycle_secs() -> u64 {
+    10
+}
+
+impl OpenWeatherConfig {
+    /// Returns the coordinates if both `lat` and `lon` are set.
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.lat.zip(self.lon)
+    }
+
+    /// Checks the values that serde cannot validate on its own.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.api_key.trim().is_empty() {
+            anyhow::bail!("openweather.api_key must not be empty");
+        }
+        if self.cities.is_empty() {
+            if self.city.trim().is_empty() && self.coordinates().is_none() {
+                anyhow::bail!("openweather.city must not be empty unless lat/lon are set");
+            }
+        } else if self.cities.iter().any(|city| city.trim().is_empty()) {
+            anyhow::bail!("openweather.cities must not contain empty names");
+        }
+        if !OPENWEATHER_UNITS.contains(&self.units()) {
+            anyhow::bail!(
+                "Invalid openweather.units '{}', expected one of {:?}",
+                self.units(),
+                OPENWEATHER_UNITS
+            );
+        }
+        if self.lat.is_some() != self.lon.is_some() {
+            anyhow::bail!("openweather.lat and openweather.lon must be set together");
+        }
+        if let Some(lat) = self.lat {
+            if !(-90.0..=90.0).contains(&lat) {
+                anyhow::bail!(
+                    "Invalid openweather.lat {}, expected a value in [-90, 90]",
+                    lat
+                );
+            }
+        }
+        if let Some(lon) = self.lon {
+            if !(-180.0..=180.0).contains(&lon) {
+                anyhow::bail!(
+                    "Invalid openweather.lon {}, expected a value in [-180, 180]",
+                    lon
+                );
+            }
+        }
+        if !HTTP_TIMEOUT_SECS_RANGE.contains(&self.http_timeout_secs) {
+            anyhow::bail!(
+                "Invalid openweather.http_timeout_secs {}, expected a value in {:?}",
+                self.http_timeout_secs,
+                HTTP_TIMEOUT_SECS_RANGE
+            );
+        }
+        if !CYCLE_SECS_RANGE.contains(&self.cycle_secs) {
+            anyhow::bail!(
+                "Invalid openweather.cycle_secs {}, expected a value in {:?}",
+                self.cycle_secs,
+                CYCLE_SECS_RANGE
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the cities shown in turn, a single `city` is a list of one.
+    pub fn city_list(&self) -> Vec<String> {
+        if self.cities.is_empty() {
+            vec![self.city.clone()]
+        } else {
+            self.cities.clone()
+        }
+    }
+
+    /// Returns the home city, the first of `city_list`.
+    pub fn home_city(&self) -> &str {
+        self.cities.first().unwrap_or(&self.city)
+    }
+
+    /// Replaces the home city, keeping the other cities of the list.
+    pub fn set_home_city(&mut self, city: &str) {
+        self.city = city.to_string();
+        if let Some(first) = self.cities.first_mut() {
+            *first = city.to_string();
+        }
+    }
+
+    /// Returns one configuration per city of `city_list`, each with a single `city`.
+    /// Only the home city keeps the coordinates.
+    pub fn city_configs(&self) -> Vec<OpenWeatherConfig> {
+        self.city_list()
+            .into_iter()
+            .enumerate()
+            .map(|(index, city)| OpenWeatherConfig {
+                city,
+                cities: Vec::new(),
+                lat: self.lat.filter(|_| index == 0),
+                lon: self.lon.filter(|_| index == 0),
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// Returns the interval at which the displayed city changes.
+    pub fn cycle_interval(&self) -> core::time::Duration {
+        core::time::Duration::from_secs(self.cycle_secs)
+    }
+
+    /// Returns the network timeout of a request.
+    pub fn http_timeout(&self) -> core::time::Duration {
+        core::time::Duration::from_secs(self.http_timeout_secs)
+    }
+
+    /// Returns the configured unit system, defaulting to metric.
+    pub fn units(&self) -> &str {
+        self.units.as_deref().unwrap_or("metric")
+    }
+
+    /// Returns the configured language, defaulting to English.
+    pub fn lang(&self) -> &str {
+        self.lang.as_deref().unwrap_or("en")
+    }
+
+    /// Returns the temperature label matching the unit system.
+    pub fn temperature_unit(&self) -> &'static str {
+        match self.units() {
+            "imperial" => "°F",
+            "standard" => "K",
+            _ => "°C",
+        }
+    }
+
+    /// Returns the wind speed label matching the unit system.
+    pub fn wind_speed_unit(&self) -> &'static str {
+        crate::weather::speed_unit(self.units())
+    }
+}
+
+/// Defines the structure for the source of the current weather.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WeatherProviderConfig {
+    /// The provider: "openweather" or "json" (default: openweather).
+    #[serde(default = "default_provider_kind")]
+    pub kind: String,
+    /// The URL of the JSON document (json only).
+    #[serde(default)]
+    pub url: String,
+    /// The location name shown if `fields.name` is not set (json only).
+    #[serde(default)]
+    pub name: String,
+    /// Where the values are found in the JSON document (json only).
+    pub fields: Option<FieldMapping>,
+}
+
+/// Weather providers that can be selected.
+const WEATHER_PROVIDERS: [&str; 2] = ["openweather", "json"];
+
+/// Default weather provider.
+fn default_provider_kind() -> String {
+    "openweather".to_string()
+}
+
+impl WeatherProviderConfig {
+    /// Returns the field mapping if the JSON provider is selected.
+    pub fn json_fields(&self) -> Option<&FieldMapping> {
+        self.fields.as_ref().filter(|_| self.kind == "json")
+    }
+
+    /// Checks the values that serde cannot validate on its own.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !WEATHER_PROVIDERS.contains(&self.kind.as_str()) {
+            anyhow::bail!(
+                "Invalid weather_provider.kind '{}', expected one of {:?}",
+                self.kind,
+                WEATHER_PROVIDERS
+            );
+        }
+        if self.kind != "json" {
+            return Ok(());
+        }
+        if self.url.trim().is_empty() {
+            anyhow::bail!("weather_provider.url must not be empty for the json provider");
+        }
+        let fields = self.fields.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("weather_provider.fields must be set for the json provider")
+        })?;
+        if let Some(pointer) = fields.pointers().find(|pointer| !pointer.starts_with('/')) {
+            anyhow::bail!(
+                "Invalid weather_provider.fields pointer '{}', expected e.g. \"/current/temp\"",
+                pointer
+            );
+        }
+        if fields.name.is_none() && self.name.trim().is_empty() {
+            anyhow::bail!("weather_provider.name must not be empty unless fields.name is set");
+        }
+        Ok(())
+    }
+}
+
+impl Default for WeatherProviderConfig {
+    fn default() -> Self {
+        Self {
+            kind: default_provider_kind(),
+            url: String::new(),
+            name: String::new(),
+            fields: None,
+        }
+    }
 }
 
 /// Defines the structure for the MQTT configuration.
 #[derive(Deserialize, Debug, Clone)]
 pub struct MqttConfig {
-    /// The URL of the MQTT broker.
+    /// Whether the broker is used at all (default: true if the section exists).
+    /// Without it the display runs without movement events and nothing is published.
+    #[serde(default = "default_mqtt_enabled")]
+    pub enabled: bool,
+    /// The URL of the MQTT broker, required while enabled.
+    #[serde(default)]
     pub broker_url: String,
     /// The username for the MQTT broker.
+    #[serde(default)]
     pub mqtt_user: String,
     /// The password for the MQTT broker.
+    #[serde(default)]
     pub mqtt_pw: String,
+    /// The topic on which movement events are received.
+    #[serde(default = "default_movement_topic")]
+    pub movement_topic: String,
+    /// The payload on the movement topic that counts as a movement event.
+    #[serde(default = "default_movement_trigger_payload")]
+    pub movement_trigger_payload: String,
+    /// The topic on which any message clears the movement history.
+    #[serde(default = "default_movement_clear_topic")]
+    pub movement_clear_topic: String,
+    /// The topic on which any message triggers an immediate weather fetch.
+    #[serde(default = "default_refresh_topic")]
+    pub refresh_topic: String,
+    /// The topic that switches the display between "home" (dashboard) and "away" (clock only).
+    #[serde(default = "default_mode_topic")]
+    pub mode_topic: String,
+    /// The availability topic: "online" after connecting, "offline" as Last Will.
+    #[serde(default = "default_status_topic")]
+    pub status_topic: String,
+    /// The number of movement events kept and shown on the display.
+    #[serde(default = "default_movement_history_len")]
+    pub movement_history_len: usize,
+    /// The seconds a movement event must follow the previous one to be kept (default: 2).
+    /// Filters the repeated triggers of a jittery sensor, 0 keeps every event.
+    #[serde(default = "default_min_event_gap_secs")]
+    pub min_event_gap_secs: u64,
+    /// The GPIO that is pulsed on every movement event, e.g. for an LED or a buzzer
+    /// (default: none).
+    #[serde(default)]
+    pub movement_alert_pin: Option<u8>,
+    /// The length of the pulse in milliseconds, 1 to 1000 (default: 100).
+    #[serde(default = "default_movement_alert_ms")]
+    pub movement_alert_ms: u32,
+    /// The QoS level of the weather messages: 0, 1 or 2 (default: 1).
+    #[serde(default = "default_publish_qos")]
+    pub publish_qos: u8,
+    /// Whether the broker keeps the last weather message for new subscribers (default: false).
+    #[serde(default)]
+    pub publish_retain: bool,
+    /// Whether the whole weather response is published as JSON to `weather/<city>` (default: true).
+    #[serde(default = "default_publish_json")]
+    pub publish_json: bool,
+    /// Whether temperature, humidity and wind are published as plain numbers to
+    /// `weather/<city>/temp`, `/humidity` and `/wind` (default: false).
+    #[serde(default)]
+    pub publish_split: bool,
+    /// The interval of the keepalive pings in seconds (default: 120, the ESP-IDF default).
+    #[serde(default = "default_keep_alive_secs")]
+    pub keep_alive_secs: u64,
+    /// The wait before reconnecting after the connection dropped, in seconds
+    /// (default: 10, the ESP-IDF default).
+    #[serde(default = "default_reconnect_timeout_secs")]
+    pub reconnect_timeout_secs: u64,
+    /// The PEM file of the CA that signed the broker certificate, relative to the workspace root.
+    /// Replaces the ESP-IDF certificate bundle; build.rs embeds the file into the firmware.
+    #[serde(default)]
+    pub ca_cert_path: Option<String>,
+    /// The PEM file of the client certificate for mutual TLS, relative to the workspace root.
+    /// build.rs embeds the file into the firmware, see `client_key`.
+    #[serde(default)]
+    pub client_cert: Option<String>,
+    /// The PEM file of the private key belonging to `client_cert`.
+    #[serde(default)]
+    pub client_key: Option<String>,
+}
+
+impl Default for MqttConfig {
+    /// A missing `[mqtt]` section disables MQTT.
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            broker_url: String::new(),
+            mqtt_user: String::new(),
+            mqtt_pw: String::new(),
+            movement_topic: default_movement_topic(),
+            movement_trigger_payload: default_movement_trigger_payload(),
+            movement_clear_topic: default_movement_clear_topic(),
+            refresh_topic: default_refresh_topic(),
+            mode_topic: default_mode_topic(),
+            status_topic: default_status_topic(),
+            movement_history_len: default_movement_history_len(),
+            min_event_gap_secs: default_min_event_gap_secs(),
+            movement_alert_pin: None,
+            movement_alert_ms: default_movement_alert_ms(),
+            publish_qos: default_publish_qos(),
+            publish_retain: false,
+            publish_json: default_publish_json(),
+            publish_split: false,
+            keep_alive_secs: default_keep_alive_secs(),
+            reconnect_timeout_secs: default_reconnect_timeout_secs(),
+            ca_cert_path: None,
+            client_cert: None,
+            client_key: None,
+        }
+    }
+}
+
+impl MqttConfig {
+    /// Checks the broker settings, only needed while MQTT is enabled.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.broker_url.is_empty() {
+            anyhow::bail!("mqtt.broker_url is required unless mqtt.enabled is false");
+        }
+
+        if self.publish_qos > 2 {
+            anyhow::bail!(
+                "mqtt.publish_qos must be 0, 1 or 2, got {}",
+                self.publish_qos
+            );
+        }
+
+        // The keepalive is a 16-bit field of the MQTT CONNECT packet, 0 would disable it
+        if !(1..=u16::MAX as u64).contains(&self.keep_alive_secs) {
+            anyhow::bail!(
+                "mqtt.keep_alive_secs must be between 1 and {}, got {}",
+                u16::MAX,
+                self.keep_alive_secs
+            );
+        }
+
+        if self.reconnect_timeout_secs == 0 {
+            anyhow::bail!("mqtt.reconnect_timeout_secs must be at least 1");
+        }
+
+        // The pulse blocks the MQTT event thread
+        if !MOVEMENT_ALERT_MS_RANGE.contains(&self.movement_alert_ms) {
+            anyhow::bail!(
+                "mqtt.movement_alert_ms must be between {} and {}, got {}",
+                MOVEMENT_ALERT_MS_RANGE.start(),
+                MOVEMENT_ALERT_MS_RANGE.end(),
+                self.movement_alert_ms
+            );
+        }
+
+        if !MQTT_SCHEMES
+            .iter()
+            .any(|scheme| self.broker_url.starts_with(scheme))
+        {
+            anyhow::bail!(
+                "mqtt.broker_url '{}' must start with one of {:?}",
+                self.broker_url,
+                MQTT_SCHEMES
+            );
+        }
+
+        match (&self.client_cert, &self.client_key) {
+            (Some(_), None) => {
+                anyhow::bail!("mqtt.client_cert is set without mqtt.client_key")
+            }
+            (None, Some(_)) => {
+                anyhow::bail!("mqtt.client_key is set without mqtt.client_cert")
+            }
+            (Some(_), Some(_))
+                if !["mqtts://", "wss://"]
+                    .iter()
+                    .any(|scheme| self.broker_url.starts_with(scheme)) =>
+            {
+                anyhow::bail!(
+                    "mqtt.client_cert needs an mqtts:// or wss:// broker_url, got '{}'",
+                    self.broker_url
+                );
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// URL schemes supported by the ESP-IDF MQTT client.
+const MQTT_SCHEMES: [&str; 4] = ["mqtt://", "mqtts://", "ws://", "wss://"];
+
+/// MQTT is enabled when the `[mqtt]` section exists, unless `enabled = false`.
+fn default_mqtt_enabled() -> bool {
+    true
+}
+
+/// Default movement topic ("Bewegung" is German for "movement").
+fn default_movement_topic() -> String {
+    "Bewegung".to_string()
+}
+
+/// Default payload that signals a movement event.
+fn default_movement_trigger_payload() -> String {
+    "1".to_string()
+}
+
+/// Default topic for clearing the movement history.
+fn default_movement_clear_topic() -> String {
+    "Bewegung/clear".to_string()
+}
+
+/// Default topic of the weather refresh command, derived from the MQTT client id.
+fn default_refresh_topic() -> String {
+    "esp32-weather-client-rust/weather_refresh".to_string()
+}
+
+/// Default topic of the display mode, derived from the MQTT client id.
+fn default_mode_topic() -> String {
+    "esp32-weather-client-rust/mode".to_string()
+}
+
+/// Default availability topic, derived from the MQTT client id.
+fn default_status_topic() -> String {
+    "esp32-weather-client-rust/status".to_string()
+}
+
+/// Default minimum gap between two movement events.
+fn default_min_event_gap_secs() -> u64 {
+    2
+}
+
+/// Accepted range of the movement alert pulse in milliseconds.
+const MOVEMENT_ALERT_MS_RANGE: core::ops::RangeInclusive<u32> = 1..=1000;
+
+/// Default length of the movement alert pulse in milliseconds.
+fn default_movement_alert_ms() -> u32 {
+    100
+}
+
+/// Default number of movement events (three rows of two).
+fn default_movement_history_len() -> usize {
+    6
+}
+
+/// Default QoS level of the weather messages (at least once).
+fn default_publish_qos() -> u8 {
+    1
+}
+
+/// The JSON weather message is published unless disabled.
+fn default_publish_json() -> bool {
+    true
+}
+
+/// Keepalive interval of the ESP-IDF MQTT client.
+fn default_keep_alive_secs() -> u64 {
+    120
+}
+
+/// Reconnect timeout of the ESP-IDF MQTT client.
+fn default_reconnect_timeout_secs() -> u64 {
+    10
+}
+
+/// Temperatures are shown with one decimal place by default.
+fn default_temp_decimals() -> usize {
+    1
+}
+
+/// Dates are shown as DD.MM.YYYY by default.
+fn default_date_format() -> String {
+    "de".to_string()
+}
+
+/// Default SPI clock of the display, as in `display_hal`.
+fn default_spi_mhz() -> u32 {
+    26
+}
+
+/// Default panel width (240x320 ST7789).
+fn default_display_width() -> u16 {
+    240
+}
+
+/// Default panel height (240x320 ST7789).
+fn default_display_height() -> u16 {
+    320
+}
+
+/// Default age in minutes after which weather data is marked as stale.
+fn default_stale_after_mins() -> u64 {
+    45
+}
+
+/// Defines the structure for the display configuration.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DisplayConfig {
+    /// The display rotation in degrees: 0, 90, 180 or 270 (default: 0, portrait).
+    #[serde(default)]
+    pub rotation: u16,
+    /// The panel width in pixels, in the unrotated portrait orientation (default: 240).
+    #[serde(default = "default_display_width")]
+    pub width: u16,
+    /// The panel height in pixels, in the unrotated portrait orientation (default: 320).
+    #[serde(default = "default_display_height")]
+    pub height: u16,
+    /// The first visible column of the controller's frame memory, e.g. 52 for 135x240 panels (default: 0).
+    #[serde(default)]
+    pub offset_x: u16,
+    /// The first visible row of the controller's frame memory, e.g. 40 for 135x240 panels (default: 0).
+    #[serde(default)]
+    pub offset_y: u16,
+    /// The age in minutes after which the weather data is marked as stale (default: 45).
+    #[serde(default = "default_stale_after_mins")]
+    pub stale_after_mins: u64,
+    /// The minutes without movement events or weather changes after which the panel sleeps,
+    /// 0 keeps it on (default: 0).
+    #[serde(default)]
+    pub power_save_minutes: u64,
+    /// The decimal places of the temperatures, 0 to 2 (default: 1).
+    #[serde(default = "default_temp_decimals")]
+    pub temp_decimals: usize,
+    /// Whether positive temperatures get a "+" (default: false).
+    #[serde(default)]
+    pub temp_show_sign: bool,
+    /// The date format: "de" (DD.MM.YYYY), "us" (MM/DD/YYYY) or "iso" (YYYY-MM-DD)
+    /// (default: "de").
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// The weekday in front of the date: "de" (Mon, Die, ...), "en" (Mon, Tue, ...) or
+    /// empty for none (default: empty). It takes the place of the time zone, the layouts
+    /// without room for the time zone show no weekday either.
+    #[serde(default)]
+    pub weekday: String,
+    /// The SPI clock in MHz, clamped to 1 to 80 (default: 26).
+    #[serde(default = "default_spi_mhz")]
+    pub spi_mhz: u32,
+    /// The GPIOs the display is wired to (optional section).
+    #[serde(default)]
+    pub pins: DisplayPinsConfig,
+    /// The GPIO of the panel's BL pin, dimmed via PWM at night (default: none, the text
+    /// colors are dimmed instead).
+    #[serde(default)]
+    pub backlight_pin: Option<u8>,
+    /// Whether the content is drawn into a frame buffer in PSRAM and sent to the panel
+    /// in one piece (default: false).
+    #[serde(default)]
+    pub frame_buffer: bool,
+    /// Whether the IP address is shown above the status line, in the portrait layouts
+    /// of the larger panels (default: false).
+    #[serde(default)]
+    pub show_ip: bool,
+}
+
+impl DisplayConfig {
+    /// Returns the date format, German if the value is invalid (rejected by `validate`)
+    pub fn date_format(&self) -> DateFormat {
+        DateFormat::parse(&self.date_format).unwrap_or_default()
+    }
+
+    /// Returns the language of the weekday in front of the date, `None` if it is not shown
+    pub fn weekday_lang(&self) -> Option<WeekdayLang> {
+        WeekdayLang::parse(&self.weekday)
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            rotation: 0,
+            width: default_display_width(),
+            height: default_display_height(),
+            offset_x: 0,
+            offset_y: 0,
+            stale_after_mins: default_stale_after_mins(),
+            power_save_minutes: 0,
+            temp_decimals: default_temp_decimals(),
+            temp_show_sign: false,
+            date_format: default_date_format(),
+            weekday: String::new(),
+            spi_mhz: default_spi_mhz(),
+            pins: DisplayPinsConfig::default(),
+            backlight_pin: None,
+            frame_buffer: false,
+            show_ip: false,
+        }
+    }
+}
+
+/// Defines the structure for the display wiring, as GPIO numbers.
+/// The defaults are the wiring of the `wifi_display` project.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DisplayPinsConfig {
+    /// The SPI clock, SCL on the display (default: 18).
+    #[serde(default = "default_sclk_pin")]
+    pub sclk: u8,
+    /// The SPI data out, SDA on the display (default: 23).
+    #[serde(default = "default_mosi_pin")]
+    pub mosi: u8,
+    /// The chip select (default: 15).
+    #[serde(default = "default_cs_pin")]
+    pub cs: u8,
+    /// The data/command select (default: 21).
+    #[serde(default = "default_dc_pin")]
+    pub dc: u8,
+    /// The hardware reset (default: 22).
+    #[serde(default = "default_rst_pin")]
+    pub rst: u8,
+}
+
+fn default_sclk_pin() -> u8 {
+    18
+}
+
+fn default_mosi_pin() -> u8 {
+    23
+}
+
+fn default_cs_pin() -> u8 {
+    15
+}
+
+fn default_dc_pin() -> u8 {
+    21
+}
+
+fn default_rst_pin() -> u8 {
+    22
+}
+
+impl Default for DisplayPinsConfig {
+    fn default() -> Self {
+        Self {
+            sclk: default_sclk_pin(),
+            mosi: default_mosi_pin(),
+            cs: default_cs_pin(),
+            dc: default_dc_pin(),
+            rst: default_rst_pin(),
+        }
+    }
+}
+
+/// Defines the structure for the memory requirements.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MemoryConfig {
+    /// The minimum SPIRAM size in bytes, checked at boot (default: 0, no check).
+    #[serde(default)]
+    pub min_spiram_bytes: usize,
+}
+
+/// Defines the structure for the configuration web server.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct WebConfig {
+    /// Whether the web server is started (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+    /// The token that must be sent with every request.
+    #[serde(default)]
+    pub token: String,
+}
+
+/// Shortest accepted watchdog timeout in seconds.
+/// The main loop feeds the watchdog before every request and Wi-Fi reconnect attempt, the
+/// longest wait between two reconnect attempts is 60 seconds.
+const MIN_WATCHDOG_TIMEOUT_SECS: u64 = 90;
+
+/// Time on top of `openweather.http_timeout_secs` a request may take before the watchdog
+/// fires, e.g. for the DNS lookup and the TLS handshake.
+const WATCHDOG_REQUEST_MARGIN_SECS: u64 = 30;
+
+/// Default watchdog timeout in seconds.
+fn default_watchdog_timeout_secs() -> u64 {
+    300
+}
+
+/// Defines the structure for the main loop watchdog.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WatchdogConfig {
+    /// The time in seconds a single step of the main loop (a request, a Wi-Fi reconnect
+    /// attempt) may take before the device reboots (default: 300).
+    #[serde(default = "default_watchdog_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_watchdog_timeout_secs(),
+        }
+    }
+}
+
+/// Defines the structure for the display colors, written as "#RRGGBB".
+#[derive(Deserialize, Debug, Clone)]
+pub struct ThemeConfig {
+    /// The text color (default: "#FFFFFF").
+    #[serde(default = "default_theme_fg", deserialize_with = "deserialize_color")]
+    pub fg: Rgb565,
+    /// The background color (default: "#000000").
+    #[serde(default = "default_theme_bg", deserialize_with = "deserialize_color")]
+    pub bg: Rgb565,
+    /// Colors the temperature by its value instead of `fg` (optional section).
+    pub temp_colors: Option<TempColorConfig>,
+}
+
+/// Defines the thresholds of the temperature colors in °C, for every unit system.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TempColorConfig {
+    /// Blue below this temperature, green from it on (default: 0).
+    #[serde(default)]
+    pub cold_below: f32,
+    /// Orange from this temperature on (default: 20).
+    #[serde(default = "default_warm_from")]
+    pub warm_from: f32,
+    /// Red above this temperature (default: 30).
+    #[serde(default = "default_hot_above")]
+    pub hot_above: f32,
+}
+
+/// Default start of the orange range in °C.
+fn default_warm_from() -> f32 {
+    20.0
+}
+
+/// Default end of the orange range in °C.
+fn default_hot_above() -> f32 {
+    30.0
+}
+
+impl TempColorConfig {
+    /// Checks that the thresholds are in ascending order.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let thresholds = [self.cold_below, self.warm_from, self.hot_above];
+        if thresholds.iter().any(|t| !t.is_finite()) || !thresholds.windows(2).all(|w| w[0] <= w[1])
+        {
+            anyhow::bail!(
+                "Invalid theme.temp_colors {:?}, expected cold_below <= warm_from <= hot_above",
+                thresholds
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for TempColorConfig {
+    fn default() -> Self {
+        Self {
+            cold_below: 0.0,
+            warm_from: default_warm_from(),
+            hot_above: default_hot_above(),
+        }
+    }
+}
+
+/// Default text color: white.
+fn default_theme_fg() -> Rgb565 {
+    Rgb565::WHITE
+}
+
+/// Default background color: black.
+fn default_theme_bg() -> Rgb565 {
+    Rgb565::BLACK
+}
+
+/// Parses a "#RRGGBB" string while the secrets are loaded, so a typo fails at boot.
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgb565, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    crate::theme::parse_rgb565(&hex).map_err(serde::de::Error::custom)
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            fg: default_theme_fg(),
+            bg: default_theme_bg(),
+            temp_colors: None,
+        }
+    }
+}
+
+/// Accepted range of the SNTP resync interval in hours.
+const RESYNC_HOURS_RANGE: core::ops::RangeInclusive<u64> = 1..=168;
+
+/// Default SNTP resync interval in hours.
+fn default_resync_hours() -> u64 {
+    6
+}
+
+/// Defines the structure for the time synchronization.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TimeConfig {
+    /// The interval in hours in which SNTP resynchronizes the clock (default: 6).
+    #[serde(default = "default_resync_hours")]
+    pub resync_hours: u64,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            resync_hours: default_resync_hours(),
+        }
+    }
+}
+
+/// Accepted range of the main loop tick in milliseconds.
+const TICK_MS_RANGE: core::ops::RangeInclusive<u32> = 10..=1000;
+
+/// Default main loop tick in milliseconds.
+fn default_tick_ms() -> u32 {
+    100
+}
+
+/// Default interval of the clock redraw in milliseconds.
+fn default_clock_redraw_ms() -> u32 {
+    1000
+}
+
+/// Default interval of the weather fetch in minutes.
+fn default_weather_interval_mins() -> u64 {
+    15
+}
+
+/// Defines the structure for the main loop timing.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ScheduleConfig {
+    /// The sleep between two main loop iterations in milliseconds (default: 100).
+    #[serde(default = "default_tick_ms")]
+    pub tick_ms: u32,
+    /// The interval in milliseconds in which the clock is redrawn (default: 1000).
+    #[serde(default = "default_clock_redraw_ms")]
+    pub clock_redraw_ms: u32,
+    /// The interval in minutes in which the weather is fetched (default: 15).
+    #[serde(default = "default_weather_interval_mins")]
+    pub weather_interval_mins: u64,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: default_tick_ms(),
+            clock_redraw_ms: default_clock_redraw_ms(),
+            weather_interval_mins: default_weather_interval_mins(),
+        }
+    }
 }
 
 impl Secrets {
@@ -48,6 +1010,275 @@ impl Secrets {
     pub fn load() -> anyhow::Result<Self> {
         let secrets: Secrets = toml::from_str(SECRETS_TOML)
             .map_err(|e| anyhow::anyhow!("Error parsing secrets.toml: {}", e))?;
+
+        secrets.validate()?;
+
         Ok(secrets)
     }
+
+    /// Checks that the configuration is usable, naming the offending field otherwise.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.wifi.networks.is_empty() {
+            anyhow::bail!("wifi.networks must contain at least one network");
+        }
+        if let Some(i) = self
+            .wifi
+            .networks
+            .iter()
+            .position(|network| network.ssid.is_empty())
+        {
+            anyhow::bail!("wifi.ssid must not be empty (network {})", i + 1);
+        }
+
+        self.openweather.validate()?;
+        self.weather_provider.validate()?;
+
+        if ![0, 90, 180, 270].contains(&self.display.rotation) {
+            anyhow::bail!(
+                "display.rotation must be 0, 90, 180 or 270, got {}",
+                self.display.rotation
+            );
+        }
+
+        if self.display.temp_decimals > 2 {
+            anyhow::bail!(
+                "display.temp_decimals must be between 0 and 2, got {}",
+                self.display.temp_decimals
+            );
+        }
+
+        if DateFormat::parse(&self.display.date_format).is_none() {
+            anyhow::bail!(
+                "display.date_format must be \"de\", \"us\" or \"iso\", got \"{}\"",
+                self.display.date_format
+            );
+        }
+        if !self.display.weekday.is_empty() && self.display.weekday_lang().is_none() {
+            anyhow::bail!(
+                "display.weekday must be \"de\", \"en\" or empty, got \"{}\"",
+                self.display.weekday
+            );
+        }
+
+        if self.web.enabled && self.web.token.trim().is_empty() {
+            anyhow::bail!("web.token must not be empty when the web server is enabled");
+        }
+
+        if self.watchdog.timeout_secs < MIN_WATCHDOG_TIMEOUT_SECS {
+            anyhow::bail!(
+                "watchdog.timeout_secs must be at least {}, got {}",
+                MIN_WATCHDOG_TIMEOUT_SECS,
+                self.watchdog.timeout_secs
+            );
+        }
+        let slowest_request = self.openweather.http_timeout_secs + WATCHDOG_REQUEST_MARGIN_SECS;
+        if self.watchdog.timeout_secs < slowest_request {
+            anyhow::bail!(
+                "watchdog.timeout_secs must be at least openweather.http_timeout_secs + {} ({}), got {}",
+                WATCHDOG_REQUEST_MARGIN_SECS,
+                slowest_request,
+                self.watchdog.timeout_secs
+            );
+        }
+
+        if !RESYNC_HOURS_RANGE.contains(&self.time.resync_hours) {
+            anyhow::bail!(
+                "time.resync_hours must be between {} and {}, got {}",
+                RESYNC_HOURS_RANGE.start(),
+                RESYNC_HOURS_RANGE.end(),
+                self.time.resync_hours
+            );
+        }
+
+        if !TICK_MS_RANGE.contains(&self.schedule.tick_ms) {
+            anyhow::bail!(
+                "schedule.tick_ms must be between {} and {}, got {}",
+                TICK_MS_RANGE.start(),
+                TICK_MS_RANGE.end(),
+                self.schedule.tick_ms
+            );
+        }
+        if self.schedule.clock_redraw_ms < self.schedule.tick_ms {
+            anyhow::bail!(
+                "schedule.clock_redraw_ms must be at least schedule.tick_ms ({}), got {}",
+                self.schedule.tick_ms,
+                self.schedule.clock_redraw_ms
+            );
+        }
+        if self.schedule.weather_interval_mins == 0 {
+            anyhow::bail!("schedule.weather_interval_mins must be at least 1");
+        }
+
+        if self.theme.fg == self.theme.bg {
+            anyhow::bail!("theme.fg and theme.bg must differ, the text would be invisible");
+        }
+        if let Some(temp_colors) = &self.theme.temp_colors {
+            temp_colors.validate()?;
+        }
+
+        if self.mqtt.enabled {
+            self.mqtt.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The smallest valid configuration, without an MQTT section
+    const MINIMAL: &str = r#"
+        [wifi]
+        ssid = "home"
+        password = "secret"
+
+        [openweather]
+        api_key = "0123456789abcdef"
+        city = "Berlin,DE"
+    "#;
+
+    fn parse(toml: &str) -> Secrets {
+        toml::from_str(toml).unwrap()
+    }
+
+    /// Error message of `validate` for `MINIMAL` with one line replaced
+    fn validate_error(from: &str, to: &str) -> String {
+        assert!(MINIMAL.contains(from));
+        parse(&MINIMAL.replace(from, to))
+            .validate()
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_validate_minimal() {
+        let secrets = parse(MINIMAL);
+        secrets.validate().unwrap();
+        assert!(!secrets.mqtt.enabled);
+    }
+
+    #[test]
+    fn test_validate_names_the_field() {
+        assert!(validate_error(r#"ssid = "home""#, r#"ssid = """#).contains("wifi.ssid"));
+        assert!(
+            validate_error(r#"api_key = "0123456789abcdef""#, r#"api_key = " ""#)
+                .contains("openweather.api_key")
+        );
+        assert!(validate_error(r#"city = "Berlin,DE""#, "").contains("openweather.city"));
+    }
+
+    #[test]
+    fn test_validate_coordinates_instead_of_city() {
+        parse(&MINIMAL.replace(r#"city = "Berlin,DE""#, "lat = 52.52\nlon = 13.40"))
+            .validate()
+            .unwrap();
+        assert!(validate_error(r#"city = "Berlin,DE""#, "lat = 52.52").contains("openweather.city"));
+        assert!(validate_error(
+            r#"city = "Berlin,DE""#,
+            "city = \"\"\nlat = 91.0\nlon = 0.0"
+        )
+        .contains("openweather.lat"));
+    }
+
+    #[test]
+    fn test_city_list() {
+        let single = parse(MINIMAL).openweather;
+        assert_eq!(single.city_list(), ["Berlin,DE"]);
+        assert_eq!(single.home_city(), "Berlin,DE");
+
+        // The list replaces `city`, its first entry is the home city
+        let several = parse(&MINIMAL.replace(
+            r#"city = "Berlin,DE""#,
+            r#"cities = ["Hamburg,DE", "Paris,FR"]"#,
+        ));
+        several.validate().unwrap();
+        assert_eq!(several.openweather.city_list(), ["Hamburg,DE", "Paris,FR"]);
+        assert_eq!(several.openweather.home_city(), "Hamburg,DE");
+    }
+
+    #[test]
+    fn test_city_configs_keep_coordinates_for_home() {
+        let secrets = parse(&MINIMAL.replace(
+            r#"city = "Berlin,DE""#,
+            "cities = [\"Berlin,DE\", \"Paris,FR\"]\nlat = 52.52\nlon = 13.40",
+        ));
+        let configs = secrets.openweather.city_configs();
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].city, "Berlin,DE");
+        assert_eq!(configs[0].coordinates(), Some((52.52, 13.40)));
+        assert_eq!(configs[1].city, "Paris,FR");
+        assert_eq!(configs[1].coordinates(), None);
+        assert!(configs.iter().all(|config| config.cities.is_empty()));
+    }
+
+    #[test]
+    fn test_validate_empty_city_in_list() {
+        assert!(
+            validate_error(r#"city = "Berlin,DE""#, r#"cities = ["Berlin,DE", " "]"#)
+                .contains("openweather.cities")
+        );
+    }
+
+    #[test]
+    fn test_validate_weekday() {
+        let with_weekday = |weekday: &str| {
+            parse(&format!(
+                "{}\n[display]\nweekday = \"{}\"\n",
+                MINIMAL, weekday
+            ))
+        };
+        assert_eq!(
+            with_weekday("de").display.weekday_lang(),
+            Some(WeekdayLang::German)
+        );
+        with_weekday("en").validate().unwrap();
+        assert!(parse(MINIMAL).display.weekday_lang().is_none());
+
+        let error = with_weekday("Monday").validate().unwrap_err().to_string();
+        assert!(error.contains("display.weekday"));
+    }
+
+    #[test]
+    fn test_validate_watchdog_timeout() {
+        let with_timeouts = |http: u64, watchdog: u64| {
+            let openweather = format!("city = \"Berlin,DE\"\nhttp_timeout_secs = {}", http);
+            parse(&format!(
+                "{}\n[watchdog]\ntimeout_secs = {}\n",
+                MINIMAL.replace(r#"city = "Berlin,DE""#, &openweather),
+                watchdog
+            ))
+            .validate()
+        };
+        with_timeouts(30, 90).unwrap();
+        let error = with_timeouts(30, 60).unwrap_err().to_string();
+        assert!(error.contains("watchdog.timeout_secs"));
+
+        // The slowest request must fit into the timeout
+        with_timeouts(120, 150).unwrap();
+        let error = with_timeouts(120, 120).unwrap_err().to_string();
+        assert!(error.contains("openweather.http_timeout_secs"));
+    }
+
+    #[test]
+    fn test_validate_broker_url() {
+        let with_mqtt = |url: &str| {
+            parse(&format!("{}\n[mqtt]\nbroker_url = \"{}\"\n", MINIMAL, url)).validate()
+        };
+        with_mqtt("mqtt://192.168.1.10:1883").unwrap();
+        with_mqtt("mqtts://broker.example.com:8883").unwrap();
+
+        let error = with_mqtt("broker.example.com:1883")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("mqtt.broker_url"));
+        let error = with_mqtt("").unwrap_err().to_string();
+        assert!(error.contains("mqtt.broker_url"));
+
+        // A disabled broker is not checked
+        parse(&format!("{}\n[mqtt]\nenabled = false\n", MINIMAL))
+            .validate()
+            .unwrap();
+    }
 }
@@ -0,0 +1,94 @@
+// stats.rs
+use core::sync::atomic::{AtomicU32, Ordering};
+use serde::Serialize;
+
+/// Counters of the weather fetches and Wi-Fi reconnects since boot
+///
+/// The main loop counts, the display and the MQTT report read a `StatsSnapshot`.
+/// The counters are not stored and start at zero after every reboot.
+pub struct Stats {
+    fetch_ok: AtomicU32,
+    fetch_err: AtomicU32,
+    wifi_reconnects: AtomicU32,
+}
+
+/// The counters at one point in time, published as JSON
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    pub fetch_ok: u32,
+    pub fetch_err: u32,
+    pub wifi_reconnects: u32,
+}
+
+impl Stats {
+    pub const fn new() -> Self {
+        Self {
+            fetch_ok: AtomicU32::new(0),
+            fetch_err: AtomicU32::new(0),
+            wifi_reconnects: AtomicU32::new(0),
+        }
+    }
+
+    /// A weather fetch delivered usable data
+    pub fn record_fetch_ok(&self) {
+        self.fetch_ok.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A weather fetch failed after all retries or returned unusable data
+    pub fn record_fetch_err(&self) {
+        self.fetch_err.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The lost Wi-Fi connection was restored
+    pub fn record_wifi_reconnect(&self) {
+        self.wifi_reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            fetch_ok: self.fetch_ok.load(Ordering::Relaxed),
+            fetch_err: self.fetch_err.load(Ordering::Relaxed),
+            wifi_reconnects: self.wifi_reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl StatsSnapshot {
+    /// Compact display text, e.g. "ok:123 err:4"
+    pub fn label(&self) -> String {
+        format!("ok:{} err:{}", self.fetch_ok, self.fetch_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters() {
+        let stats = Stats::new();
+        assert_eq!(stats.snapshot().label(), "ok:0 err:0");
+
+        for _ in 0..3 {
+            stats.record_fetch_ok();
+        }
+        stats.record_fetch_err();
+        stats.record_wifi_reconnect();
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.label(), "ok:3 err:1");
+        assert_eq!(snapshot.wifi_reconnects, 1);
+    }
+
+    #[test]
+    fn test_snapshot_json() {
+        let snapshot = StatsSnapshot {
+            fetch_ok: 123,
+            fetch_err: 4,
+            wifi_reconnects: 2,
+        };
+        assert_eq!(
+            serde_json::to_string(&snapshot).unwrap(),
+            r#"{"fetch_ok":123,"fetch_err":4,"wifi_reconnects":2}"#
+        );
+    }
+}
@@ -0,0 +1,104 @@
+// temperature.rs
+use crate::secrets::TempColorConfig;
+use embedded_graphics::pixelcolor::Rgb565;
+
+/// Formats a temperature for the display, e.g. "14.6°C", "+0.3°C" or "72°F"
+///
+/// Halves round away from zero (0.5 becomes 1), unlike `format!` which rounds them to even.
+/// A value that rounds to zero never gets a sign, so -0.04 is "0.0" instead of "-0.0".
+/// With `show_sign` positive values get a "+", which helps to tell them apart near freezing.
+pub fn format_temp(value: f32, unit: &str, decimals: usize, show_sign: bool) -> String {
+    let factor = 10f32.powi(decimals as i32);
+    let rounded = (value * factor).round() / factor;
+    // Adding 0.0 turns -0.0 into 0.0
+    let rounded = rounded + 0.0;
+    let sign = if show_sign && rounded > 0.0 { "+" } else { "" };
+    format!("{}{:.*}{}", sign, decimals, rounded, unit)
+}
+
+/// Converts a temperature of the OpenWeatherMap unit system to °C
+pub fn to_celsius(value: f32, units: &str) -> f32 {
+    match units {
+        "imperial" => (value - 32.0) * 5.0 / 9.0,
+        "standard" => value - 273.15,
+        _ => value,
+    }
+}
+
+/// Color of the temperature text: blue below `cold_below`, green below `warm_from`,
+/// orange up to `hot_above` and red above
+pub fn temp_color(celsius: f32, thresholds: &TempColorConfig) -> Rgb565 {
+    if celsius < thresholds.cold_below {
+        Rgb565::new(0, 40, 31)
+    } else if celsius < thresholds.warm_from {
+        Rgb565::new(0, 50, 0)
+    } else if celsius <= thresholds.hot_above {
+        Rgb565::new(31, 32, 0)
+    } else {
+        Rgb565::new(31, 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_temp() {
+        assert_eq!(format_temp(14.62, "°C", 1, false), "14.6°C");
+        assert_eq!(format_temp(14.62, "°C", 0, false), "15°C");
+        assert_eq!(format_temp(-3.5, "°C", 1, true), "-3.5°C");
+        assert_eq!(format_temp(0.3, "°C", 1, true), "+0.3°C");
+        assert_eq!(format_temp(71.6, "°F", 0, false), "72°F");
+        assert_eq!(format_temp(291.15, "K", 1, false), "291.2K");
+    }
+
+    #[test]
+    fn test_format_temp_near_zero() {
+        assert_eq!(format_temp(-0.04, "°C", 1, false), "0.0°C");
+        assert_eq!(format_temp(-0.04, "°C", 1, true), "0.0°C");
+        assert_eq!(format_temp(0.04, "°C", 1, true), "0.0°C");
+        assert_eq!(format_temp(-0.4, "°C", 0, true), "0°C");
+    }
+
+    #[test]
+    fn test_format_temp_rounds_halves_away_from_zero() {
+        assert_eq!(format_temp(0.5, "°C", 0, false), "1°C");
+        assert_eq!(format_temp(2.5, "°C", 0, true), "+3°C");
+        assert_eq!(format_temp(-0.5, "°C", 0, false), "-1°C");
+        assert_eq!(format_temp(0.25, "°C", 1, false), "0.3°C");
+    }
+
+    #[test]
+    fn test_to_celsius() {
+        assert_eq!(to_celsius(21.5, "metric"), 21.5);
+        assert_eq!(to_celsius(32.0, "imperial"), 0.0);
+        assert_eq!(to_celsius(86.0, "imperial"), 30.0);
+        assert!((to_celsius(273.15, "standard")).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_temp_color_boundaries() {
+        let thresholds = TempColorConfig::default();
+        let blue = temp_color(-0.1, &thresholds);
+        let green = temp_color(0.0, &thresholds);
+        let orange = temp_color(20.0, &thresholds);
+        let red = temp_color(30.1, &thresholds);
+        assert_eq!(temp_color(19.9, &thresholds), green);
+        assert_eq!(temp_color(30.0, &thresholds), orange);
+        assert_eq!(
+            [blue, green, orange, red]
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            4
+        );
+
+        // Fahrenheit is compared in °C: 68°F is 20°C
+        assert_eq!(
+            temp_color(to_celsius(68.0, "imperial"), &thresholds),
+            orange
+        );
+        assert_eq!(temp_color(to_celsius(31.0, "imperial"), &thresholds), blue);
+    }
+}
@@ -0,0 +1,42 @@
+// theme.rs
+use embedded_graphics::pixelcolor::Rgb565;
+
+/// Parses a color written as "#RRGGBB" and reduces it to the 5-6-5 bits of the display
+pub fn parse_rgb565(s: &str) -> anyhow::Result<Rgb565> {
+    let hex = s
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| anyhow::anyhow!("Invalid color '{}', expected \"#RRGGBB\"", s))?;
+    let rgb = u32::from_str_radix(hex, 16)?;
+    let channel = |shift: u32| ((rgb >> shift) & 0xFF) as u8;
+    Ok(Rgb565::new(
+        channel(16) >> 3,
+        channel(8) >> 2,
+        channel(0) >> 3,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::pixelcolor::RgbColor;
+
+    #[test]
+    fn test_parse_rgb565() {
+        assert_eq!(parse_rgb565("#FFFFFF").unwrap(), Rgb565::WHITE);
+        assert_eq!(parse_rgb565("#000000").unwrap(), Rgb565::BLACK);
+        assert_eq!(parse_rgb565("#ff0000").unwrap(), Rgb565::RED);
+        // The low bits of each channel are dropped
+        assert_eq!(parse_rgb565("#F0F0F0").unwrap(), Rgb565::new(30, 60, 30));
+    }
+
+    #[test]
+    fn test_parse_rgb565_invalid() {
+        for input in [
+            "", "#", "#FFF", "#FFFFF", "#FFFFFFF", "FFFFFF", "#GGGGGG", "#+FFFFF",
+        ] {
+            let error = parse_rgb565(input).unwrap_err().to_string();
+            assert!(error.contains("#RRGGBB"), "{}: {}", input, error);
+        }
+    }
+}
@@ -1,64 +1,201 @@
 // time_utils.rs
-use chrono::{Datelike, TimeZone, Timelike, Utc};
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike, Utc, Weekday};
 //use log::*;
 
-/// Calculates whether a given time is in Daylight Saving Time (CEST).
-/// Summer: Last Sunday on March 2:00 UTC to last Sunday on October 3:00 UTC
-pub fn is_dst(year: i32, month: u32, day: u32, hour: u32) -> bool {
-    // Last Sunday in March (start of CEST)
-    let march_last_sunday_day = 31 - ((5 * year / 4 + 4) % 7);
-
-    // Last Sunday in October (end of CEST)
-    let october_last_sunday_day = 31 - ((5 * year / 4 + 1) % 7);
-
-    match month {
-        1 | 2 => false, // January, February: always CET
-        3 => {
-            // March: CEST from last Sunday 2:00 UTC
-            if day < march_last_sunday_day as u32 {
-                false
-            } else if day > march_last_sunday_day as u32 {
-                true
-            } else {
-                // On the changeover day: from 2:00 UTC
-                hour >= 2
-            }
+/// Describes a DST changeover as "the nth weekday of a month at a given UTC hour".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransitionRule {
+    /// The month of the changeover (1-12).
+    pub month: u32,
+    /// The occurrence of the weekday within the month (1-4, 5 = last).
+    pub week: u32,
+    /// The weekday of the changeover.
+    pub weekday: Weekday,
+    /// The hour (UTC) at which the changeover happens.
+    pub utc_hour: u32,
+}
+
+impl TransitionRule {
+    /// Returns the day of the month on which the changeover happens in `year`.
+    pub fn day_in(&self, year: i32) -> u32 {
+        let date = if self.week >= 5 {
+            last_weekday_of_month(year, self.month, self.weekday)
+        } else {
+            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, self.week as u8)
+        };
+        date.map(|date| date.day()).unwrap_or(1)
+    }
+}
+
+/// Finds the last occurrence of `weekday` in the given month.
+/// Walks backwards from the last day of the month, so it is exact for every year.
+pub fn last_weekday_of_month(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+
+    // The day before the first of the next month is the last day of this month
+    let mut date = NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()?;
+    while date.weekday() != weekday {
+        date = date.pred_opt()?;
+    }
+    Some(date)
+}
+
+/// The start and end of daylight saving time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DstRules {
+    /// When daylight saving time starts.
+    pub start: TransitionRule,
+    /// When daylight saving time ends.
+    pub end: TransitionRule,
+    /// The additional offset during daylight saving time in seconds.
+    pub dst_offset_secs: i64,
+}
+
+/// Describes a time zone by its base UTC offset and optional DST rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeZoneRules {
+    /// The base (standard time) UTC offset in seconds.
+    pub base_offset_secs: i64,
+    /// The abbreviation during standard time, e.g. "CET".
+    pub std_abbr: &'static str,
+    /// The abbreviation during daylight saving time, e.g. "CEST".
+    pub dst_abbr: &'static str,
+    /// The DST rules, `None` if the zone does not observe DST.
+    pub dst: Option<DstRules>,
+}
+
+/// Berlin (CET/CEST).
+/// Summer: Last Sunday on March 1:00 UTC to last Sunday on October 1:00 UTC
+pub const BERLIN: TimeZoneRules = TimeZoneRules {
+    base_offset_secs: 3600,
+    std_abbr: "CET",
+    dst_abbr: "CEST",
+    dst: Some(DstRules {
+        start: TransitionRule {
+            month: 3,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        end: TransitionRule {
+            month: 10,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        dst_offset_secs: 3600,
+    }),
+};
+
+/// London (GMT/BST).
+/// Summer: Last Sunday on March 1:00 UTC to last Sunday on October 1:00 UTC
+/// Only used by the tests of the generic rules, the apps show Berlin time.
+#[cfg(test)]
+pub const LONDON: TimeZoneRules = TimeZoneRules {
+    base_offset_secs: 0,
+    std_abbr: "GMT",
+    dst_abbr: "BST",
+    dst: Some(DstRules {
+        start: TransitionRule {
+            month: 3,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        end: TransitionRule {
+            month: 10,
+            week: 5,
+            weekday: Weekday::Sun,
+            utc_hour: 1,
+        },
+        dst_offset_secs: 3600,
+    }),
+};
+
+/// US Eastern (EST/EDT).
+/// Summer: Second Sunday in March 7:00 UTC to first Sunday in November 6:00 UTC
+/// Only used by the tests of the generic rules, the apps show Berlin time.
+#[cfg(test)]
+pub const US_EASTERN: TimeZoneRules = TimeZoneRules {
+    base_offset_secs: -5 * 3600,
+    std_abbr: "EST",
+    dst_abbr: "EDT",
+    dst: Some(DstRules {
+        start: TransitionRule {
+            month: 3,
+            week: 2,
+            weekday: Weekday::Sun,
+            utc_hour: 7,
+        },
+        end: TransitionRule {
+            month: 11,
+            week: 1,
+            weekday: Weekday::Sun,
+            utc_hour: 6,
+        },
+        dst_offset_secs: 3600,
+    }),
+};
+
+impl TimeZoneRules {
+    /// Calculates whether a given UTC time is in Daylight Saving Time.
+    pub fn is_dst(&self, year: i32, month: u32, day: u32, hour: u32) -> bool {
+        let dst = match self.dst {
+            Some(dst) => dst,
+            None => return false,
+        };
+
+        let now = (month, day, hour);
+        let start = (dst.start.month, dst.start.day_in(year), dst.start.utc_hour);
+        let end = (dst.end.month, dst.end.day_in(year), dst.end.utc_hour);
+
+        if start <= end {
+            // Northern hemisphere: DST lies within the calendar year
+            now >= start && now < end
+        } else {
+            // Southern hemisphere: DST spans the turn of the year
+            now >= start || now < end
         }
-        4..=9 => true, // April to September: always CEST
-        10 => {
-            // October: CEST until last Sunday 3:00 UTC
-            if day < october_last_sunday_day as u32 {
-                true
-            } else if day > october_last_sunday_day as u32 {
-                false
-            } else {
-                // On the changeover day: until 3:00 UTC
-                hour < 3
+    }
+
+    /// Returns the total UTC offset in seconds for the given UTC time.
+    pub fn offset_secs(&self, year: i32, month: u32, day: u32, hour: u32) -> i64 {
+        match self.dst {
+            Some(dst) if self.is_dst(year, month, day, hour) => {
+                self.base_offset_secs + dst.dst_offset_secs
             }
+            _ => self.base_offset_secs,
+        }
+    }
+
+    /// Returns the time zone abbreviation for the given UTC time.
+    pub fn abbreviation(&self, year: i32, month: u32, day: u32, hour: u32) -> &'static str {
+        if self.is_dst(year, month, day, hour) {
+            self.dst_abbr
+        } else {
+            self.std_abbr
         }
-        11 | 12 => false, // November, December: always CET
-        _ => false,
     }
 }
 
-/// Converts UTC time to Berlin time (CET/CEST)
-pub fn utc_to_berlin(utc_timestamp: i64) -> (i32, u32, u32, u32, u32, u32) {
+/// Converts UTC time to local time according to the given time zone rules
+pub fn utc_to_local(utc_timestamp: i64, tz: TimeZoneRules) -> (i32, u32, u32, u32, u32, u32) {
     let utc_time = Utc.timestamp_opt(utc_timestamp, 0).unwrap();
 
-    let year = utc_time.year();
-    let month = utc_time.month();
-    let day = utc_time.day();
-    let hour = utc_time.hour();
-
-    // Check if daylight saving time is active
-    let offset_hours = if is_dst(year, month, day, hour) {
-        2 // CEST: UTC+2
-    } else {
-        1 // CET: UTC+1
-    };
+    // Determine the offset (including DST) for this instant
+    let offset_secs = tz.offset_secs(
+        utc_time.year(),
+        utc_time.month(),
+        utc_time.day(),
+        utc_time.hour(),
+    );
 
     // Add offset
-    let local_timestamp = utc_timestamp + (offset_hours * 3600);
+    let local_timestamp = utc_timestamp + offset_secs;
     let local_time = Utc.timestamp_opt(local_timestamp, 0).unwrap();
 
     (
@@ -71,23 +208,174 @@ pub fn utc_to_berlin(utc_timestamp: i64) -> (i32, u32, u32, u32, u32, u32) {
     )
 }
 
+/// Converts UTC time to Berlin time (CET/CEST)
+pub fn utc_to_berlin(utc_timestamp: i64) -> (i32, u32, u32, u32, u32, u32) {
+    utc_to_local(utc_timestamp, BERLIN)
+}
+
 /// Formats the time as a string "HH:MM:SS"
 pub fn format_time(hour: u32, minute: u32, second: u32) -> String {
     format!("{:02}:{:02}:{:02}", hour, minute, second)
 }
 
-/// Formats the date as a string "DD.MM.YYYY"
-pub fn format_date(day: u32, month: u32, year: i32) -> String {
-    format!("{:02}.{:02}.{}", day, month, year)
+/// Formats the unsynchronized system clock as "HH:MM:SS", without a time zone
+/// After a cold boot the RTC starts at the epoch, so the time counts up from 00:00:00.
+/// A soft restart keeps the RTC running and the time continues from its value.
+pub fn format_unsynced_time(utc_timestamp: u64) -> String {
+    let secs = utc_timestamp % 86_400;
+    format_time(
+        (secs / 3600) as u32,
+        (secs / 60 % 60) as u32,
+        (secs % 60) as u32,
+    )
+}
+
+/// Order and separators of a formatted date, all variants are 10 characters wide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateFormat {
+    /// "DD.MM.YYYY"
+    #[default]
+    German,
+    /// "MM/DD/YYYY"
+    Us,
+    /// "YYYY-MM-DD"
+    Iso,
 }
 
-/// Returns the current time zone as a string
-pub fn get_timezone_str(year: i32, month: u32, day: u32, hour: u32) -> &'static str {
-    if is_dst(year, month, day, hour) {
-        "CEST"
+impl DateFormat {
+    /// Parses a `date_format` config value: "de", "us" or "iso"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "de" => Some(Self::German),
+            "us" => Some(Self::Us),
+            "iso" => Some(Self::Iso),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the date in the given format, day and month zero-padded
+pub fn format_date_with(day: u32, month: u32, year: i32, format: DateFormat) -> String {
+    match format {
+        DateFormat::German => format!("{:02}.{:02}.{}", day, month, year),
+        DateFormat::Us => format!("{:02}/{:02}/{}", month, day, year),
+        DateFormat::Iso => format!("{}-{:02}-{:02}", year, month, day),
+    }
+}
+
+/// Language used for weekday abbreviations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeekdayLang {
+    German,
+    English,
+}
+
+impl WeekdayLang {
+    /// Parses a `weekday` config value: "de" or "en"
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "de" => Some(Self::German),
+            "en" => Some(Self::English),
+            _ => None,
+        }
+    }
+}
+
+/// Three-letter German weekday abbreviations, starting on Monday
+const WEEKDAYS_DE: [&str; 7] = ["Mon", "Die", "Mit", "Don", "Fre", "Sam", "Son"];
+
+/// Three-letter English weekday abbreviations, starting on Monday
+const WEEKDAYS_EN: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Returns the three-letter weekday abbreviation for a date, `None` for invalid dates
+pub fn weekday_abbr(day: u32, month: u32, year: i32, lang: WeekdayLang) -> Option<&'static str> {
+    let weekday = NaiveDate::from_ymd_opt(year, month, day)?.weekday();
+    let table = match lang {
+        WeekdayLang::German => &WEEKDAYS_DE,
+        WeekdayLang::English => &WEEKDAYS_EN,
+    };
+    Some(table[weekday.num_days_from_monday() as usize])
+}
+
+/// Formats the date with the weekday in front, e.g. "Mon 13.10.2025"
+pub fn format_date_long(
+    day: u32,
+    month: u32,
+    year: i32,
+    lang: WeekdayLang,
+    format: DateFormat,
+) -> String {
+    let date = format_date_with(day, month, year, format);
+    match weekday_abbr(day, month, year, lang) {
+        Some(weekday) => format!("{} {}", weekday, date),
+        None => date,
+    }
+}
+
+/// Formats the time of a movement event for the event list.
+/// Events from the current (Berlin) day show "HH:MM:SS", older ones "DD.MM HH:MM";
+/// both fit into one column of the two-column list (11 characters at most).
+pub fn format_event_time(event_utc: i64, now_utc: i64) -> String {
+    let (year, month, day, hour, minute, second) = utc_to_berlin(event_utc);
+    let (now_year, now_month, now_day, ..) = utc_to_berlin(now_utc);
+
+    if (year, month, day) == (now_year, now_month, now_day) {
+        format_time(hour, minute, second)
     } else {
-        "CET"
+        format!("{:02}.{:02} {:02}:{:02}", day, month, hour, minute)
+    }
+}
+
+/// Returns whether the sun is up, given the sunrise and sunset of the current day
+/// (all UTC timestamps, as reported by OpenWeatherMap).
+/// `None` if the times are unknown (0), e.g. during polar day or night.
+pub fn is_daytime(utc_timestamp: i64, sunrise: i64, sunset: i64) -> Option<bool> {
+    if sunrise == 0 || sunset == 0 {
+        return None;
     }
+    Some((sunrise..sunset).contains(&utc_timestamp))
+}
+
+/// Formats the time since boot for the status line, e.g. "up 45m", "up 3h12m" or "up 2d05h"
+pub fn format_uptime(uptime_secs: u64) -> String {
+    let minutes = uptime_secs / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("up {}d{:02}h", days, hours)
+    } else if hours > 0 {
+        format!("up {}h{:02}m", hours, minutes)
+    } else {
+        format!("up {}m", minutes)
+    }
+}
+
+/// Formats the minutes since the last successful weather fetch, "fetch --" before the first one
+pub fn format_fetch_age(age_secs: Option<u64>) -> String {
+    match age_secs {
+        Some(age_secs) => format!("fetch {}m ago", age_secs / 60),
+        None => "fetch --".to_string(),
+    }
+}
+
+/// Formats the observation time of the weather data (OWM `dt`) as "obs HH:MM" in Berlin time,
+/// empty if the response had no time
+pub fn format_observation_time(dt: i64) -> String {
+    if dt <= 0 {
+        return String::new();
+    }
+    let (_, _, _, hour, minute, _) = utc_to_berlin(dt);
+    format!("obs {:02}:{:02}", hour, minute)
+}
+
+/// Drift of the clock in ppm, from how far it was ahead of NTP (`offset_ms`, negative when
+/// behind) after running `elapsed_secs` since the previous sync. `None` without a previous sync.
+pub fn clock_drift_ppm(offset_ms: i64, elapsed_secs: u64) -> Option<f64> {
+    (elapsed_secs > 0).then(|| offset_ms as f64 * 1000.0 / elapsed_secs as f64)
+}
+
+/// Returns the current time zone as a string
+pub fn get_timezone_str(year: i32, month: u32, day: u32, hour: u32) -> &'static str {
+    BERLIN.abbreviation(year, month, day, hour)
 }
 
 #[cfg(test)]
@@ -97,18 +385,242 @@ mod tests {
     #[test]
     fn test_dst_calculation() {
         // March 15, 2024, 10:00 UTC -> CET (before changeover)
-        assert_eq!(is_dst(2024, 3, 15, 10), false);
+        assert_eq!(BERLIN.is_dst(2024, 3, 15, 10), false);
 
         // March 31, 2024, 03:00 UTC -> CEST (after changeover)
-        assert_eq!(is_dst(2024, 3, 31, 3), true);
+        assert_eq!(BERLIN.is_dst(2024, 3, 31, 3), true);
 
         // July 15, 2024, 12:00 UTC -> CEST
-        assert_eq!(is_dst(2024, 7, 15, 12), true);
+        assert_eq!(BERLIN.is_dst(2024, 7, 15, 12), true);
 
         // October 27, 2024, 02:00 UTC -> CET (after changeover)
-        assert_eq!(is_dst(2024, 10, 27, 4), false);
+        assert_eq!(BERLIN.is_dst(2024, 10, 27, 4), false);
 
         // December 15, 2024, 18:00 UTC -> CET
-        assert_eq!(is_dst(2024, 12, 15, 18), false);
+        assert_eq!(BERLIN.is_dst(2024, 12, 15, 18), false);
+    }
+
+    #[test]
+    fn test_last_sunday_regression() {
+        // Known changeover dates (last Sunday in March / October)
+        let known = [
+            (2023, 26, 29),
+            (2024, 31, 27),
+            (2027, 28, 31),
+            // 2100 is not a leap year, the Gauss approximation is off by one day here
+            (2100, 28, 31),
+        ];
+
+        for (year, march_day, october_day) in known {
+            assert_eq!(
+                last_weekday_of_month(year, 3, Weekday::Sun).map(|d| d.day()),
+                Some(march_day)
+            );
+            assert_eq!(
+                last_weekday_of_month(year, 10, Weekday::Sun).map(|d| d.day()),
+                Some(october_day)
+            );
+
+            // CET before, CEST after the March changeover
+            assert_eq!(BERLIN.is_dst(year, 3, march_day - 1, 12), false);
+            assert_eq!(BERLIN.is_dst(year, 3, march_day, 2), true);
+
+            // CEST before, CET after the October changeover
+            assert_eq!(BERLIN.is_dst(year, 10, october_day - 1, 12), true);
+            assert_eq!(BERLIN.is_dst(year, 10, october_day, 3), false);
+        }
+
+        // December must not overflow into the next year
+        assert_eq!(
+            last_weekday_of_month(2024, 12, Weekday::Tue).map(|d| d.day()),
+            Some(31)
+        );
+    }
+
+    #[test]
+    fn test_us_eastern_dst_calculation() {
+        // March 10, 2024 (second Sunday), 06:00 UTC -> EST (before changeover)
+        assert_eq!(US_EASTERN.is_dst(2024, 3, 10, 6), false);
+
+        // March 10, 2024 (second Sunday), 07:00 UTC -> EDT (after changeover)
+        assert_eq!(US_EASTERN.is_dst(2024, 3, 10, 7), true);
+
+        // March 3, 2024 (first Sunday) -> EST, Europe-style rules would not apply here
+        assert_eq!(US_EASTERN.is_dst(2024, 3, 3, 12), false);
+
+        // November 3, 2024 (first Sunday), 05:00 UTC -> EDT (before changeover)
+        assert_eq!(US_EASTERN.is_dst(2024, 11, 3, 5), true);
+
+        // November 3, 2024 (first Sunday), 06:00 UTC -> EST (after changeover)
+        assert_eq!(US_EASTERN.is_dst(2024, 11, 3, 6), false);
+    }
+
+    #[test]
+    fn test_eu_changeover_at_one_utc() {
+        // 2024-03-31: 00:59:59 UTC is 01:59:59 CET, one second later it is 03:00:00 CEST
+        assert_eq!(utc_to_berlin(1_711_846_799), (2024, 3, 31, 1, 59, 59));
+        assert_eq!(utc_to_berlin(1_711_846_800), (2024, 3, 31, 3, 0, 0));
+
+        // 2024-10-27: 00:59:59 UTC is 02:59:59 CEST, one second later it is 02:00:00 CET
+        assert_eq!(utc_to_berlin(1_729_990_799), (2024, 10, 27, 2, 59, 59));
+        assert_eq!(utc_to_berlin(1_729_990_800), (2024, 10, 27, 2, 0, 0));
+
+        // The hours from 01:00 to 03:00 UTC already follow the new offset
+        for (month, day) in [(3, 31), (10, 27)] {
+            let dst_before = month == 10;
+            assert_eq!(BERLIN.is_dst(2024, month, day, 0), dst_before);
+            for hour in 1..=3 {
+                assert_eq!(BERLIN.is_dst(2024, month, day, hour), !dst_before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_utc_to_local() {
+        // 2024-07-15 12:00:00 UTC
+        let summer = 1_721_044_800;
+        assert_eq!(utc_to_local(summer, BERLIN), (2024, 7, 15, 14, 0, 0));
+        assert_eq!(utc_to_local(summer, LONDON), (2024, 7, 15, 13, 0, 0));
+        assert_eq!(utc_to_local(summer, US_EASTERN), (2024, 7, 15, 8, 0, 0));
+
+        // 2024-01-15 03:00:00 UTC
+        let winter = 1_705_287_600;
+        assert_eq!(utc_to_local(winter, BERLIN), (2024, 1, 15, 4, 0, 0));
+        assert_eq!(utc_to_local(winter, LONDON), (2024, 1, 15, 3, 0, 0));
+        assert_eq!(utc_to_local(winter, US_EASTERN), (2024, 1, 14, 22, 0, 0));
+
+        // The thin wrapper must agree with the Berlin preset
+        assert_eq!(utc_to_berlin(summer), utc_to_local(summer, BERLIN));
+    }
+
+    #[test]
+    fn test_format_date_with() {
+        assert_eq!(
+            format_date_with(5, 3, 2025, DateFormat::default()),
+            "05.03.2025"
+        );
+        assert_eq!(format_date_with(5, 3, 2025, DateFormat::Us), "03/05/2025");
+        assert_eq!(format_date_with(5, 3, 2025, DateFormat::Iso), "2025-03-05");
+    }
+
+    #[test]
+    fn test_date_format_parse() {
+        assert_eq!(DateFormat::parse("de"), Some(DateFormat::German));
+        assert_eq!(DateFormat::parse("us"), Some(DateFormat::Us));
+        assert_eq!(DateFormat::parse("iso"), Some(DateFormat::Iso));
+        assert_eq!(DateFormat::parse("DD.MM.YYYY"), None);
+    }
+
+    #[test]
+    fn test_format_date_long() {
+        // Monday, October 13, 2025
+        assert_eq!(
+            format_date_long(13, 10, 2025, WeekdayLang::English, DateFormat::German),
+            "Mon 13.10.2025"
+        );
+
+        // Thursday, February 29, 2024 (leap day)
+        assert_eq!(
+            format_date_long(29, 2, 2024, WeekdayLang::English, DateFormat::German),
+            "Thu 29.02.2024"
+        );
+        assert_eq!(
+            format_date_long(29, 2, 2024, WeekdayLang::German, DateFormat::German),
+            "Don 29.02.2024"
+        );
+
+        // Saturday, January 1, 2000
+        assert_eq!(
+            format_date_long(1, 1, 2000, WeekdayLang::German, DateFormat::German),
+            "Sam 01.01.2000"
+        );
+
+        // The date follows the configured format
+        assert_eq!(
+            format_date_long(13, 10, 2025, WeekdayLang::English, DateFormat::Us),
+            "Mon 10/13/2025"
+        );
+
+        // Invalid dates fall back to the short format
+        assert_eq!(
+            format_date_long(30, 2, 2024, WeekdayLang::English, DateFormat::German),
+            "30.02.2024"
+        );
+    }
+
+    #[test]
+    fn test_weekday_lang_parse() {
+        assert_eq!(WeekdayLang::parse("de"), Some(WeekdayLang::German));
+        assert_eq!(WeekdayLang::parse("en"), Some(WeekdayLang::English));
+        assert_eq!(WeekdayLang::parse("fr"), None);
+    }
+
+    #[test]
+    fn test_is_daytime() {
+        // Berlin, 2024-06-21: sunrise 02:43 UTC, sunset 19:33 UTC
+        let sunrise = 1718937780;
+        let sunset = 1718998380;
+
+        assert_eq!(is_daytime(sunrise - 1, sunrise, sunset), Some(false));
+        assert_eq!(is_daytime(sunrise, sunrise, sunset), Some(true));
+        assert_eq!(is_daytime(sunset - 1, sunrise, sunset), Some(true));
+        assert_eq!(is_daytime(sunset, sunrise, sunset), Some(false));
+
+        // Unknown sunrise/sunset
+        assert_eq!(is_daytime(sunrise, 0, 0), None);
+    }
+
+    #[test]
+    fn test_format_event_time() {
+        // 2025-10-13 20:14:03 UTC = 22:14:03 CEST
+        let event = 1760386443;
+
+        // Same day in Berlin
+        assert_eq!(format_event_time(event, event), "22:14:03");
+        assert_eq!(format_event_time(event, event + 3600), "22:14:03");
+
+        // 2025-10-14 00:30 CEST: the event is from yesterday
+        assert_eq!(format_event_time(event, event + 8157), "13.10 22:14");
+        assert!(format_event_time(event, event + 8157).len() <= 11);
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(59), "up 0m");
+        assert_eq!(format_uptime(45 * 60), "up 45m");
+        assert_eq!(format_uptime(3 * 3600 + 12 * 60 + 59), "up 3h12m");
+        assert_eq!(format_uptime(2 * 86400 + 5 * 3600), "up 2d05h");
+    }
+
+    #[test]
+    fn test_format_unsynced_time() {
+        assert_eq!(format_unsynced_time(0), "00:00:00");
+        assert_eq!(format_unsynced_time(3 * 3600 + 25 * 60 + 7), "03:25:07");
+        // Past one day the time wraps around
+        assert_eq!(format_unsynced_time(86_400 + 61), "00:01:01");
+    }
+
+    #[test]
+    fn test_format_fetch_age() {
+        assert_eq!(format_fetch_age(None), "fetch --");
+        assert_eq!(format_fetch_age(Some(30)), "fetch 0m ago");
+        assert_eq!(format_fetch_age(Some(14 * 60 + 5)), "fetch 14m ago");
+    }
+
+    #[test]
+    fn test_format_observation_time() {
+        // 2024-10-15 13:00 UTC, summer time
+        assert_eq!(format_observation_time(1728997200), "obs 15:00");
+        // 2024-01-01 00:32 UTC, winter time
+        assert_eq!(format_observation_time(1704069120), "obs 01:32");
+        assert_eq!(format_observation_time(0), "");
+    }
+
+    #[test]
+    fn test_clock_drift_ppm() {
+        // 432 ms ahead after 6 hours
+        assert_eq!(clock_drift_ppm(432, 6 * 3600), Some(20.0));
+        assert_eq!(clock_drift_ppm(-216, 6 * 3600), Some(-10.0));
+        assert_eq!(clock_drift_ppm(100, 0), None);
     }
 }
@@ -0,0 +1,60 @@
+// trend.rs
+
+/// Change of the temperature since the previous reading
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TempTrend {
+    Rising,
+    Falling,
+    Steady,
+}
+
+/// Changes up to this size (in °C or K) count as steady, so that rounding noise
+/// of the API does not flip the arrow on every fetch
+pub const TREND_DEADBAND: f32 = 0.2;
+
+/// Returns the deadband in the configured unit system, 0.2°C are 0.36°F
+pub fn deadband_for_units(units: &str) -> f32 {
+    match units {
+        "imperial" => TREND_DEADBAND * 1.8,
+        _ => TREND_DEADBAND,
+    }
+}
+
+/// Compares two readings in the same unit; a difference up to `deadband` is steady
+pub fn temp_trend(previous: f32, current: f32, deadband: f32) -> TempTrend {
+    let delta = current - previous;
+    if delta > deadband {
+        TempTrend::Rising
+    } else if delta < -deadband {
+        TempTrend::Falling
+    } else {
+        TempTrend::Steady
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_trend() {
+        assert_eq!(temp_trend(14.6, 15.1, TREND_DEADBAND), TempTrend::Rising);
+        assert_eq!(temp_trend(14.6, 13.9, TREND_DEADBAND), TempTrend::Falling);
+        assert_eq!(temp_trend(14.6, 14.6, TREND_DEADBAND), TempTrend::Steady);
+
+        // Inside the deadband in both directions
+        assert_eq!(temp_trend(14.6, 14.75, TREND_DEADBAND), TempTrend::Steady);
+        assert_eq!(temp_trend(14.6, 14.45, TREND_DEADBAND), TempTrend::Steady);
+        assert_eq!(temp_trend(-0.1, 0.2, TREND_DEADBAND), TempTrend::Rising);
+    }
+
+    #[test]
+    fn test_deadband_for_units() {
+        assert_eq!(deadband_for_units("metric"), TREND_DEADBAND);
+        assert_eq!(deadband_for_units("standard"), TREND_DEADBAND);
+        // 0.3°F is less than 0.2°C
+        let deadband = deadband_for_units("imperial");
+        assert_eq!(temp_trend(58.3, 58.6, deadband), TempTrend::Steady);
+        assert_eq!(temp_trend(58.3, 58.7, deadband), TempTrend::Rising);
+    }
+}
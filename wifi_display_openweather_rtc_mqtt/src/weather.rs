@@ -0,0 +1,272 @@
+// weather.rs
+use serde::{Deserialize, Serialize};
+use weather_common::http_utils::HttpStatusError;
+
+/// Complete weather response from OpenWeatherMap API
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeatherResponse {
+    pub weather: Vec<Weather>,
+    pub main: Main,
+    /// Missing in some station responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wind: Option<Wind>,
+    /// Missing in some station responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clouds: Option<Clouds>,
+    pub name: String,
+    /// Time of the measurement (UTC timestamp), 0 if missing
+    #[serde(default)]
+    pub dt: i64,
+    #[serde(default)]
+    pub sys: Sys,
+}
+
+/// Weather condition details (description and icon code)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Weather {
+    pub description: String,
+    pub icon: String,
+}
+
+/// Main weather parameters (temperature and humidity)
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Main {
+    pub temp: f32,
+    /// Missing in some station responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub humidity: Option<i32>,
+}
+
+/// Wind information
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Wind {
+    /// In m/s, or mph with imperial units, see `speed_unit`
+    pub speed: f32,
+}
+
+/// Cloud cover
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Clouds {
+    /// Cloudiness in percent
+    pub all: i32,
+}
+
+/// Location details of the response
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Sys {
+    /// ISO 3166 country code of the resolved city, e.g. "US"
+    #[serde(default)]
+    pub country: String,
+    /// Sunrise of the current day (UTC timestamp)
+    #[serde(default)]
+    pub sunrise: i64,
+    /// Sunset of the current day (UTC timestamp)
+    #[serde(default)]
+    pub sunset: i64,
+}
+
+/// Error body returned by OpenWeatherMap for non-200 responses
+/// `cod` is sent as a number for some errors and as a string for others
+#[derive(Deserialize, Debug)]
+pub struct OwmError {
+    #[serde(deserialize_with = "deserialize_owm_code")]
+    pub cod: u16,
+    pub message: String,
+}
+
+impl OwmError {
+    /// Builds the error from a non-200 response, falling back to the HTTP status
+    /// if the body is not an OWM error object
+    fn from_response(status: u16, body: &str) -> Self {
+        serde_json::from_str(body).unwrap_or_else(|_| OwmError {
+            cod: status,
+            message: "unexpected response".to_string(),
+        })
+    }
+
+    /// Replaces an `HttpStatusError` with the OWM error object from its body,
+    /// other errors are passed through unchanged
+    pub fn from_http_error(error: anyhow::Error) -> anyhow::Error {
+        match error.downcast::<HttpStatusError>() {
+            Ok(http_error) => Self::from_response(http_error.status, &http_error.body).into(),
+            Err(error) => error,
+        }
+    }
+}
+
+impl std::fmt::Display for OwmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OWM {}: {}", self.cod, self.message)
+    }
+}
+
+impl std::error::Error for OwmError {}
+
+/// Check whether a weather fetch error is worth retrying
+/// Network errors, timeouts and 5xx responses are transient;
+/// 4xx responses (e.g. 401 bad API key, 404 unknown city) and parse errors are not.
+pub fn is_transient_error(error: &anyhow::Error) -> bool {
+    if let Some(owm_error) = error.downcast_ref::<OwmError>() {
+        return owm_error.cod >= 500;
+    }
+    error.downcast_ref::<serde_json::Error>().is_none()
+}
+
+/// Accepts the OWM `cod` field as either a number or a numeric string
+fn deserialize_owm_code<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Code {
+        Number(u16),
+        Text(String),
+    }
+
+    match Code::deserialize(deserializer)? {
+        Code::Number(code) => Ok(code),
+        Code::Text(code) => code.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parses the body of the OpenWeatherMap `/data/2.5/weather` endpoint
+///
+/// # Returns
+/// * `Ok(WeatherResponse)` - The weather data
+/// * `Err` - `OwmError` if the body is an error object, otherwise the `serde_json::Error`
+pub fn parse_weather(body: &str) -> anyhow::Result<WeatherResponse> {
+    match serde_json::from_str(body) {
+        Ok(weather) => Ok(weather),
+        // An error object instead of weather data, e.g. `{"cod":"404","message":"city not found"}`
+        Err(e) => match serde_json::from_str::<OwmError>(body) {
+            Ok(owm_error) => Err(owm_error.into()),
+            Err(_) => Err(e.into()),
+        },
+    }
+}
+
+/// Returns the label of the wind speed OpenWeatherMap sends for a unit system
+/// Only imperial units switch to mph, metric and standard both use m/s.
+pub fn speed_unit(units: &str) -> &'static str {
+    match units {
+        "imperial" => "mph",
+        _ => "m/s",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A captured response for Berlin, shortened to the usual top-level fields
+    const BERLIN: &str = r#"{
+        "coord":{"lon":13.4105,"lat":52.5244},
+        "weather":[{"id":803,"main":"Clouds","description":"broken clouds","icon":"04d"}],
+        "base":"stations",
+        "main":{"temp":14.62,"feels_like":13.93,"temp_min":13.3,"temp_max":15.57,"pressure":1021,"humidity":72},
+        "visibility":10000,
+        "wind":{"speed":4.12,"deg":250},
+        "clouds":{"all":75},
+        "dt":1728997200,
+        "sys":{"type":2,"id":2011538,"country":"DE","sunrise":1728970449,"sunset":1729008402},
+        "timezone":7200,"id":2950159,"name":"Berlin","cod":200
+    }"#;
+
+    #[test]
+    fn test_parse_weather() {
+        let weather = parse_weather(BERLIN).unwrap();
+        assert_eq!(weather.name, "Berlin");
+        assert_eq!(weather.main.temp, 14.62);
+        assert_eq!(weather.main.humidity, Some(72));
+        assert_eq!(weather.wind.map(|wind| wind.speed), Some(4.12));
+        assert_eq!(weather.clouds.map(|clouds| clouds.all), Some(75));
+        assert_eq!(weather.dt, 1728997200);
+        assert_eq!(weather.sys.country, "DE");
+        assert_eq!(weather.sys.sunrise, 1728970449);
+        assert_eq!(weather.weather[0].icon, "04d");
+    }
+
+    #[test]
+    fn test_parse_weather_error_cod() {
+        // `cod` is a string for 404 and a number for 401
+        let error = parse_weather(r#"{"cod":"404","message":"city not found"}"#).unwrap_err();
+        let owm_error = error.downcast_ref::<OwmError>().unwrap();
+        assert_eq!(owm_error.cod, 404);
+        assert_eq!(owm_error.message, "city not found");
+
+        let error = parse_weather(r#"{"cod":401,"message":"Invalid API key"}"#).unwrap_err();
+        assert_eq!(error.downcast_ref::<OwmError>().unwrap().cod, 401);
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        let owm = |cod| {
+            anyhow::Error::from(OwmError {
+                cod,
+                message: String::new(),
+            })
+        };
+        assert!(is_transient_error(&owm(500)));
+        assert!(is_transient_error(&owm(503)));
+        assert!(!is_transient_error(&owm(401)));
+        assert!(!is_transient_error(&owm(404)));
+        assert!(!is_transient_error(&owm(429)));
+
+        // Timeouts and other transport errors are retried, broken JSON is not
+        assert!(is_transient_error(&anyhow::anyhow!("connection timed out")));
+        assert!(!is_transient_error(&parse_weather("<html>").unwrap_err()));
+    }
+
+    #[test]
+    fn test_parse_weather_defaults() {
+        // Without `sys` the location and sun times fall back to their defaults,
+        // an empty `weather` list is accepted and handled by the caller
+        let body = r#"{
+            "weather":[],
+            "main":{"temp":-3.5,"humidity":90},
+            "wind":{"speed":0.5},
+            "name":"Station"
+        }"#;
+        let weather = parse_weather(body).unwrap();
+        assert!(weather.weather.is_empty());
+        assert_eq!(weather.sys.country, "");
+        assert_eq!(weather.sys.sunset, 0);
+    }
+
+    #[test]
+    fn test_parse_weather_missing_wind() {
+        // Station responses without `wind`, `clouds` or `main.humidity` are still shown
+        let body = BERLIN
+            .replace(r#""wind":{"speed":4.12,"deg":250},"#, "")
+            .replace(r#""clouds":{"all":75},"#, "")
+            .replace(r#""pressure":1021,"humidity":72"#, r#""pressure":1021"#);
+        let weather = parse_weather(&body).unwrap();
+        assert!(weather.wind.is_none());
+        assert!(weather.clouds.is_none());
+        assert_eq!(weather.main.humidity, None);
+        assert_eq!(weather.main.temp, 14.62);
+
+        // The published JSON leaves the missing values out instead of sending null
+        let json = serde_json::to_string(&weather).unwrap();
+        assert!(!json.contains("wind"));
+        // "clouds" alone would also match the description "broken clouds"
+        assert!(!json.contains(r#""clouds""#));
+        assert!(!json.contains("humidity"));
+    }
+
+    #[test]
+    fn test_parse_weather_missing_temp() {
+        // Without a temperature there is nothing to show, the parse error is not retried
+        let body = BERLIN.replace(r#""temp":14.62,"#, "");
+        let error = parse_weather(&body).unwrap_err();
+        assert!(error.downcast_ref::<serde_json::Error>().is_some());
+    }
+
+    #[test]
+    fn test_speed_unit() {
+        assert_eq!(speed_unit("imperial"), "mph");
+        assert_eq!(speed_unit("metric"), "m/s");
+        assert_eq!(speed_unit("standard"), "m/s");
+    }
+}
@@ -0,0 +1,251 @@
+// web_config.rs
+use crate::secrets::OpenWeatherConfig;
+
+/// A change of the runtime configuration, `None` keeps the current value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigUpdate {
+    pub city: Option<String>,
+    pub units: Option<String>,
+}
+
+/// Decodes an `application/x-www-form-urlencoded` body into key/value pairs
+pub fn parse_form(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (urldecode(key), urldecode(value))
+        })
+        .collect()
+}
+
+/// Returns the value of the first pair named `key`
+pub fn form_value<'a>(pairs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Reverses the form encoding: `+` is a space, `%XX` a byte; invalid escapes are kept as they are
+fn urldecode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = input
+                    .get(i + 1..i + 3)
+                    .map(|hex| u8::from_str_radix(hex, 16));
+                if let Some(Ok(byte)) = hex {
+                    decoded.push(byte);
+                    i += 2;
+                } else {
+                    decoded.push(b'%');
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Compares the token of a request with the configured one.
+/// Takes the same time for every wrong token of the right length, an empty token never matches.
+pub fn token_matches(expected: &str, given: &str) -> bool {
+    if expected.is_empty() || expected.len() != given.len() {
+        return false;
+    }
+    expected
+        .bytes()
+        .zip(given.bytes())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// Extracts the `city` and `units` fields of a submitted form, blank fields are ignored
+pub fn parse_update(pairs: &[(String, String)]) -> ConfigUpdate {
+    let field = |key| {
+        form_value(pairs, key)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    ConfigUpdate {
+        city: field("city"),
+        units: field("units"),
+    }
+}
+
+/// Applies an update to the configuration.
+/// The configuration stays unchanged if the result would not be valid.
+pub fn apply_update(config: &mut OpenWeatherConfig, update: &ConfigUpdate) -> anyhow::Result<()> {
+    let mut updated = config.clone();
+    if let Some(city) = &update.city {
+        updated.set_home_city(city);
+    }
+    if let Some(units) = &update.units {
+        updated.units = Some(units.clone());
+    }
+    updated.validate()?;
+    *config = updated;
+    Ok(())
+}
+
+/// Escapes text for use in HTML content and attribute values
+fn html_escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Renders the page asking for the token
+/// The token is posted in the form body, a token in the URL would end up in logs and the
+/// browser history.
+pub fn render_login() -> String {
+    "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+     <meta name=\"viewport\" content=\"width=device-width\"><title>Weather station</title></head>\
+     <body><h1>Weather station</h1>\
+     <form method=\"post\" action=\"/config\">\
+     <p><label>Token <input type=\"password\" name=\"token\"></label></p>\
+     <p><button>Open</button></p></form></body></html>"
+        .to_string()
+}
+
+/// Renders the configuration page with the current values and an optional status message
+pub fn render_form(config: &OpenWeatherConfig, token: &str, message: Option<&str>) -> String {
+    let units = ["metric", "imperial", "standard"]
+        .iter()
+        .map(|unit| {
+            let selected = if *unit == config.units() {
+                " selected"
+            } else {
+                ""
+            };
+            format!("<option{}>{}</option>", selected, unit)
+        })
+        .collect::<String>();
+    let message = message
+        .map(|message| format!("<p>{}</p>", html_escape(message)))
+        .unwrap_or_default();
+    let coordinates_note = if config.coordinates().is_some() {
+        "<p>lat/lon are set in secrets.toml and take precedence over the city.</p>"
+    } else {
+        ""
+    };
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width\"><title>Weather station</title></head>\
+         <body><h1>Weather station</h1>{}{}\
+         <form method=\"post\" action=\"/config\">\
+         <input type=\"hidden\" name=\"token\" value=\"{}\">\
+         <p><label>City <input name=\"city\" value=\"{}\"></label></p>\
+         <p><label>Units <select name=\"units\">{}</select></label></p>\
+         <p><button>Save</button></p></form></body></html>",
+        message,
+        coordinates_note,
+        html_escape(token),
+        html_escape(config.home_city()),
+        units
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> OpenWeatherConfig {
+        OpenWeatherConfig {
+            api_key: "key".to_string(),
+            city: "Berlin".to_string(),
+            cities: Vec::new(),
+            cycle_secs: 10,
+            units: None,
+            lang: None,
+            lat: None,
+            lon: None,
+            http_timeout_secs: 30,
+        }
+    }
+
+    #[test]
+    fn test_parse_form() {
+        let pairs = parse_form("token=a%2Bb&city=Frankfurt+am+Main&units=&flag");
+        assert_eq!(form_value(&pairs, "token"), Some("a+b"));
+        assert_eq!(form_value(&pairs, "city"), Some("Frankfurt am Main"));
+        assert_eq!(form_value(&pairs, "units"), Some(""));
+        assert_eq!(form_value(&pairs, "flag"), Some(""));
+        assert_eq!(form_value(&pairs, "lang"), None);
+
+        // UTF-8 and invalid escapes
+        let pairs = parse_form("city=M%C3%BCnchen%2");
+        assert_eq!(form_value(&pairs, "city"), Some("München%2"));
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("secret", "secret"));
+        assert!(!token_matches("secret", "secreT"));
+        assert!(!token_matches("secret", "secret2"));
+        assert!(!token_matches("", ""));
+    }
+
+    #[test]
+    fn test_apply_update() {
+        let mut config = config();
+        let update = parse_update(&parse_form("city=Springfield%2CUS&units=imperial"));
+        apply_update(&mut config, &update).unwrap();
+        assert_eq!(config.city, "Springfield,US");
+        assert_eq!(config.units(), "imperial");
+
+        // Blank fields keep the current value
+        apply_update(&mut config, &parse_update(&parse_form("city=+&units="))).unwrap();
+        assert_eq!(config.city, "Springfield,US");
+
+        // Invalid units are rejected without touching the configuration
+        let update = parse_update(&parse_form("city=Paris&units=kelvin"));
+        assert!(apply_update(&mut config, &update).is_err());
+        assert_eq!(config.city, "Springfield,US");
+        assert_eq!(config.units(), "imperial");
+
+        // With a list of cities the first one is replaced
+        config.cities = vec!["Paris".to_string(), "Rome".to_string()];
+        apply_update(&mut config, &parse_update(&parse_form("city=Oslo"))).unwrap();
+        assert_eq!(config.city_list(), ["Oslo", "Rome"]);
+        assert_eq!(config.home_city(), "Oslo");
+    }
+
+    #[test]
+    fn test_render_login_posts_the_token() {
+        let html = render_login();
+        assert!(html.contains("method=\"post\" action=\"/config\""));
+        assert!(html.contains("type=\"password\" name=\"token\""));
+        // Only the token is asked for, the values need it first
+        assert!(!html.contains("name=\"city\""));
+    }
+
+    #[test]
+    fn test_render_form_escapes() {
+        let mut config = config();
+        config.city = "<b>\"x\"".to_string();
+        let html = render_form(&config, "t&k", Some("Saved"));
+        assert!(html.contains("value=\"&lt;b&gt;&quot;x&quot;\""));
+        assert!(html.contains("value=\"t&amp;k\""));
+        assert!(html.contains("<p>Saved</p>"));
+        assert!(html.contains("<option selected>metric</option>"));
+    }
+}